@@ -0,0 +1,44 @@
+use crate::waveforms::{MONO, SAMPLE_RATE, STEREO};
+
+/// Default number of frames rendered per block
+pub const DEFAULT_BLOCK_SIZE: usize = 512;
+
+/// Sample rates the engine can be switched between
+pub const SAMPLE_RATES: [u32; 3] = [44100, 48000, 96000];
+
+/// Engine-wide audio settings shared by the oscillators, envelopes, effects,
+/// the offline renderer and the output device
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioConfig {
+    pub sample_rate: u32,    // Samples per second per channel
+    pub block_size: usize,   // Frames rendered per block
+    pub channels: u16,       // Output channels (1 = mono, 2 = stereo)
+}
+
+impl AudioConfig {
+    pub fn new(sample_rate: u32, block_size: usize, channels: u16) -> Self {
+        Self {
+            sample_rate: sample_rate.max(1),
+            block_size: block_size.max(1),
+            channels: channels.clamp(MONO, STEREO),
+        }
+    }
+
+    /// Convert interleaved stereo frames from the mixer to the configured channel count
+    pub fn output_from_stereo(&self, stereo: Vec<f32>) -> Vec<f32> {
+        if self.channels == STEREO {
+            return stereo;
+        }
+
+        // Fold down to mono
+        stereo.chunks_exact(2)
+            .map(|frame| (frame[0] + frame[1]) * 0.5)
+            .collect()
+    }
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self::new(SAMPLE_RATE as u32, DEFAULT_BLOCK_SIZE, STEREO)
+    }
+}
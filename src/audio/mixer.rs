@@ -1,149 +1,380 @@
-use rodio::{Source, Sink};
-use std::time::Duration;
-use crate::state::{State, Track, MasterTrack, RecordedNote};
-use crate::waveforms::{Waveform, AMPLITUDE};
-use crate::waveforms::adsr_envelope::ADSREnvelope;
+use crate::audio::unison::UnisonVoice;
+use crate::audio::voice::{VoiceGenerator, VoiceSource};
+use crate::effects::AudioEffect;
+use crate::modulation::{EffectSlot, Modulation};
+use crate::state::{State, Track, MasterTrack};
+use crate::waveforms::{Waveform, AMPLITUDE, STEREO};
+use crate::waveforms::adsr_envelope::{ADSREnvelope, EnvelopeGate};
 use crate::waveforms::sine_wave::SineWave;
 use crate::waveforms::square_wave::SquareWave;
 use crate::waveforms::triangle_wave::TriangleWave;
 use crate::waveforms::sawtooth_wave::SawtoothWave;
-use crate::effects::AudioEffect;
+use crate::waveforms::noise::NoiseGenerator;
+use crate::waveforms::drum_voice::{DrumSound, DrumVoice};
+use crate::waveforms::fm::FmVoice;
+use crate::waveforms::wavetable::WavetableOscillator;
 use crate::music_theory::note::Note;
 
+/// Frames rendered between modulation updates, under a millisecond at common sample rates
+const MODULATION_BLOCK_SIZE: usize = 32;
+
+/// Most parameters of one effect that modulation can move at once
+const MAX_MODULATED_PARAMETERS: usize = 32;
+
 /// Multi-track audio mixer that handles playback of all tracks
 pub struct MultiTrackMixer {
     sample_rate: u32,
+    // Per-block buffers, kept between blocks so rendering doesn't allocate
+    track_buses: Vec<Vec<f32>>,
+    render_order: Vec<usize>,
 }
 
 impl MultiTrackMixer {
     pub fn new(sample_rate: u32) -> Self {
-        Self { sample_rate }
+        Self {
+            sample_rate,
+            track_buses: Vec::new(),
+            render_order: Vec::new(),
+        }
     }
     
-    /// Play a note on a specific track
-    pub fn play_note_on_track(
-        &self,
-        track: &Track,
-        note: Note,
-        sink: &mut Sink,
-    ) {
-        let base_frequency = note.frequency(track.octave);
+    /// Render at a new sample rate from the next block on
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+    }
+    
+    /// Start a note on a specific track, allocating one of the track's voices
+    ///
+    /// The note sustains until the returned gate is closed.
+    pub fn note_on(&self, track: &mut Track, note: Note) -> EnvelopeGate {
+        let octave = track.octave;
+        let source = self.create_voice_source(track, note, octave);
+        self.start_voice(track, note, octave, source)
+    }
+    
+    /// Start a note in `octave` on a specific track that releases by itself after `duration` seconds
+    ///
+    /// Recorded notes pass the octave they were played in, which may differ from the track's current one.
+    pub fn note_on_for(&self, track: &mut Track, note: Note, octave: i32, duration: f32) {
+        let mut source = self.create_voice_source(track, note, octave);
+        source.release_after(duration);
+        self.start_voice(track, note, octave, source);
+    }
+    
+    /// Hand a voice to the track's pool and retrigger the track's envelope modulation sources
+    ///
+    /// Legato notes leave the modulation envelopes running, like the amplitude envelope.
+    fn start_voice(&self, track: &mut Track, note: Note, octave: i32, source: VoiceSource) -> EnvelopeGate {
+        let legato = track.voices.holds_legato_voice();
+        let gate = track.voices.note_on(note, octave, source);
+        if legato {
+            track.modulation.continue_with(gate.clone());
+        } else {
+            track.modulation.trigger(track.amp_envelope(), gate.clone(), self.sample_rate);
+        }
+        gate
+    }
+    
+    /// Build the enveloped oscillator for a note in `octave` on a specific track
+    pub fn create_voice_source(&self, track: &Track, note: Note, octave: i32) -> VoiceSource {
+        let base_frequency = note.frequency(octave);
         
-        // Create waveform based on track settings
-        let synth = match track.waveform {
-            Waveform::SINE => {
-                let sine_wave = SineWave::new(base_frequency);
-                let adsr_envelope = ADSREnvelope::new(
-                    sine_wave,
-                    track.attack as f32 / 99.0 * 2.0,
-                    track.decay as f32 / 99.0 * 2.0,
-                    track.sustain as f32 / 99.0,
-                    track.release as f32 / 99.0 * 2.0
-                );
-                Box::new(adsr_envelope) as Box<dyn Source<Item=f32> + 'static + Send>
-            },
-            Waveform::SQUARE => {
-                let square_wave = SquareWave::new(base_frequency);
-                let adsr_envelope = ADSREnvelope::new(
-                    square_wave,
-                    track.attack as f32 / 99.0 * 2.0,
-                    track.decay as f32 / 99.0 * 2.0,
-                    track.sustain as f32 / 99.0,
-                    track.release as f32 / 99.0 * 2.0
-                );
-                Box::new(adsr_envelope) as Box<dyn Source<Item=f32> + 'static + Send>
-            },
-            Waveform::TRIANGLE => {
-                let triangle_wave = TriangleWave::new(base_frequency);
-                let adsr_envelope = ADSREnvelope::new(
-                    triangle_wave,
-                    track.attack as f32 / 99.0 * 2.0,
-                    track.decay as f32 / 99.0 * 2.0,
-                    track.sustain as f32 / 99.0,
-                    track.release as f32 / 99.0 * 2.0
-                );
-                Box::new(adsr_envelope) as Box<dyn Source<Item=f32> + 'static + Send>
-            },
-            Waveform::SAWTOOTH => {
-                let sawtooth_wave = SawtoothWave::new(base_frequency);
-                let adsr_envelope = ADSREnvelope::new(
-                    sawtooth_wave,
-                    track.attack as f32 / 99.0 * 2.0,
-                    track.decay as f32 / 99.0 * 2.0,
-                    track.sustain as f32 / 99.0,
-                    track.release as f32 / 99.0 * 2.0
-                );
-                Box::new(adsr_envelope) as Box<dyn Source<Item=f32> + 'static + Send>
-            },
+        // Loaded samples take the place of the oscillator
+        if let Some(player) = track.sample_map.as_ref().and_then(|map| map.player(note, octave, base_frequency)) {
+            return ADSREnvelope::with_shape(Box::new(player), track.amp_envelope());
+        }
+        
+        // Drum-mode tracks map each note to a drum of the kit
+        if track.drum_mode {
+            let drum = DrumVoice::new(DrumSound::for_note(note), base_frequency, self.sample_rate);
+            return ADSREnvelope::with_shape(Box::new(drum), track.amp_envelope());
+        }
+        
+        // Create waveform based on track settings, stacked when unison is on
+        let gate = EnvelopeGate::new(); // FM operator envelopes release with the note
+        let oscillator = if track.unison.is_active() {
+            let seed = (track.voices.notes_started() as u32).wrapping_mul(0x9E37_79B9) ^ base_frequency.to_bits();
+            let stack = UnisonVoice::new(&track.unison, base_frequency, seed, |layer, frequency| {
+                self.create_oscillator(track, frequency, &gate, layer as u32)
+            });
+            Box::new(stack) as Box<dyn VoiceGenerator>
+        } else {
+            self.create_oscillator(track, base_frequency, &gate, 0)
         };
         
-        // Apply track volume and pan
-        let source_with_volume = synth.amplify(AMPLITUDE * track.volume);
+        ADSREnvelope::with_gate(oscillator, track.amp_envelope(), gate)
+    }
+    
+    /// Build one oscillator of the track's waveform, `seed` keeps the noise of unison layers apart
+    fn create_oscillator(&self, track: &Track, frequency: f32, gate: &EnvelopeGate, seed: u32) -> Box<dyn VoiceGenerator> {
+        match track.waveform {
+            Waveform::SINE => Box::new(SineWave::new(frequency, self.sample_rate)),
+            Waveform::SQUARE if track.band_limited => Box::new(SquareWave::new(frequency, self.sample_rate)),
+            Waveform::SQUARE => Box::new(SquareWave::new_raw(frequency, self.sample_rate)),
+            Waveform::TRIANGLE => Box::new(TriangleWave::new(frequency, self.sample_rate)),
+            Waveform::SAWTOOTH if track.band_limited => Box::new(SawtoothWave::new(frequency, self.sample_rate)),
+            Waveform::SAWTOOTH => Box::new(SawtoothWave::new_raw(frequency, self.sample_rate)),
+            Waveform::NOISE(color) => Box::new(NoiseGenerator::new(color, frequency.to_bits().wrapping_add(seed), self.sample_rate)),
+            Waveform::FM => Box::new(FmVoice::new(track.fm_patch, frequency, self.sample_rate, gate.clone())),
+            Waveform::WAVETABLE => Box::new(WavetableOscillator::new(track.wavetable.clone(), track.current_wavetable_position, frequency, self.sample_rate)),
+        }
+    }
+    
+    /// Render all voices of a track into its stereo bus, applying the track filter, volume, pan and effects
+    ///
+    /// `buffer` holds interleaved left/right frames. The track's modulation is updated
+    /// every MODULATION_BLOCK_SIZE frames. `key` is the rendered bus of the track's sidechain
+    /// source, covering the same frames as `buffer`; without one the compressor follows the track itself.
+    pub fn render_track(&self, track: &mut Track, buffer: &mut [f32], key: Option<&[f32]>) {
+        let block_size = MODULATION_BLOCK_SIZE * STEREO as usize;
+        for (index, block) in buffer.chunks_mut(block_size).enumerate() {
+            let start = index * block_size;
+            let key_block = key.map(|key| &key[start..start + block.len()]);
+            self.render_track_block(track, block, key_block);
+        }
+    }
+    
+    /// Render one modulation block of a track
+    fn render_track_block(&self, track: &mut Track, buffer: &mut [f32], key: Option<&[f32]>) {
+        let frames = buffer.len() / 2;
+        let modulation = track.modulation.advance(frames, self.sample_rate);
+        
+        // Voices render straight into the track's stereo bus
+        buffer.fill(0.0);
+        track.voices.set_pitch_offset(modulation.pitch);
+        track.current_wavetable_position = (track.wavetable_position + modulation.wavetable_position).clamp(0.0, 1.0);
+        if track.waveform == Waveform::WAVETABLE {
+            track.voices.set_wavetable_position(track.current_wavetable_position);
+        }
+        track.voices.render(buffer);
+        
+        // Filter the oscillators before the amp stage
+        if track.filter_enabled {
+            let cutoff = track.filter.cutoff();
+            if modulation.cutoff != 0.0 {
+                let value = track.filter.cutoff_normalized() + modulation.cutoff;
+                track.filter.set_cutoff_normalized(value);
+            }
+            
+            track.filter.process_block(buffer);
+            
+            if modulation.cutoff != 0.0 {
+                track.filter.set_cutoff(cutoff);
+            }
+        }
+        
+        // Apply track volume and pan, balancing stereo voices between the channels
+        let gain = AMPLITUDE * (track.volume + modulation.volume).clamp(0.0, 1.0);
+        let pan = (track.pan + modulation.pan).clamp(-1.0, 1.0);
+        let (left_gain, right_gain) = apply_pan(gain, pan);
+        for frame in buffer.chunks_exact_mut(2) {
+            frame[0] *= left_gain;
+            frame[1] *= right_gain;
+        }
         
         // Apply track-specific effects
-        let source_with_effects = self.apply_track_effects(source_with_volume, track);
+        self.apply_track_effects(track, &modulation, buffer, key);
+    }
+    
+    /// Apply effects to a track's bus in series: Gate -> Equalizer -> Compressor -> Distortion -> Bitcrusher -> Waveshaper -> Delay -> Reverb -> Flanger -> Chorus -> Phaser -> Tremolo -> Limiter
+    ///
+    /// The effects live on the track and see every block, including silent ones,
+    /// so delay and reverb tails carry on after the voices that fed them have ended.
+    /// With a `key` block the compressor ducks under it instead of following the track.
+    fn apply_track_effects(&self, track: &mut Track, modulation: &Modulation, buffer: &mut [f32], key: Option<&[f32]>) {
+        for chained in track.effects.iter_mut().filter(|chained| chained.enabled) {
+            with_modulated_parameters(chained.effect.as_mut(), modulation, chained.slot, |effect| process_keyed(effect, buffer, key));
+        }
+    }
+    
+    /// Render the next block of the full mix: every track's voices summed and sent through the master
+    ///
+    /// `output` holds interleaved left/right frames. Sidechain sources are rendered before the
+    /// tracks they key, so each keyed compressor hears the same block of its source.
+    pub fn render(&mut self, tracks: &mut [Track], master_track: &mut MasterTrack, output: &mut [f32]) {
+        output.fill(0.0);
+        fill_sidechain_order(tracks, &mut self.render_order);
         
-        // Add to sink
-        sink.append(source_with_effects);
-    }
-    
-    /// Apply effects to a track's audio source
-    fn apply_track_effects<S>(&self, source: S, track: &Track) -> Box<dyn Source<Item=f32> + Send>
-    where
-        S: Source<Item=f32> + Send + 'static,
-    {
-        // For now, just return the source as-is since we need to implement effects processing
-        // TODO: Implement proper track-specific effects processing
-        if track.delay_enabled || track.reverb_enabled || track.flanger_enabled {
-            // Effects are enabled but we need to implement the processor
-            Box::new(source)
-        } else {
-            Box::new(source)
+        // An empty bus is one not rendered yet in this block
+        self.track_buses.resize_with(tracks.len(), Vec::new);
+        for bus in &mut self.track_buses {
+            bus.clear();
+        }
+        
+        for position in 0..self.render_order.len() {
+            let index = self.render_order[position];
+            let mut track_bus = std::mem::take(&mut self.track_buses[index]);
+            track_bus.resize(output.len(), 0.0);
+            
+            let key = tracks[index].sidechain_source
+                .and_then(|source| self.track_buses.get(source))
+                .filter(|bus| !bus.is_empty())
+                .map(|bus| bus.as_slice());
+            self.render_track(&mut tracks[index], &mut track_bus, key);
+            
+            for (out, sample) in output.iter_mut().zip(&track_bus) {
+                *out += sample;
+            }
+            self.track_buses[index] = track_bus;
         }
+        
+        self.apply_master_effects(master_track, output);
     }
     
-    /// Play back recorded notes from multiple tracks simultaneously
+    /// Trigger recorded notes from multiple tracks that start at the given playback time
     pub fn play_multi_track_sequence(
         &self,
-        state: &State,
-        sink: &mut Sink,
+        state: &mut State,
         playback_time: f32,
     ) {
         let playing_tracks = state.playing_tracks();
         
         for track_id in playing_tracks {
-            let track = &state.tracks[track_id];
-            self.play_track_at_time(track, sink, playback_time);
+            let track = &mut state.tracks[track_id];
+            self.play_track_at_time(track, playback_time);
         }
     }
     
-    /// Play a specific track's notes at a given time
-    fn play_track_at_time(&self, track: &Track, sink: &mut Sink, playback_time: f32) {
+    /// Trigger a specific track's notes that start at a given time
+    fn play_track_at_time(&self, track: &mut Track, playback_time: f32) {
         let frame_time_threshold = 0.05; // 50ms threshold
         
-        for recorded_note in &track.recorded_notes {
-            let note_start = recorded_note.timestamp;
-            
-            // Check if this note should start playing now
-            if playback_time >= note_start && playback_time < note_start + frame_time_threshold {
-                self.play_note_on_track(track, recorded_note.note, sink);
+        let starting_notes: Vec<(Note, i32, f32)> = track.recorded_notes.iter()
+            .filter(|recorded_note| {
+                // Check if this note should start playing now
+                let note_start = recorded_note.timestamp;
+                playback_time >= note_start && playback_time < note_start + frame_time_threshold
+            })
+            .map(|recorded_note| (recorded_note.note, recorded_note.octave, recorded_note.duration))
+            .collect();
+        
+        for (note, octave, duration) in starting_notes {
+            self.note_on_for(track, note, octave, duration);
+        }
+    }
+    
+    /// Run the summed tracks through the master bus: Gate -> Equalizer -> Compressor -> Distortion -> Bitcrusher -> Waveshaper -> Delay -> Reverb -> Flanger -> Chorus -> Phaser -> Tremolo -> Limiter -> volume -> output limiter
+    ///
+    /// The output limiter always runs last so the sum of all tracks can never clip the output.
+    pub fn apply_master_effects(&self, master_track: &mut MasterTrack, buffer: &mut [f32]) {
+        master_track.effects.process_block(buffer);
+        
+        // Apply master volume, then limit
+        for frame in buffer.chunks_exact_mut(2) {
+            let (left, right) = master_track.limiter.process_frame(frame[0] * master_track.volume, frame[1] * master_track.volume);
+            frame[0] = left;
+            frame[1] = right;
+        }
+    }
+}
+
+/// Run a block through an effect, sidechain effects detecting the level of `key` when there is one
+fn process_keyed(effect: &mut dyn AudioEffect, buffer: &mut [f32], key: Option<&[f32]>) {
+    if let Some(key) = key {
+        if let Some(sidechain) = effect.as_sidechain_mut() {
+            sidechain.process_block_keyed(buffer, key);
+            return;
+        }
+    }
+    effect.process_block(buffer);
+}
+
+/// Apply an effect's modulated parameters for the duration of `process`, then restore its own settings
+///
+/// The settings are saved in the effect's own units rather than normalized, so values outside
+/// the modulation range survive and nothing drifts from block to block.
+fn with_modulated_parameters(effect: &mut dyn AudioEffect, modulation: &Modulation, slot: EffectSlot, process: impl FnOnce(&mut dyn AudioEffect)) {
+    let mut base_values = [(0, 0.0); MAX_MODULATED_PARAMETERS];
+    let mut count = 0;
+    for (parameter, offset) in modulation.effect_offsets(slot).take(MAX_MODULATED_PARAMETERS) {
+        if let (Some(base), Some(value)) = (effect.parameter_value(parameter), effect.parameter(parameter)) {
+            effect.set_parameter(parameter, value + offset);
+            base_values[count] = (parameter, base);
+            count += 1;
+        }
+    }
+    
+    process(effect);
+    
+    for &(parameter, base) in &base_values[..count] {
+        effect.set_parameter_value(parameter, base);
+    }
+}
+
+/// Order to render tracks in so every sidechain source comes before the tracks it keys
+///
+/// Tracks caught in a loop of sidechains are rendered last, in track order; the first of
+/// them to render has no key yet and follows its own signal.
+pub fn sidechain_order(tracks: &[Track]) -> Vec<usize> {
+    let mut order = Vec::with_capacity(tracks.len());
+    fill_sidechain_order(tracks, &mut order);
+    order
+}
+
+/// Write the sidechain render order into `order`, reusing its storage
+fn fill_sidechain_order(tracks: &[Track], order: &mut Vec<usize>) {
+    order.clear();
+    
+    // Each pass adds the tracks whose source was rendered in an earlier pass
+    loop {
+        let rendered = order.len();
+        for index in 0..tracks.len() {
+            if order.contains(&index) {
+                continue;
+            }
+            let ready = match tracks[index].sidechain_source {
+                Some(source) => source == index || source >= tracks.len() || order[..rendered].contains(&source),
+                None => true,
+            };
+            if ready {
+                order.push(index);
             }
         }
+        if order.len() == rendered {
+            break;
+        }
     }
     
-    /// Calculate final master mix with master effects
-    pub fn apply_master_effects(&self, _master_track: &MasterTrack, sample: f32) -> f32 {
-        // Apply master volume
-        sample * _master_track.volume
-        // TODO: Apply master effects (delay, reverb, flanger)
+    for index in 0..tracks.len() {
+        if !order.contains(&index) {
+            order.push(index);
+        }
     }
 }
 
-/// Panning utility function
+/// Constant-power panning utility function
 pub fn apply_pan(sample: f32, pan: f32) -> (f32, f32) {
     // Pan from -1.0 (left) to 1.0 (right)
     let left_gain = ((1.0 - pan) / 2.0).sqrt();
     let right_gain = ((1.0 + pan) / 2.0).sqrt();
     
     (sample * left_gain, sample * right_gain)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracks_keyed_by(sources: &[Option<usize>]) -> Vec<Track> {
+        sources.iter().enumerate().map(|(index, &source)| {
+            let mut track = Track::new(index, format!("Track {}", index + 1), 48000);
+            track.sidechain_source = source;
+            track
+        }).collect()
+    }
+
+    #[test]
+    fn sidechain_sources_render_before_the_tracks_they_key() {
+        let tracks = tracks_keyed_by(&[Some(2), None, Some(1), Some(3), Some(9)]);
+
+        // Keys from the track itself or from a missing track do not hold a track back
+        assert_eq!(sidechain_order(&tracks), vec![1, 3, 4, 2, 0]);
+    }
+
+    #[test]
+    fn sidechain_cycles_render_last_in_track_order() {
+        let tracks = tracks_keyed_by(&[Some(1), Some(2), Some(0), None, Some(0)]);
+
+        assert_eq!(sidechain_order(&tracks), vec![3, 0, 1, 2, 4]);
+    }
+}
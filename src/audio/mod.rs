@@ -1,3 +1,10 @@
+pub mod config;
 pub mod mixer;
+pub mod offline;
+pub mod unison;
+pub mod voice;
+pub mod wav;
 
-pub use mixer::MultiTrackMixer;
\ No newline at end of file
+pub use config::AudioConfig;
+pub use mixer::MultiTrackMixer;
+pub use offline::OfflineRenderer;
\ No newline at end of file
@@ -0,0 +1,120 @@
+use crate::audio::config::AudioConfig;
+use crate::audio::mixer::{sidechain_order, MultiTrackMixer};
+use crate::audio::wav::{write_wav_file, WavFormat};
+use crate::effects::AudioEffect;
+use crate::state::{MasterTrack, RecordedNote, State, Track};
+use crate::waveforms::STEREO;
+
+/// Seconds rendered after the last note so release stages and effect tails can ring out
+const DEFAULT_TAIL_SECONDS: f32 = 2.0;
+
+/// Renders the recorded tracks of a project to a sample buffer without an audio device
+pub struct OfflineRenderer {
+    config: AudioConfig,
+    tail_seconds: f32,
+}
+
+impl OfflineRenderer {
+    pub fn new(config: AudioConfig) -> Self {
+        Self {
+            config,
+            tail_seconds: DEFAULT_TAIL_SECONDS,
+        }
+    }
+
+    /// Render one pass of every recorded track through the track and master effects
+    ///
+    /// # Returns
+    /// Interleaved samples in the configured channel count, covering the longest track loop plus the release tail.
+    pub fn render(&self, state: &State) -> Vec<f32> {
+        let mixer = MultiTrackMixer::new(self.config.sample_rate);
+
+        // All loops restart together, so one pass lasts as long as the longest track
+        let loop_duration = state.tracks.iter()
+            .flat_map(|track| track.recorded_notes.iter())
+            .map(|note| note.timestamp + note.duration)
+            .fold(0.0f32, f32::max);
+
+        if loop_duration <= 0.0 {
+            return Vec::new();
+        }
+
+        let total_frames = ((loop_duration + self.tail_seconds) * self.config.sample_rate as f32) as usize;
+        let mut output = vec![0.0; total_frames * STEREO as usize];
+
+        // Sidechain sources go first so the tracks they key can duck under them
+        let mut track_buses: Vec<Option<Vec<f32>>> = vec![None; state.tracks.len()];
+        for index in sidechain_order(&state.tracks) {
+            let track = &state.tracks[index];
+            if track.recorded_notes.is_empty() {
+                continue;
+            }
+
+            let key = track.sidechain_source
+                .and_then(|source| track_buses.get(source))
+                .and_then(|bus| bus.as_deref());
+            let track_bus = self.render_track(&mixer, track, total_frames, key);
+
+            for (out, sample) in output.iter_mut().zip(&track_bus) {
+                *out += sample;
+            }
+            track_buses[index] = Some(track_bus);
+        }
+
+        self.apply_master_bus(&mixer, &state.master_track, &mut output);
+
+        self.config.output_from_stereo(output)
+    }
+
+    /// Render the project and write it to a WAV file
+    pub fn render_to_wav(&self, state: &State, file_path: &str, format: WavFormat) -> Result<(), Box<dyn std::error::Error>> {
+        let samples = self.render(state);
+        if samples.is_empty() {
+            return Err("No recorded notes to render".into());
+        }
+
+        write_wav_file(file_path, &samples, self.config.sample_rate, self.config.channels, format)?;
+        Ok(())
+    }
+
+    /// Play all notes of a track through the mixer, with the track's filter, modulation, pan and effects
+    ///
+    /// `key` is the rendered bus of the track's sidechain source, if it has one with notes.
+    fn render_track(&self, mixer: &MultiTrackMixer, track: &Track, total_frames: usize, key: Option<&[f32]>) -> Vec<f32> {
+        let mut track_bus = vec![0.0; total_frames * STEREO as usize];
+
+        // Render a copy so the live voices and effect instances of the track are left untouched
+        let mut track = track.detached_copy();
+        track.set_sample_rate(self.config.sample_rate);
+        track.reset_processing();
+
+        let mut recorded_notes: Vec<RecordedNote> = track.recorded_notes.clone();
+        recorded_notes.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+
+        // Render up to each note start, then trigger the note
+        let mut position = 0;
+        for recorded_note in recorded_notes {
+            let start = ((recorded_note.timestamp.max(0.0) * self.config.sample_rate as f32) as usize).min(total_frames);
+            mixer.render_track(&mut track, &mut track_bus[position * 2..start * 2], key.map(|key| &key[position * 2..start * 2]));
+            position = start;
+
+            if start < total_frames {
+                mixer.note_on_for(&mut track, recorded_note.note, recorded_note.octave, recorded_note.duration);
+            }
+        }
+        mixer.render_track(&mut track, &mut track_bus[position * 2..], key.map(|key| &key[position * 2..]));
+
+        track_bus
+    }
+
+    /// Apply the master effects and master volume to the summed tracks
+    fn apply_master_bus(&self, mixer: &MultiTrackMixer, master_track: &MasterTrack, output: &mut [f32]) {
+        // The master is cloned so the live effect instances in the state are left untouched
+        let mut master_track = master_track.clone();
+        master_track.set_sample_rate(self.config.sample_rate);
+        master_track.effects.reset();
+        master_track.limiter.reset();
+
+        mixer.apply_master_effects(&mut master_track, output);
+    }
+}
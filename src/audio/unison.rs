@@ -0,0 +1,148 @@
+use rodio::Source;
+use std::f32::consts::SQRT_2;
+use std::time::Duration;
+use crate::audio::mixer::apply_pan;
+use crate::audio::voice::VoiceGenerator;
+use crate::waveforms::random::Random;
+use crate::waveforms::MONO;
+
+/// Most oscillators a note can stack
+pub const MAX_UNISON_VOICES: usize = 16;
+
+/// Widest detune spread in cents
+pub const MAX_DETUNE_CENTS: f32 = 100.0;
+
+/// Unison settings of a track
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Unison {
+    pub voices: usize,       // Oscillators stacked per note (1 - MAX_UNISON_VOICES)
+    pub detune: f32,         // Cents between the lowest and the highest oscillator
+    pub stereo_spread: f32,  // 0.0 keeps every oscillator centered, 1.0 pans the outer ones hard left and right
+    pub random_phase: bool,  // Start every oscillator at a random phase instead of all at zero
+}
+
+impl Default for Unison {
+    fn default() -> Self {
+        Self {
+            voices: 1,
+            detune: 25.0,
+            stereo_spread: 0.5,
+            random_phase: true,
+        }
+    }
+}
+
+impl Unison {
+    /// Whether notes play more than one oscillator
+    pub fn is_active(&self) -> bool {
+        self.voices > 1
+    }
+
+    /// Detune in cents and pan of one oscillator of the stack, spread evenly from lowest and left to highest and right
+    pub fn layer(&self, index: usize) -> (f32, f32) {
+        if self.voices <= 1 {
+            return (0.0, 0.0);
+        }
+        let position = index as f32 / (self.voices - 1) as f32 * 2.0 - 1.0;
+        (position * self.detune * 0.5, position * self.stereo_spread)
+    }
+}
+
+/// One oscillator of a unison stack
+struct UnisonLayer {
+    generator: Box<dyn VoiceGenerator>,
+    ratio: f32,       // Frequency factor of the layer's detune
+    left_gain: f32,
+    right_gain: f32,
+}
+
+/// Detuned oscillators playing one note, spread across the stereo field
+///
+/// The stack is scaled by 1/√n so that adding oscillators thickens the sound
+/// without making it much louder.
+pub struct UnisonVoice {
+    layers: Vec<UnisonLayer>,
+    gain: f32,
+}
+
+impl UnisonVoice {
+    /// Stack the oscillators that `build` makes from a layer index and a frequency
+    ///
+    /// `seed` picks the random start phases, so every note can start differently.
+    pub fn new(unison: &Unison, frequency: f32, seed: u32, mut build: impl FnMut(usize, f32) -> Box<dyn VoiceGenerator>) -> Self {
+        let mut random = Random::new(seed);
+        let voices = unison.voices.clamp(1, MAX_UNISON_VOICES);
+
+        let layers = (0..voices)
+            .map(|index| {
+                let (cents, pan) = unison.layer(index);
+                let ratio = 2.0f32.powf(cents / 1200.0);
+                let mut generator = build(index, frequency * ratio);
+                if unison.random_phase {
+                    generator.set_phase(random.next_unit());
+                }
+
+                // Centered layers keep unity gain on both channels, like a mono voice
+                let (left_gain, right_gain) = apply_pan(SQRT_2, pan);
+                UnisonLayer { generator, ratio, left_gain, right_gain }
+            })
+            .collect();
+
+        Self {
+            layers,
+            gain: 1.0 / (voices as f32).sqrt(),
+        }
+    }
+}
+
+impl VoiceGenerator for UnisonVoice {
+    fn set_frequency(&mut self, frequency: f32) {
+        for layer in &mut self.layers {
+            layer.generator.set_frequency(frequency * layer.ratio);
+        }
+    }
+
+    fn set_wavetable_position(&mut self, position: f32) {
+        for layer in &mut self.layers {
+            layer.generator.set_wavetable_position(position);
+        }
+    }
+
+    fn next_frame(&mut self) -> Option<(f32, f32)> {
+        let (mut left, mut right) = (0.0, 0.0);
+        for layer in &mut self.layers {
+            let sample = layer.generator.next()?;
+            left += sample * layer.left_gain;
+            right += sample * layer.right_gain;
+        }
+        Some((left * self.gain, right * self.gain))
+    }
+}
+
+/// Implementation of the [Iterator] trait for the [UnisonVoice], the mono sum of both channels
+impl Iterator for UnisonVoice {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        self.next_frame().map(|(left, right)| (left + right) * 0.5)
+    }
+}
+
+/// Implementation of the [Source] trait for the [UnisonVoice]
+impl Source for UnisonVoice {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        MONO
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.layers.first().map_or(0, |layer| layer.generator.sample_rate())
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
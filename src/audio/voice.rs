@@ -0,0 +1,660 @@
+use std::fmt;
+use std::time::Duration;
+use rodio::Source;
+use crate::music_theory::note::Note;
+use crate::waveforms::adsr_envelope::{ADSREnvelope, EnvelopeGate, StereoSource};
+use crate::waveforms::oscillator::Oscillator;
+use crate::waveforms::noise::NoiseGenerator;
+use crate::waveforms::drum_voice::DrumVoice;
+use crate::waveforms::sampler::SamplePlayer;
+use crate::waveforms::fm::FmVoice;
+use crate::waveforms::wavetable::WavetableOscillator;
+
+/// Number of voices a track can play at once unless configured otherwise
+pub const DEFAULT_VOICE_COUNT: usize = 8;
+
+/// Most voices a track can be set to play at once
+pub const MAX_VOICE_COUNT: usize = 32;
+
+/// Longest glide time in seconds
+pub const MAX_GLIDE_TIME: f32 = 2.0;
+
+/// Sound generator of a voice whose pitch can follow modulation while it plays
+pub trait VoiceGenerator: Source<Item = f32> + Send {
+    fn set_frequency(&mut self, frequency: f32);
+
+    /// Move to another wavetable position (0.0 - 1.0), ignored by generators without a table
+    fn set_wavetable_position(&mut self, _position: f32) {}
+
+    /// Set the start phase (0 to 1), ignored by generators without a cycle
+    fn set_phase(&mut self, _phase: f32) {}
+
+    /// Next left/right frame, mono generators play the same sample on both channels
+    fn next_frame(&mut self) -> Option<(f32, f32)> {
+        self.next().map(|sample| (sample, sample))
+    }
+}
+
+impl<T> VoiceGenerator for T
+where
+    T: Oscillator + Source<Item = f32> + Send,
+{
+    fn set_frequency(&mut self, frequency: f32) {
+        Oscillator::set_frequency(self, frequency);
+    }
+
+    fn set_phase(&mut self, phase: f32) {
+        Oscillator::set_phase(self, phase);
+    }
+}
+
+/// Noise has no pitch, so pitch modulation leaves it unchanged
+impl VoiceGenerator for NoiseGenerator {
+    fn set_frequency(&mut self, _frequency: f32) {}
+}
+
+/// Pitch modulation retunes a drum relative to the note that started it
+impl VoiceGenerator for DrumVoice {
+    fn set_frequency(&mut self, frequency: f32) {
+        DrumVoice::set_frequency(self, frequency);
+    }
+}
+
+/// Pitch modulation changes the playback speed of a sample
+impl VoiceGenerator for SamplePlayer {
+    fn set_frequency(&mut self, frequency: f32) {
+        SamplePlayer::set_frequency(self, frequency);
+    }
+}
+
+/// Pitch modulation moves every operator, keeping their ratios
+impl VoiceGenerator for FmVoice {
+    fn set_frequency(&mut self, frequency: f32) {
+        FmVoice::set_frequency(self, frequency);
+    }
+
+    fn set_phase(&mut self, phase: f32) {
+        FmVoice::set_phase(self, phase);
+    }
+}
+
+/// Wavetable voices follow the track's modulated table position
+impl VoiceGenerator for WavetableOscillator {
+    fn set_frequency(&mut self, frequency: f32) {
+        WavetableOscillator::set_frequency(self, frequency);
+    }
+
+    fn set_wavetable_position(&mut self, position: f32) {
+        self.set_position(position);
+    }
+
+    fn set_phase(&mut self, phase: f32) {
+        WavetableOscillator::set_phase(self, phase);
+    }
+}
+
+impl Source for Box<dyn VoiceGenerator> {
+    fn current_frame_len(&self) -> Option<usize> {
+        (**self).current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        (**self).channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        (**self).sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        (**self).total_duration()
+    }
+}
+
+impl StereoSource for Box<dyn VoiceGenerator> {
+    fn next_frame(&mut self) -> Option<(f32, f32)> {
+        (**self).next_frame()
+    }
+}
+
+/// Enveloped oscillator played by a single voice
+pub type VoiceSource = ADSREnvelope<Box<dyn VoiceGenerator>>;
+
+/// Policy deciding which voice is taken over when every voice is busy
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VoiceStealing {
+    Oldest,    // Steal the voice that was triggered first
+    Quietest,  // Steal the voice with the lowest envelope level
+    SameNote,  // Retrigger a voice already playing the note, falling back to the oldest
+}
+
+impl VoiceStealing {
+    pub fn next(self) -> Self {
+        match self {
+            VoiceStealing::Oldest => VoiceStealing::Quietest,
+            VoiceStealing::Quietest => VoiceStealing::SameNote,
+            VoiceStealing::SameNote => VoiceStealing::Oldest,
+        }
+    }
+}
+
+impl fmt::Display for VoiceStealing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VoiceStealing::Oldest => write!(f, "oldest"),
+            VoiceStealing::Quietest => write!(f, "quietest"),
+            VoiceStealing::SameNote => write!(f, "same note"),
+        }
+    }
+}
+
+/// How a track's notes share its voices
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlayMode {
+    Poly,    // Every note gets a voice of its own
+    Mono,    // One voice, retriggered by every note and gliding from the last pitch
+    Legato,  // One voice, notes played while it is held slide to their pitch without retriggering
+}
+
+impl PlayMode {
+    pub fn next(self) -> Self {
+        match self {
+            PlayMode::Poly => PlayMode::Mono,
+            PlayMode::Mono => PlayMode::Legato,
+            PlayMode::Legato => PlayMode::Poly,
+        }
+    }
+}
+
+impl fmt::Display for PlayMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlayMode::Poly => write!(f, "Poly"),
+            PlayMode::Mono => write!(f, "Mono"),
+            PlayMode::Legato => write!(f, "Legato"),
+        }
+    }
+}
+
+/// How the glide time of a mono track is measured
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GlideMode {
+    ConstantTime,  // Every glide takes the glide time, whatever the interval
+    ConstantRate,  // The glide time is per octave, so wider intervals take longer
+}
+
+impl GlideMode {
+    pub fn next(self) -> Self {
+        match self {
+            GlideMode::ConstantTime => GlideMode::ConstantRate,
+            GlideMode::ConstantRate => GlideMode::ConstantTime,
+        }
+    }
+}
+
+impl fmt::Display for GlideMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GlideMode::ConstantTime => write!(f, "constant time"),
+            GlideMode::ConstantRate => write!(f, "constant rate"),
+        }
+    }
+}
+
+/// Pitch slide between the notes of a mono or legato track
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Glide {
+    pub time: f32,  // Seconds per glide or per octave, depending on the mode; 0 jumps straight to the note
+    pub mode: GlideMode,
+}
+
+impl Glide {
+    /// Glide speed in semitones per sample over an interval, None when the glide is off
+    fn rate(&self, semitones: f32, sample_rate: u32) -> Option<f32> {
+        if self.time <= 0.0 {
+            return None;
+        }
+
+        let samples = self.time * sample_rate as f32;
+        Some(match self.mode {
+            GlideMode::ConstantTime => semitones.abs() / samples,
+            GlideMode::ConstantRate => 12.0 / samples,
+        })
+    }
+}
+
+impl Default for Glide {
+    fn default() -> Self {
+        Self {
+            time: 0.0,
+            mode: GlideMode::ConstantTime,
+        }
+    }
+}
+
+/// A single sounding note
+pub struct Voice {
+    pub note: Note,
+    pub octave: i32,
+    frequency: f32, // Pitch of the note before modulation, moving towards the target while gliding
+    target_frequency: f32,
+    glide_rate: f32, // Semitones per sample
+    source: VoiceSource,
+    key_gate: Option<EnvelopeGate>, // Gate of the note holding a mono voice, which releases it when closed
+    age: u64, // Trigger order, lower is older
+}
+
+impl Voice {
+    fn new(note: Note, octave: i32, source: VoiceSource, age: u64) -> Self {
+        let frequency = note.frequency(octave);
+        Self {
+            note,
+            octave,
+            frequency,
+            target_frequency: frequency,
+            glide_rate: 0.0,
+            source,
+            key_gate: None,
+            age,
+        }
+    }
+
+    /// Whether the voice is still held by its note
+    fn is_held(&self) -> bool {
+        !self.source.is_released()
+            && self.source.gate().is_open()
+            && self.key_gate.as_ref().is_none_or(EnvelopeGate::is_open)
+    }
+
+    /// Start sliding from the current pitch to another, or jump there when the glide is off
+    fn glide_to(&mut self, frequency: f32, glide: Glide) {
+        self.target_frequency = frequency;
+        let semitones = 12.0 * (frequency / self.frequency).log2();
+        match glide.rate(semitones, self.source.sample_rate()) {
+            Some(rate) => self.glide_rate = rate,
+            None => self.frequency = frequency,
+        }
+    }
+
+    /// Move the pitch along the glide by a number of frames
+    fn advance_glide(&mut self, frames: usize, pitch_ratio: f32) {
+        if self.frequency == self.target_frequency {
+            return;
+        }
+
+        let remaining = 12.0 * (self.target_frequency / self.frequency).log2();
+        let step = self.glide_rate * frames as f32;
+        self.frequency = if remaining.abs() <= step {
+            self.target_frequency
+        } else {
+            self.frequency * 2.0f32.powf(step.copysign(remaining) / 12.0)
+        };
+        self.source.source_mut().set_frequency(self.frequency * pitch_ratio);
+    }
+
+    /// Current envelope level of the voice (0.0 - 1.0)
+    pub fn level(&self) -> f32 {
+        self.source.current_level()
+    }
+}
+
+/// Fixed-size pool of voices with note-on handling and voice stealing
+pub struct VoicePool {
+    voices: Vec<Voice>,
+    max_voices: usize,
+    stealing: VoiceStealing,
+    play_mode: PlayMode,
+    glide: Glide,
+    last_frequency: Option<f32>, // Pitch of the last note played, where mono glides start once its voice has ended
+    next_age: u64,
+    pitch_ratio: f32, // Frequency factor of the current pitch modulation
+}
+
+impl VoicePool {
+    pub fn new(max_voices: usize, stealing: VoiceStealing) -> Self {
+        Self {
+            voices: Vec::with_capacity(max_voices.max(1)),
+            max_voices: max_voices.max(1),
+            stealing,
+            play_mode: PlayMode::Poly,
+            glide: Glide::default(),
+            last_frequency: None,
+            next_age: 0,
+            pitch_ratio: 1.0,
+        }
+    }
+
+    /// Create an empty pool with the same voice count, stealing policy, play mode and glide
+    pub fn with_settings_of(other: &VoicePool) -> Self {
+        let mut pool = Self::new(other.max_voices, other.stealing);
+        pool.play_mode = other.play_mode;
+        pool.glide = other.glide;
+        pool
+    }
+
+    /// Set the maximum number of simultaneous voices, dropping the oldest if shrinking
+    pub fn set_max_voices(&mut self, max_voices: usize) {
+        self.max_voices = max_voices.clamp(1, MAX_VOICE_COUNT);
+        while self.voices.len() > self.max_voices {
+            if let Some(index) = self.oldest_voice() {
+                self.voices.remove(index);
+            }
+        }
+    }
+
+    pub fn max_voices(&self) -> usize {
+        self.max_voices
+    }
+
+    pub fn set_stealing(&mut self, stealing: VoiceStealing) {
+        self.stealing = stealing;
+    }
+
+    pub fn stealing(&self) -> VoiceStealing {
+        self.stealing
+    }
+
+    /// Switch between poly, mono and legato, keeping only the newest voice in the mono modes
+    pub fn set_play_mode(&mut self, play_mode: PlayMode) {
+        self.play_mode = play_mode;
+        if play_mode != PlayMode::Poly {
+            if let Some(index) = self.newest_voice() {
+                let voice = self.voices.swap_remove(index);
+                self.voices.clear();
+                self.voices.push(voice);
+            }
+        }
+    }
+
+    pub fn play_mode(&self) -> PlayMode {
+        self.play_mode
+    }
+
+    pub fn set_glide(&mut self, glide: Glide) {
+        self.glide = Glide {
+            time: glide.time.clamp(0.0, MAX_GLIDE_TIME),
+            mode: glide.mode,
+        };
+    }
+
+    pub fn glide(&self) -> Glide {
+        self.glide
+    }
+
+    /// Whether the next note will slide the held legato voice instead of starting a new one
+    pub fn holds_legato_voice(&self) -> bool {
+        self.play_mode == PlayMode::Legato
+            && self.newest_voice().is_some_and(|index| self.voices[index].is_held())
+    }
+
+    /// Start a new voice, stealing one according to the policy when the pool is full
+    ///
+    /// Returns the gate of the new voice, closing it releases exactly this voice. In the
+    /// mono modes the note takes over the track's single voice instead, see `mono_note_on`.
+    pub fn note_on(&mut self, note: Note, octave: i32, mut source: VoiceSource) -> EnvelopeGate {
+        if self.play_mode != PlayMode::Poly {
+            return self.mono_note_on(note, octave, source);
+        }
+
+        let gate = source.gate();
+        let frequency = note.frequency(octave);
+        if self.pitch_ratio != 1.0 {
+            source.source_mut().set_frequency(frequency * self.pitch_ratio);
+        }
+        let voice = Voice::new(note, octave, source, self.next_age);
+        self.next_age += 1;
+
+        // Same-note stealing retriggers in place even when voices are still free
+        if self.stealing == VoiceStealing::SameNote {
+            if let Some(index) = self.voices.iter().position(|v| v.note == note && v.octave == octave) {
+                self.voices[index] = voice;
+                return gate;
+            }
+        }
+
+        if self.voices.len() < self.max_voices {
+            self.voices.push(voice);
+            return gate;
+        }
+
+        let steal_index = match self.stealing {
+            VoiceStealing::Quietest => self.quietest_voice(),
+            VoiceStealing::Oldest | VoiceStealing::SameNote => self.oldest_voice(),
+        };
+
+        if let Some(index) = steal_index {
+            self.voices[index] = voice;
+        }
+        
+        gate
+    }
+
+    /// Play a note on the single voice of a mono or legato pool
+    ///
+    /// In legato mode a note played while the voice is held slides the voice to its pitch
+    /// and leaves the envelope running, otherwise the new source replaces the voice. Mono
+    /// mode glides every new note from the last pitch played. The last note played owns
+    /// the voice: the returned gate releases it, letting go of earlier notes does not.
+    fn mono_note_on(&mut self, note: Note, octave: i32, source: VoiceSource) -> EnvelopeGate {
+        let key_gate = EnvelopeGate::new();
+        let frequency = note.frequency(octave);
+        let age = self.next_age;
+        self.next_age += 1;
+
+        if self.holds_legato_voice() {
+            self.last_frequency = Some(frequency);
+            if let Some(index) = self.newest_voice() {
+                let voice = &mut self.voices[index];
+                voice.source.continue_with(&source);
+                voice.note = note;
+                voice.octave = octave;
+                voice.age = age;
+                voice.key_gate = Some(key_gate.clone());
+                voice.glide_to(frequency, self.glide);
+                voice.source.source_mut().set_frequency(voice.frequency * self.pitch_ratio);
+                return key_gate;
+            }
+        }
+
+        let previous_frequency = self.newest_voice().map(|index| self.voices[index].frequency).or(self.last_frequency);
+        self.last_frequency = Some(frequency);
+        let mut voice = Voice::new(note, octave, source, age);
+        voice.key_gate = Some(key_gate.clone());
+        if let (PlayMode::Mono, Some(previous_frequency)) = (self.play_mode, previous_frequency) {
+            voice.frequency = previous_frequency;
+            voice.glide_to(frequency, self.glide);
+        }
+        voice.source.source_mut().set_frequency(voice.frequency * self.pitch_ratio);
+
+        self.voices.clear();
+        self.voices.push(voice);
+        key_gate
+    }
+
+    /// Shift the pitch of every voice, including ones started later, by a number of semitones
+    pub fn set_pitch_offset(&mut self, semitones: f32) {
+        let pitch_ratio = 2.0f32.powf(semitones / 12.0);
+        if pitch_ratio == self.pitch_ratio {
+            return;
+        }
+
+        self.pitch_ratio = pitch_ratio;
+        for voice in &mut self.voices {
+            voice.source.source_mut().set_frequency(voice.frequency * pitch_ratio);
+        }
+    }
+
+    /// Move every voice to a wavetable position (0.0 - 1.0)
+    pub fn set_wavetable_position(&mut self, position: f32) {
+        for voice in &mut self.voices {
+            voice.source.source_mut().set_wavetable_position(position);
+        }
+    }
+
+    /// Silence every voice immediately
+    pub fn stop_all(&mut self) {
+        self.voices.clear();
+    }
+
+    /// Number of notes started since the pool was created
+    pub fn notes_started(&self) -> u64 {
+        self.next_age
+    }
+
+    /// Add the output of all voices to a buffer of interleaved left/right frames, dropping voices that have finished
+    ///
+    /// Glides and the note-off of mono voices advance once per call, so the buffer should be short.
+    pub fn render(&mut self, output: &mut [f32]) {
+        let pitch_ratio = self.pitch_ratio;
+        self.voices.retain_mut(|voice| {
+            if voice.key_gate.as_ref().is_some_and(|gate| !gate.is_open()) {
+                voice.source.release();
+            }
+            voice.advance_glide(output.len() / 2, pitch_ratio);
+
+            for frame in output.chunks_exact_mut(2) {
+                match voice.source.next_frame() {
+                    Some((left, right)) => {
+                        frame[0] += left;
+                        frame[1] += right;
+                    },
+                    None => return false,
+                }
+            }
+
+            // Sequenced notes release by themselves, their gate tells the modulation envelopes
+            if voice.source.is_released() {
+                if let Some(gate) = &voice.key_gate {
+                    gate.close();
+                }
+            }
+            true
+        });
+    }
+
+    fn oldest_voice(&self) -> Option<usize> {
+        self.voices.iter()
+            .enumerate()
+            .min_by_key(|(_, voice)| voice.age)
+            .map(|(index, _)| index)
+    }
+
+    fn newest_voice(&self) -> Option<usize> {
+        self.voices.iter()
+            .enumerate()
+            .max_by_key(|(_, voice)| voice.age)
+            .map(|(index, _)| index)
+    }
+
+    fn quietest_voice(&self) -> Option<usize> {
+        self.voices.iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.level().total_cmp(&b.level()))
+            .map(|(index, _)| index)
+    }
+}
+
+impl fmt::Debug for VoicePool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VoicePool")
+            .field("active_voices", &self.voices.len())
+            .field("max_voices", &self.max_voices)
+            .field("stealing", &self.stealing)
+            .field("play_mode", &self.play_mode)
+            .field("glide", &self.glide)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::waveforms::sine_wave::SineWave;
+
+    const SAMPLE_RATE: u32 = 48000;
+
+    fn source(note: Note, attack: f32) -> VoiceSource {
+        let generator: Box<dyn VoiceGenerator> = Box::new(SineWave::new(note.frequency(4), SAMPLE_RATE));
+        ADSREnvelope::new(generator, attack, 0.1, 0.8, 0.1)
+    }
+
+    fn render(pool: &mut VoicePool, frames: usize) {
+        let mut buffer = vec![0.0; frames * 2];
+        pool.render(&mut buffer);
+    }
+
+    fn notes(pool: &VoicePool) -> Vec<Note> {
+        let mut voices: Vec<&Voice> = pool.voices.iter().collect();
+        voices.sort_by_key(|voice| voice.age);
+        voices.iter().map(|voice| voice.note).collect()
+    }
+
+    #[test]
+    fn oldest_stealing_takes_the_first_voice() {
+        let mut pool = VoicePool::new(2, VoiceStealing::Oldest);
+        for note in [Note::C, Note::E, Note::G] {
+            pool.note_on(note, 4, source(note, 0.01));
+        }
+
+        assert_eq!(notes(&pool), vec![Note::E, Note::G]);
+    }
+
+    #[test]
+    fn quietest_stealing_takes_the_lowest_envelope() {
+        let mut pool = VoicePool::new(2, VoiceStealing::Quietest);
+        pool.note_on(Note::C, 4, source(Note::C, 0.001));
+        pool.note_on(Note::E, 4, source(Note::E, 1.0));
+        render(&mut pool, 480);
+
+        // The slow attack of E leaves it quieter than the older C
+        pool.note_on(Note::G, 4, source(Note::G, 0.01));
+        assert_eq!(notes(&pool), vec![Note::C, Note::G]);
+    }
+
+    #[test]
+    fn same_note_stealing_retriggers_in_place() {
+        let mut pool = VoicePool::new(4, VoiceStealing::SameNote);
+        pool.note_on(Note::C, 4, source(Note::C, 0.01));
+        pool.note_on(Note::E, 4, source(Note::E, 0.01));
+        pool.note_on(Note::C, 4, source(Note::C, 0.01));
+        assert_eq!(notes(&pool), vec![Note::E, Note::C]);
+
+        // The same note in another octave is a different note
+        pool.note_on(Note::C, 5, source(Note::C, 0.01));
+        assert_eq!(pool.voices.len(), 3);
+    }
+
+    #[test]
+    fn same_note_stealing_falls_back_to_the_oldest() {
+        let mut pool = VoicePool::new(2, VoiceStealing::SameNote);
+        for note in [Note::C, Note::E, Note::G] {
+            pool.note_on(note, 4, source(note, 0.01));
+        }
+
+        assert_eq!(notes(&pool), vec![Note::E, Note::G]);
+    }
+
+    #[test]
+    fn legato_hands_the_voice_to_the_next_note() {
+        let mut pool = VoicePool::new(8, VoiceStealing::Oldest);
+        pool.set_play_mode(PlayMode::Legato);
+        let first = pool.note_on(Note::C, 4, source(Note::C, 0.01));
+        render(&mut pool, 4800);
+        assert!(pool.holds_legato_voice());
+
+        // The held voice slides to the new note without restarting its envelope
+        let second = pool.note_on(Note::E, 4, source(Note::E, 0.01));
+        render(&mut pool, 2);
+        assert_eq!(notes(&pool), vec![Note::E]);
+        assert!(pool.voices[0].level() > 0.5);
+
+        // Only the note that owns the voice releases it
+        first.close();
+        render(&mut pool, 64);
+        assert!(pool.holds_legato_voice());
+
+        second.close();
+        render(&mut pool, 64);
+        assert!(!pool.holds_legato_voice());
+    }
+}
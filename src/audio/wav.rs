@@ -0,0 +1,316 @@
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Sample encodings supported when writing RIFF/WAVE files
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WavFormat {
+    Pcm16,
+    Pcm24,
+    Float32,
+}
+
+impl WavFormat {
+    /// Number of bits used to store a single sample
+    pub fn bits_per_sample(&self) -> u16 {
+        match self {
+            WavFormat::Pcm16 => 16,
+            WavFormat::Pcm24 => 24,
+            WavFormat::Float32 => 32,
+        }
+    }
+
+    /// WAVE format tag (1 = integer PCM, 3 = IEEE float)
+    fn format_tag(&self) -> u16 {
+        match self {
+            WavFormat::Pcm16 | WavFormat::Pcm24 => 1,
+            WavFormat::Float32 => 3,
+        }
+    }
+
+    fn bytes_per_sample(&self) -> u16 {
+        self.bits_per_sample() / 8
+    }
+}
+
+/// Write interleaved samples in the range -1.0 to 1.0 as a RIFF/WAVE stream
+///
+/// # Parameters
+/// - `writer`: Destination for the encoded bytes
+/// - `samples`: Interleaved samples (one per channel per frame)
+/// - `sample_rate`: Sample rate in Hz
+/// - `channels`: Number of interleaved channels
+/// - `format`: Sample encoding to use
+pub fn write_wav<W: Write>(writer: &mut W, samples: &[f32], sample_rate: u32, channels: u16, format: WavFormat) -> std::io::Result<()> {
+    let bytes_per_sample = format.bytes_per_sample() as u32;
+    let block_align = channels as u32 * bytes_per_sample;
+    let byte_rate = sample_rate * block_align;
+    let data_size = samples.len() as u32 * bytes_per_sample;
+
+    // Non-PCM formats carry an extension size field and a 'fact' chunk
+    let is_float = format == WavFormat::Float32;
+    let fmt_size: u32 = if is_float { 18 } else { 16 };
+    let fact_size: u32 = if is_float { 12 } else { 0 };
+    let riff_size = 4 + (8 + fmt_size) + fact_size + (8 + data_size) + (data_size & 1);
+
+    // RIFF header
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&riff_size.to_le_bytes())?;
+    writer.write_all(b"WAVE")?;
+
+    // Format chunk
+    writer.write_all(b"fmt ")?;
+    writer.write_all(&fmt_size.to_le_bytes())?;
+    writer.write_all(&format.format_tag().to_le_bytes())?;
+    writer.write_all(&channels.to_le_bytes())?;
+    writer.write_all(&sample_rate.to_le_bytes())?;
+    writer.write_all(&byte_rate.to_le_bytes())?;
+    writer.write_all(&(block_align as u16).to_le_bytes())?;
+    writer.write_all(&format.bits_per_sample().to_le_bytes())?;
+    if is_float {
+        writer.write_all(&0u16.to_le_bytes())?;
+
+        // Fact chunk holds the number of sample frames
+        let frames = samples.len() as u32 / channels.max(1) as u32;
+        writer.write_all(b"fact")?;
+        writer.write_all(&4u32.to_le_bytes())?;
+        writer.write_all(&frames.to_le_bytes())?;
+    }
+
+    // Data chunk
+    writer.write_all(b"data")?;
+    writer.write_all(&data_size.to_le_bytes())?;
+    for &sample in samples {
+        let sample = sample.clamp(-1.0, 1.0);
+        match format {
+            WavFormat::Pcm16 => {
+                let value = (sample * i16::MAX as f32).round() as i16;
+                writer.write_all(&value.to_le_bytes())?;
+            },
+            WavFormat::Pcm24 => {
+                let value = (sample * 8_388_607.0).round() as i32;
+                writer.write_all(&value.to_le_bytes()[0..3])?;
+            },
+            WavFormat::Float32 => {
+                writer.write_all(&sample.to_le_bytes())?;
+            },
+        }
+    }
+
+    // Chunks are word aligned
+    if data_size & 1 == 1 {
+        writer.write_all(&[0])?;
+    }
+
+    Ok(())
+}
+
+/// Write interleaved samples to a WAV file on disk
+pub fn write_wav_file(file_path: &str, samples: &[f32], sample_rate: u32, channels: u16, format: WavFormat) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(file_path)?);
+    write_wav(&mut writer, samples, sample_rate, channels, format)?;
+    writer.flush()
+}
+
+/// Decoded contents of a RIFF/WAVE file
+#[derive(Debug, Clone)]
+pub struct WavData {
+    pub samples: Vec<f32>,                    // Interleaved samples in the range -1.0 to 1.0
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub loop_points: Option<(usize, usize)>,  // Sustain loop from a 'smpl' chunk, in frames (end exclusive)
+    pub root_key: Option<u8>,                 // MIDI unity note from a 'smpl' chunk
+}
+
+impl WavData {
+    /// Number of sample frames
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels.max(1) as usize
+    }
+
+    /// The channels averaged into a single channel
+    pub fn to_mono(&self) -> Vec<f32> {
+        let channels = self.channels.max(1) as usize;
+        self.samples.chunks_exact(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect()
+    }
+}
+
+/// Read a RIFF/WAVE stream
+///
+/// Decodes 8, 16, 24 and 32-bit integer PCM as well as 32 and 64-bit float data,
+/// including WAVE_FORMAT_EXTENSIBLE files. Loop points and the root key are taken
+/// from a 'smpl' chunk when the file has one; other chunks are skipped.
+pub fn read_wav<R: Read>(reader: &mut R) -> Result<WavData, Box<dyn std::error::Error>> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err("Not a RIFF/WAVE file".into());
+    }
+
+    let mut format: Option<(u16, u16, u32, u16)> = None; // (format tag, channels, sample rate, bits per sample)
+    let mut data: Option<&[u8]> = None;
+    let mut loop_points = None;
+    let mut root_key = None;
+
+    // Walk the chunks, which are padded to an even length
+    let mut offset = 12;
+    while offset + 8 <= bytes.len() {
+        let id = &bytes[offset..offset + 4];
+        let size = read_u32(&bytes, offset + 4) as usize;
+        let body_start = offset + 8;
+        let body = &bytes[body_start..(body_start + size).min(bytes.len())];
+
+        match id {
+            b"fmt " if body.len() >= 16 => {
+                let mut format_tag = read_u16(body, 0);
+                // Extensible files keep the actual format in the first two bytes of the sub-format GUID
+                if format_tag == 0xFFFE && body.len() >= 26 {
+                    format_tag = read_u16(body, 24);
+                }
+                format = Some((format_tag, read_u16(body, 2), read_u32(body, 4), read_u16(body, 14)));
+            },
+            b"data" => data = Some(body),
+            b"smpl" if body.len() >= 36 => {
+                root_key = Some(read_u32(body, 12).min(127) as u8);
+                let loop_count = read_u32(body, 28);
+                if loop_count > 0 && body.len() >= 36 + 24 {
+                    // The loop end in the chunk is the last frame played
+                    let start = read_u32(body, 36 + 8) as usize;
+                    let end = read_u32(body, 36 + 12) as usize + 1;
+                    if end > start {
+                        loop_points = Some((start, end));
+                    }
+                }
+            },
+            _ => {},
+        }
+
+        offset = body_start + size + (size & 1);
+    }
+
+    let (format_tag, channels, sample_rate, bits_per_sample) = format.ok_or("WAV file has no format chunk")?;
+    let data = data.ok_or("WAV file has no data chunk")?;
+    if channels == 0 || sample_rate == 0 {
+        return Err("WAV file has no channels or no sample rate".into());
+    }
+
+    let samples: Vec<f32> = match (format_tag, bits_per_sample) {
+        (1, 8) => data.iter().map(|&byte| (byte as f32 - 128.0) / 128.0).collect(),
+        (1, 16) => data.chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0)
+            .collect(),
+        (1, 24) => data.chunks_exact(3)
+            .map(|b| i32::from_le_bytes([0, b[0], b[1], b[2]]) as f32 / 2_147_483_648.0)
+            .collect(),
+        (1, 32) => data.chunks_exact(4)
+            .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2_147_483_648.0)
+            .collect(),
+        (3, 32) => data.chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect(),
+        (3, 64) => data.chunks_exact(8)
+            .map(|b| f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as f32)
+            .collect(),
+        _ => return Err(format!("Unsupported WAV encoding (format {}, {} bits)", format_tag, bits_per_sample).into()),
+    };
+
+    Ok(WavData {
+        samples,
+        sample_rate,
+        channels,
+        loop_points,
+        root_key,
+    })
+}
+
+/// Read a WAV file from disk
+pub fn read_wav_file(file_path: &str) -> Result<WavData, Box<dyn std::error::Error>> {
+    let mut file = File::open(file_path)?;
+    read_wav(&mut file)
+}
+
+/// WAV files of a directory, sorted by name
+pub fn wav_files_in(directory: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("wav")))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stereo sine frames covering both signs of the range
+    fn test_signal() -> Vec<f32> {
+        (0..1024).map(|i| (i as f32 * 0.05).sin() * 0.9).collect()
+    }
+
+    /// Write the test signal and read it back, checking every sample within `tolerance`
+    fn assert_round_trip(format: WavFormat, tolerance: f32) {
+        let samples = test_signal();
+        let mut bytes = Vec::new();
+        write_wav(&mut bytes, &samples, 44100, 2, format).unwrap();
+
+        let wav = read_wav(&mut bytes.as_slice()).unwrap();
+        assert_eq!(wav.sample_rate, 44100);
+        assert_eq!(wav.channels, 2);
+        assert_eq!(wav.frames(), samples.len() / 2);
+        for (read, written) in wav.samples.iter().zip(&samples) {
+            assert!((read - written).abs() <= tolerance, "{:?}: wrote {}, read {}", format, written, read);
+        }
+    }
+
+    // Integer data is written scaled by the largest positive value and read back scaled by
+    // the full range, so it may come back off by up to two steps
+    #[test]
+    fn pcm16_round_trip() {
+        assert_round_trip(WavFormat::Pcm16, 2.0 / 32768.0);
+    }
+
+    #[test]
+    fn pcm24_round_trip() {
+        assert_round_trip(WavFormat::Pcm24, 2.0 / 8_388_608.0);
+    }
+
+    #[test]
+    fn float_round_trip() {
+        assert_round_trip(WavFormat::Float32, 0.0);
+    }
+
+    #[test]
+    fn smpl_chunk_sets_root_key_and_exclusive_loop_end() {
+        let mut bytes = Vec::new();
+        write_wav(&mut bytes, &test_signal(), 44100, 1, WavFormat::Pcm16).unwrap();
+
+        // Root key 48 and one loop over frames 100 to 199, the chunk storing the last frame played
+        let mut smpl = vec![0u8; 60];
+        smpl[12..16].copy_from_slice(&48u32.to_le_bytes());
+        smpl[28..32].copy_from_slice(&1u32.to_le_bytes());
+        smpl[44..48].copy_from_slice(&100u32.to_le_bytes());
+        smpl[48..52].copy_from_slice(&199u32.to_le_bytes());
+        bytes.extend_from_slice(b"smpl");
+        bytes.extend_from_slice(&(smpl.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&smpl);
+        let riff_size = bytes.len() as u32 - 8;
+        bytes[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+        let wav = read_wav(&mut bytes.as_slice()).unwrap();
+        assert_eq!(wav.root_key, Some(48));
+        assert_eq!(wav.loop_points, Some((100, 200)));
+        assert_eq!(wav.frames(), test_signal().len());
+    }
+}
@@ -0,0 +1,158 @@
+use super::AudioEffect;
+
+/// Bit depths reachable through the normalized bits parameter
+const MIN_BITS: f32 = 1.0;
+const MAX_BITS: f32 = 16.0;
+
+/// Lowest rate the crusher can hold samples at, in Hz
+const MIN_CRUSH_RATE_HZ: f32 = 500.0;
+
+/// Bitcrusher effect: quantizes to a lower bit depth and holds samples at a lower rate
+///
+/// The aliasing and stepping are the point, so nothing is oversampled or filtered.
+/// At a few bits and a few kHz it gives the crunch of early sound chips.
+#[derive(Debug, Clone)]
+pub struct BitcrusherEffect {
+    bits: f32,            // Bit depth (1.0 - 16.0), fractional depths step smoothly between the whole ones
+    crush_rate: f32,      // Rate new samples are taken at in Hz, up to the sample rate
+    mix: f32,             // Dry/wet mix (0.0 - 1.0)
+    hold_phase: f32,      // Progress to the next held sample (0 to 1)
+    held: (f32, f32),     // Held left and right samples
+    sample_rate: u32,
+}
+
+impl BitcrusherEffect {
+    /// Create a new bitcrusher effect
+    ///
+    /// # Parameters
+    /// - `bits`: Bit depth (1 - 16)
+    /// - `crush_rate`: Rate samples are held at in Hz (500 Hz up to the sample rate)
+    /// - `mix`: Dry/wet mix (0.0 - 1.0)
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(bits: f32, crush_rate: f32, mix: f32, sample_rate: u32) -> Self {
+        Self {
+            bits: bits.clamp(MIN_BITS, MAX_BITS),
+            crush_rate: crush_rate.clamp(MIN_CRUSH_RATE_HZ, sample_rate as f32),
+            mix: mix.clamp(0.0, 1.0),
+            hold_phase: 1.0,
+            held: (0.0, 0.0),
+            sample_rate,
+        }
+    }
+
+    /// Round a sample to the nearest step of the bit depth
+    fn quantize(&self, sample: f32) -> f32 {
+        let steps = 2.0f32.powf(self.bits - 1.0);
+        (sample.clamp(-1.0, 1.0) * steps).round() / steps
+    }
+
+    /// Whether a new sample is due, moving the hold on by one sample
+    fn advance_hold(&mut self) -> bool {
+        self.hold_phase += self.crush_rate / self.sample_rate as f32;
+        if self.hold_phase >= 1.0 {
+            self.hold_phase -= self.hold_phase.floor();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Set bit depth (1 - 16)
+    pub fn set_bits(&mut self, bits: f32) {
+        self.bits = bits.clamp(MIN_BITS, MAX_BITS);
+    }
+
+    pub fn bits(&self) -> f32 {
+        self.bits
+    }
+
+    /// Set the rate samples are held at in Hz
+    pub fn set_crush_rate(&mut self, crush_rate: f32) {
+        self.crush_rate = crush_rate.clamp(MIN_CRUSH_RATE_HZ, self.sample_rate as f32);
+    }
+
+    pub fn crush_rate(&self) -> f32 {
+        self.crush_rate
+    }
+
+    /// Set dry/wet mix (0.0 - 1.0)
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+}
+
+impl AudioEffect for BitcrusherEffect {
+    fn process_sample(&mut self, input: f32) -> f32 {
+        if self.advance_hold() {
+            self.held.0 = self.quantize(input);
+        }
+        input * (1.0 - self.mix) + self.held.0 * self.mix
+    }
+
+    fn process_frame(&mut self, left: f32, right: f32) -> (f32, f32) {
+        if self.advance_hold() {
+            self.held = (self.quantize(left), self.quantize(right));
+        }
+        (left * (1.0 - self.mix) + self.held.0 * self.mix,
+         right * (1.0 - self.mix) + self.held.1 * self.mix)
+    }
+
+    fn reset(&mut self) {
+        self.hold_phase = 1.0;
+        self.held = (0.0, 0.0);
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+        self.crush_rate = self.crush_rate.min(sample_rate as f32);
+        self.reset();
+    }
+
+    fn name(&self) -> &str {
+        "Bitcrusher"
+    }
+
+    fn parameter_names(&self) -> &[&'static str] {
+        &["Bits", "Rate", "Mix"]
+    }
+
+    fn parameter(&self, index: usize) -> Option<f32> {
+        // The rate moves exponentially so each step of the knob is the same musical interval
+        let rate_range = (self.sample_rate as f32 / MIN_CRUSH_RATE_HZ).ln();
+        match index {
+            0 => Some((self.bits - MIN_BITS) / (MAX_BITS - MIN_BITS)),
+            1 => Some(((self.crush_rate / MIN_CRUSH_RATE_HZ).ln() / rate_range).clamp(0.0, 1.0)),
+            2 => Some(self.mix),
+            _ => None,
+        }
+    }
+
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        let value = value.clamp(0.0, 1.0);
+        let rate_range = (self.sample_rate as f32 / MIN_CRUSH_RATE_HZ).ln();
+        match index {
+            0 => self.set_bits(MIN_BITS + value * (MAX_BITS - MIN_BITS)),
+            1 => self.set_crush_rate(MIN_CRUSH_RATE_HZ * (value * rate_range).exp()),
+            2 => self.set_mix(value),
+            _ => {}
+        }
+    }
+
+    fn parameter_value(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(self.bits),
+            1 => Some(self.crush_rate),
+            2 => Some(self.mix),
+            _ => None,
+        }
+    }
+
+    fn set_parameter_value(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_bits(value),
+            1 => self.set_crush_rate(value),
+            2 => self.set_mix(value),
+            _ => {}
+        }
+    }
+}
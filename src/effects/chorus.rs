@@ -0,0 +1,198 @@
+use super::AudioEffect;
+use crate::waveforms::sine_wave::sine_at;
+
+/// Most delay voices a chorus can sweep
+pub const MAX_CHORUS_VOICES: usize = 4;
+
+/// Delay of the voices at the center of their sweep, and how far they sweep around it
+const BASE_DELAY_MS: f32 = 15.0;
+const MAX_SWEEP_MS: f32 = 8.0;
+
+/// LFO rates reachable through the normalized rate parameter
+const MIN_LFO_RATE_HZ: f32 = 0.05;
+const MAX_LFO_RATE_HZ: f32 = 5.0;
+
+/// Chorus effect: several copies of the input through delays swept by phase-shifted LFOs
+///
+/// The voices spread evenly over the LFO cycle, and the right channel's voices sit halfway
+/// between the left channel's, so the copies drift apart across the stereo image.
+#[derive(Debug, Clone)]
+pub struct ChorusEffect {
+    buffer: Vec<f32>,
+    buffer_right: Vec<f32>,  // Right channel line for stereo processing
+    write_index: usize,
+
+    lfo_phase: f32,
+    lfo_rate: f32,        // LFO frequency in Hz
+
+    // Parameters
+    depth: f32,           // Sweep depth (0.0 - 1.0)
+    voices: usize,        // Delayed copies per channel (1 - MAX_CHORUS_VOICES)
+    mix: f32,             // Dry/wet mix (0.0 - 1.0)
+
+    sample_rate: u32,
+}
+
+impl ChorusEffect {
+    /// Create a new chorus effect
+    ///
+    /// # Parameters
+    /// - `lfo_rate`: LFO rate in Hz (typically 0.1 - 2.0)
+    /// - `depth`: Sweep depth (0.0 - 1.0)
+    /// - `voices`: Delayed copies per channel (1 - 4)
+    /// - `mix`: Dry/wet mix (0.0 - 1.0)
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(lfo_rate: f32, depth: f32, voices: usize, mix: f32, sample_rate: u32) -> Self {
+        let buffer_size = ((BASE_DELAY_MS + MAX_SWEEP_MS) / 1000.0 * sample_rate as f32) as usize + 2;
+
+        Self {
+            buffer: vec![0.0; buffer_size],
+            buffer_right: vec![0.0; buffer_size],
+            write_index: 0,
+            lfo_phase: 0.0,
+            lfo_rate: lfo_rate.max(0.01),
+            depth: depth.clamp(0.0, 1.0),
+            voices: voices.clamp(1, MAX_CHORUS_VOICES),
+            mix: mix.clamp(0.0, 1.0),
+            sample_rate,
+        }
+    }
+
+    /// Read a delay line `delay` samples behind the sample just written, interpolating linearly
+    fn read_delayed(&self, buffer: &[f32], delay: f32) -> f32 {
+        let len = buffer.len();
+        let position = (self.write_index + len) as f32 - delay;
+        let index = position as usize;
+        let fraction = position - index as f32;
+        let older = buffer[index % len];
+        let newer = buffer[(index + 1) % len];
+        older + (newer - older) * fraction
+    }
+
+    /// Average of the voices of one channel, the voices' LFOs starting at `phase_offset`
+    fn read_voices(&self, buffer: &[f32], phase_offset: f32) -> f32 {
+        let base_delay = BASE_DELAY_MS / 1000.0 * self.sample_rate as f32;
+        let sweep = MAX_SWEEP_MS / 1000.0 * self.sample_rate as f32 * self.depth;
+
+        let sum: f32 = (0..self.voices)
+            .map(|voice| {
+                let phase = (self.lfo_phase + phase_offset + voice as f32 / self.voices as f32).fract();
+                self.read_delayed(buffer, base_delay + sine_at(phase) * sweep)
+            })
+            .sum();
+        sum / self.voices as f32
+    }
+
+    /// Advance the write position and the LFO by one sample
+    fn advance(&mut self) {
+        self.write_index = (self.write_index + 1) % self.buffer.len();
+        self.lfo_phase += self.lfo_rate / self.sample_rate as f32;
+        if self.lfo_phase >= 1.0 {
+            self.lfo_phase -= 1.0;
+        }
+    }
+
+    /// Set LFO rate in Hz
+    pub fn set_lfo_rate(&mut self, rate: f32) {
+        self.lfo_rate = rate.max(0.01);
+    }
+
+    /// Set sweep depth (0.0 - 1.0)
+    pub fn set_depth(&mut self, depth: f32) {
+        self.depth = depth.clamp(0.0, 1.0);
+    }
+
+    /// Set the number of delayed copies per channel (1 - 4)
+    pub fn set_voices(&mut self, voices: usize) {
+        self.voices = voices.clamp(1, MAX_CHORUS_VOICES);
+    }
+
+    pub fn voices(&self) -> usize {
+        self.voices
+    }
+
+    /// Set dry/wet mix (0.0 - 1.0)
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+}
+
+impl AudioEffect for ChorusEffect {
+    fn process_sample(&mut self, input: f32) -> f32 {
+        self.buffer[self.write_index] = input;
+        let wet = self.read_voices(&self.buffer, 0.0);
+        self.advance();
+
+        input * (1.0 - self.mix) + wet * self.mix
+    }
+
+    fn process_frame(&mut self, left: f32, right: f32) -> (f32, f32) {
+        self.buffer[self.write_index] = left;
+        self.buffer_right[self.write_index] = right;
+
+        // The right channel's voices sweep halfway between the left channel's
+        let wet_left = self.read_voices(&self.buffer, 0.0);
+        let wet_right = self.read_voices(&self.buffer_right, 0.5 / self.voices as f32);
+        self.advance();
+
+        (left * (1.0 - self.mix) + wet_left * self.mix,
+         right * (1.0 - self.mix) + wet_right * self.mix)
+    }
+
+    fn reset(&mut self) {
+        self.buffer.fill(0.0);
+        self.buffer_right.fill(0.0);
+        self.write_index = 0;
+        self.lfo_phase = 0.0;
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        // Rebuild the delay lines so the delays stay the same in milliseconds
+        *self = Self::new(self.lfo_rate, self.depth, self.voices, self.mix, sample_rate);
+    }
+
+    fn name(&self) -> &str {
+        "Chorus"
+    }
+
+    fn parameter_names(&self) -> &[&'static str] {
+        &["Rate", "Depth", "Mix"]
+    }
+
+    fn parameter(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(((self.lfo_rate - MIN_LFO_RATE_HZ) / (MAX_LFO_RATE_HZ - MIN_LFO_RATE_HZ)).clamp(0.0, 1.0)),
+            1 => Some(self.depth),
+            2 => Some(self.mix),
+            _ => None,
+        }
+    }
+
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        let value = value.clamp(0.0, 1.0);
+        match index {
+            0 => self.set_lfo_rate(MIN_LFO_RATE_HZ + value * (MAX_LFO_RATE_HZ - MIN_LFO_RATE_HZ)),
+            1 => self.set_depth(value),
+            2 => self.set_mix(value),
+            _ => {}
+        }
+    }
+
+    fn parameter_value(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(self.lfo_rate),
+            1 => Some(self.depth),
+            2 => Some(self.mix),
+            _ => None,
+        }
+    }
+
+    fn set_parameter_value(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_lfo_rate(value),
+            1 => self.set_depth(value),
+            2 => self.set_mix(value),
+            _ => {}
+        }
+    }
+}
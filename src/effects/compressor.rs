@@ -0,0 +1,237 @@
+use super::{AudioEffect, SidechainEffect};
+
+/// Ranges reachable through the normalized parameters
+const MIN_THRESHOLD_DB: f32 = -60.0;
+const MAX_RATIO: f32 = 20.0;
+const MIN_ATTACK_MS: f32 = 0.1;
+const MAX_ATTACK_MS: f32 = 100.0;
+const MIN_RELEASE_MS: f32 = 10.0;
+const MAX_RELEASE_MS: f32 = 1000.0;
+const MAX_KNEE_DB: f32 = 24.0;
+const MAX_MAKEUP_DB: f32 = 24.0;
+
+/// Level reported for silence, in dB
+const SILENCE_DB: f32 = -120.0;
+
+/// Compressor effect: turns down the signal above a threshold by a ratio
+///
+/// Both channels share one peak detector so the stereo image stays steady while compressing.
+/// The knee blends between no compression and the full ratio around the threshold. Keyed by
+/// another signal through `SidechainEffect`, it ducks its input whenever the key gets loud.
+#[derive(Debug, Clone)]
+pub struct CompressorEffect {
+    threshold_db: f32,    // Level compression starts at (-60 - 0 dB)
+    ratio: f32,           // dB of input above the threshold per dB of output (1.0 - 20.0)
+    attack_ms: f32,       // Time to clamp down on a peak
+    release_ms: f32,      // Time to recover after a peak
+    knee_db: f32,         // Width of the soft knee around the threshold (0 - 24 dB)
+    makeup_db: f32,       // Gain after compression (0 - 24 dB)
+    attack_coefficient: f32,
+    release_coefficient: f32,
+    reduction_db: f32,    // Smoothed gain reduction currently applied
+    sample_rate: u32,
+}
+
+impl CompressorEffect {
+    /// Create a new compressor effect
+    ///
+    /// # Parameters
+    /// - `threshold_db`: Level compression starts at (-60 - 0 dB)
+    /// - `ratio`: Compression ratio (1.0 - 20.0)
+    /// - `attack_ms`: Attack time in milliseconds (0.1 - 100)
+    /// - `release_ms`: Release time in milliseconds (10 - 1000)
+    /// - `knee_db`: Soft knee width (0 - 24 dB)
+    /// - `makeup_db`: Makeup gain (0 - 24 dB)
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(threshold_db: f32, ratio: f32, attack_ms: f32, release_ms: f32, knee_db: f32, makeup_db: f32, sample_rate: u32) -> Self {
+        let mut compressor = Self {
+            threshold_db: threshold_db.clamp(MIN_THRESHOLD_DB, 0.0),
+            ratio: ratio.clamp(1.0, MAX_RATIO),
+            attack_ms,
+            release_ms,
+            knee_db: knee_db.clamp(0.0, MAX_KNEE_DB),
+            makeup_db: makeup_db.clamp(0.0, MAX_MAKEUP_DB),
+            attack_coefficient: 0.0,
+            release_coefficient: 0.0,
+            reduction_db: 0.0,
+            sample_rate,
+        };
+        compressor.set_attack(attack_ms);
+        compressor.set_release(release_ms);
+        compressor
+    }
+
+    /// Gain reduction the curve asks for at a detected level, in dB
+    fn target_reduction_db(&self, level_db: f32) -> f32 {
+        let over = level_db - self.threshold_db;
+        let slope = 1.0 - 1.0 / self.ratio;
+
+        if 2.0 * over <= -self.knee_db {
+            0.0
+        } else if 2.0 * over.abs() < self.knee_db {
+            // Quadratic blend through the knee
+            slope * (over + self.knee_db / 2.0).powi(2) / (2.0 * self.knee_db)
+        } else {
+            slope * over
+        }
+    }
+
+    /// Gain for the next sample from its detected peak
+    fn next_gain(&mut self, peak: f32) -> f32 {
+        let target = self.target_reduction_db(gain_to_db(peak));
+        let coefficient = if target > self.reduction_db { self.attack_coefficient } else { self.release_coefficient };
+        self.reduction_db = target + (self.reduction_db - target) * coefficient;
+        db_to_gain(self.makeup_db - self.reduction_db)
+    }
+
+    /// Set threshold in dB (-60 - 0)
+    pub fn set_threshold_db(&mut self, threshold_db: f32) {
+        self.threshold_db = threshold_db.clamp(MIN_THRESHOLD_DB, 0.0);
+    }
+
+    /// Set compression ratio (1.0 - 20.0)
+    pub fn set_ratio(&mut self, ratio: f32) {
+        self.ratio = ratio.clamp(1.0, MAX_RATIO);
+    }
+
+    /// Set attack time in milliseconds
+    pub fn set_attack(&mut self, attack_ms: f32) {
+        self.attack_ms = attack_ms.clamp(MIN_ATTACK_MS, MAX_ATTACK_MS);
+        self.attack_coefficient = time_coefficient(self.attack_ms, self.sample_rate);
+    }
+
+    /// Set release time in milliseconds
+    pub fn set_release(&mut self, release_ms: f32) {
+        self.release_ms = release_ms.clamp(MIN_RELEASE_MS, MAX_RELEASE_MS);
+        self.release_coefficient = time_coefficient(self.release_ms, self.sample_rate);
+    }
+
+    /// Set soft knee width in dB (0 - 24)
+    pub fn set_knee_db(&mut self, knee_db: f32) {
+        self.knee_db = knee_db.clamp(0.0, MAX_KNEE_DB);
+    }
+
+    /// Set makeup gain in dB (0 - 24)
+    pub fn set_makeup_db(&mut self, makeup_db: f32) {
+        self.makeup_db = makeup_db.clamp(0.0, MAX_MAKEUP_DB);
+    }
+}
+
+impl AudioEffect for CompressorEffect {
+    fn process_sample(&mut self, input: f32) -> f32 {
+        input * self.next_gain(input.abs())
+    }
+
+    fn process_frame(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let gain = self.next_gain(left.abs().max(right.abs()));
+        (left * gain, right * gain)
+    }
+
+    fn reset(&mut self) {
+        self.reduction_db = 0.0;
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+        self.set_attack(self.attack_ms);
+        self.set_release(self.release_ms);
+        self.reset();
+    }
+
+    fn name(&self) -> &str {
+        "Compressor"
+    }
+
+    fn gain_reduction_db(&self) -> f32 {
+        self.reduction_db
+    }
+
+    fn as_sidechain_mut(&mut self) -> Option<&mut dyn SidechainEffect> {
+        Some(self)
+    }
+
+    fn parameter_names(&self) -> &[&'static str] {
+        &["Threshold", "Ratio", "Attack", "Release", "Knee", "Makeup"]
+    }
+
+    fn parameter(&self, index: usize) -> Option<f32> {
+        // Ratio and times move exponentially, so the short end of each range gets its share of the knob
+        match index {
+            0 => Some(1.0 - self.threshold_db / MIN_THRESHOLD_DB),
+            1 => Some(self.ratio.ln() / MAX_RATIO.ln()),
+            2 => Some(exponential_position(self.attack_ms, MIN_ATTACK_MS, MAX_ATTACK_MS)),
+            3 => Some(exponential_position(self.release_ms, MIN_RELEASE_MS, MAX_RELEASE_MS)),
+            4 => Some(self.knee_db / MAX_KNEE_DB),
+            5 => Some(self.makeup_db / MAX_MAKEUP_DB),
+            _ => None,
+        }
+    }
+
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        let value = value.clamp(0.0, 1.0);
+        match index {
+            0 => self.set_threshold_db((1.0 - value) * MIN_THRESHOLD_DB),
+            1 => self.set_ratio(MAX_RATIO.powf(value)),
+            2 => self.set_attack(exponential_value(value, MIN_ATTACK_MS, MAX_ATTACK_MS)),
+            3 => self.set_release(exponential_value(value, MIN_RELEASE_MS, MAX_RELEASE_MS)),
+            4 => self.set_knee_db(value * MAX_KNEE_DB),
+            5 => self.set_makeup_db(value * MAX_MAKEUP_DB),
+            _ => {}
+        }
+    }
+
+    fn parameter_value(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(self.threshold_db),
+            1 => Some(self.ratio),
+            2 => Some(self.attack_ms),
+            3 => Some(self.release_ms),
+            4 => Some(self.knee_db),
+            5 => Some(self.makeup_db),
+            _ => None,
+        }
+    }
+
+    fn set_parameter_value(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_threshold_db(value),
+            1 => self.set_ratio(value),
+            2 => self.set_attack(value),
+            3 => self.set_release(value),
+            4 => self.set_knee_db(value),
+            5 => self.set_makeup_db(value),
+            _ => {}
+        }
+    }
+}
+
+impl SidechainEffect for CompressorEffect {
+    fn process_frame_keyed(&mut self, left: f32, right: f32, key_left: f32, key_right: f32) -> (f32, f32) {
+        let gain = self.next_gain(key_left.abs().max(key_right.abs()));
+        (left * gain, right * gain)
+    }
+}
+
+pub(super) fn db_to_gain(db: f32) -> f32 {
+    10.0f32.powf(db / 20.0)
+}
+
+/// Level of a linear gain in dB, SILENCE_DB for silence
+pub(super) fn gain_to_db(gain: f32) -> f32 {
+    if gain > 0.0 { (20.0 * gain.log10()).max(SILENCE_DB) } else { SILENCE_DB }
+}
+
+/// One-pole smoothing coefficient for a time constant in milliseconds
+pub(super) fn time_coefficient(time_ms: f32, sample_rate: u32) -> f32 {
+    (-1.0 / (time_ms / 1000.0 * sample_rate as f32).max(1.0)).exp()
+}
+
+/// Normalized position (0.0 - 1.0) of a value on an exponential range
+pub(super) fn exponential_position(value: f32, min: f32, max: f32) -> f32 {
+    ((value / min).ln() / (max / min).ln()).clamp(0.0, 1.0)
+}
+
+/// Value at a normalized position (0.0 - 1.0) on an exponential range
+pub(super) fn exponential_value(position: f32, min: f32, max: f32) -> f32 {
+    min * (max / min).powf(position)
+}
@@ -1,20 +1,34 @@
 use super::AudioEffect;
 
+/// Delay times reachable through the normalized time parameter; the longest also sizes the delay lines
+const MIN_DELAY_TIME_MS: f32 = 10.0;
+const MAX_DELAY_TIME_MS: f32 = 1000.0;
+
+/// Time for the read position to glide most of the way to a new delay time, so changes don't click
+const DELAY_GLIDE_MS: f32 = 50.0;
+
+/// Taps as fractions of the delay time with their levels, the golden ratio ones adding richness
+const DELAY_TAPS: [(f32, f32); 3] = [(1.0, 0.6), (0.618, 0.25), (0.382, 0.15)];
+
 /// Enhanced delay effect with multiple taps and filtering
 #[derive(Debug, Clone)]
 pub struct DelayEffect {
     buffer: Vec<f32>,
+    buffer_right: Vec<f32>,  // Right channel line for stereo processing
     write_index: usize,
-    delay_samples: usize,
+    delay_time_ms: f32,
+    delay_samples: f32,         // Current delay of the main tap, gliding towards the target
+    target_delay_samples: f32,
+    glide_coefficient: f32,
     feedback: f32,    // Amount of delayed signal fed back (0.0 - 0.99)
     mix: f32,         // Dry/wet mix (0.0 = dry only, 1.0 = wet only)
     sample_rate: u32,
-    // Multiple delay taps for richer sound
-    tap1_samples: usize,
-    tap2_samples: usize,
     // High-frequency damping filter
     damping_filter: f32,
+    damping_filter_right: f32,
     damping_coefficient: f32,
+    // Feed echoes across channels so they bounce left and right
+    ping_pong: bool,
 }
 
 impl DelayEffect {
@@ -24,45 +38,38 @@ impl DelayEffect {
     /// - `delay_time_ms`: Delay time in milliseconds
     /// - `feedback`: Feedback amount (0.0 - 0.99)
     /// - `mix`: Dry/wet mix (0.0 - 1.0)
-    /// - `sample_rate`: Audio sample rate (e.g., 44100)
+    /// - `sample_rate`: Audio sample rate in Hz
     pub fn new(delay_time_ms: f32, feedback: f32, mix: f32, sample_rate: u32) -> Self {
-        let delay_samples = ((delay_time_ms / 1000.0) * sample_rate as f32) as usize;
-        let buffer_size = delay_samples.max(1024); // Ensure minimum buffer size
-        
-        // Create multiple delay taps for stereo width and richness
-        let tap1_samples = (delay_samples as f32 * 0.618) as usize; // Golden ratio for musicality
-        let tap2_samples = (delay_samples as f32 * 0.382) as usize; // Complementary ratio
+        // The lines hold the longest delay up front so changing the time never reallocates them,
+        // with room for the sample after the read position when interpolating
+        let line_length = (MAX_DELAY_TIME_MS / 1000.0 * sample_rate as f32).ceil() as usize + 2;
+        let delay_time_ms = delay_time_ms.clamp(0.0, MAX_DELAY_TIME_MS);
+        let delay_samples = delay_time_ms / 1000.0 * sample_rate as f32;
         
         Self {
-            buffer: vec![0.0; buffer_size],
+            buffer: vec![0.0; line_length],
+            buffer_right: vec![0.0; line_length],
             write_index: 0,
+            delay_time_ms,
             delay_samples,
+            target_delay_samples: delay_samples,
+            glide_coefficient: (-1.0 / (DELAY_GLIDE_MS / 1000.0 * sample_rate as f32)).exp(),
             feedback: feedback.clamp(0.0, 0.95), // Slightly higher max feedback
             mix: mix.clamp(0.0, 1.0),
             sample_rate,
-            tap1_samples,
-            tap2_samples,
             damping_filter: 0.0,
+            damping_filter_right: 0.0,
             damping_coefficient: 0.3, // Gentle high-frequency roll-off
+            ping_pong: false,
         }
     }
     
-    /// Update delay time in milliseconds
+    /// Update delay time in milliseconds (up to 1000)
+    ///
+    /// The taps glide to the new time rather than jumping, bending the pitch of the echoes briefly.
     pub fn set_delay_time(&mut self, delay_time_ms: f32) {
-        let new_delay_samples = ((delay_time_ms / 1000.0) * self.sample_rate as f32) as usize;
-        
-        if new_delay_samples != self.delay_samples {
-            self.delay_samples = new_delay_samples;
-            
-            // Update taps
-            self.tap1_samples = (new_delay_samples as f32 * 0.618) as usize;
-            self.tap2_samples = (new_delay_samples as f32 * 0.382) as usize;
-            
-            // Resize buffer if needed
-            if new_delay_samples >= self.buffer.len() {
-                self.buffer.resize(new_delay_samples + 1024, 0.0);
-            }
-        }
+        self.delay_time_ms = delay_time_ms.clamp(0.0, MAX_DELAY_TIME_MS);
+        self.target_delay_samples = self.delay_time_ms / 1000.0 * self.sample_rate as f32;
     }
     
     /// Set feedback amount (0.0 - 0.99)
@@ -75,31 +82,47 @@ impl DelayEffect {
         self.mix = mix.clamp(0.0, 1.0);
     }
     
-    /// Read a sample from the delay buffer at a specific tap position
-    fn read_tap(&self, tap_samples: usize) -> f32 {
-        if tap_samples == 0 || tap_samples >= self.buffer.len() {
-            return 0.0;
-        }
-        
-        let read_index = if self.write_index >= tap_samples {
-            self.write_index - tap_samples
-        } else {
-            self.buffer.len() - (tap_samples - self.write_index)
-        };
-        
-        self.buffer[read_index % self.buffer.len()]
+    /// Enable or disable ping-pong echoes in stereo processing
+    pub fn set_ping_pong(&mut self, ping_pong: bool) {
+        self.ping_pong = ping_pong;
+    }
+    
+    pub fn ping_pong(&self) -> bool {
+        self.ping_pong
+    }
+    
+    /// Move the current delay one sample further towards the target delay
+    fn glide_delay(&mut self) {
+        self.delay_samples = self.target_delay_samples + (self.delay_samples - self.target_delay_samples) * self.glide_coefficient;
+    }
+    
+    /// Mix the taps of a delay buffer with different amplitudes
+    fn read_wet(&self, buffer: &[f32]) -> f32 {
+        DELAY_TAPS.iter()
+            .map(|&(fraction, level)| read_delayed(buffer, self.write_index, self.delay_samples * fraction) * level)
+            .sum()
     }
 }
 
+/// Read a delay line `delay` samples behind the write position, interpolating between samples
+fn read_delayed(buffer: &[f32], write_index: usize, delay: f32) -> f32 {
+    let len = buffer.len();
+    let delay = delay.clamp(1.0, (len - 2) as f32);
+    let position = (write_index + len) as f32 - delay;
+    let index = position as usize;
+    let fraction = position - index as f32;
+    
+    let current = buffer[index % len];
+    let next = buffer[(index + 1) % len];
+    current + (next - current) * fraction
+}
+
 impl AudioEffect for DelayEffect {
     fn process_sample(&mut self, input: f32) -> f32 {
-        // Read from multiple delay taps for richer sound
-        let main_tap = self.read_tap(self.delay_samples);
-        let tap1 = self.read_tap(self.tap1_samples);
-        let tap2 = self.read_tap(self.tap2_samples);
+        self.glide_delay();
         
-        // Mix the taps with different amplitudes
-        let wet_signal = main_tap * 0.6 + tap1 * 0.25 + tap2 * 0.15;
+        // Read from multiple delay taps for richer sound
+        let wet_signal = self.read_wet(&self.buffer);
         
         // Apply high-frequency damping to feedback
         self.damping_filter = wet_signal * (1.0 - self.damping_coefficient) + 
@@ -115,13 +138,142 @@ impl AudioEffect for DelayEffect {
         input * (1.0 - self.mix) + wet_signal * self.mix
     }
     
+    fn process_frame(&mut self, left: f32, right: f32) -> (f32, f32) {
+        self.glide_delay();
+        let wet_left = self.read_wet(&self.buffer);
+        let wet_right = self.read_wet(&self.buffer_right);
+        
+        // Apply high-frequency damping to feedback
+        self.damping_filter = wet_left * (1.0 - self.damping_coefficient) + 
+                            self.damping_filter * self.damping_coefficient;
+        self.damping_filter_right = wet_right * (1.0 - self.damping_coefficient) + 
+                            self.damping_filter_right * self.damping_coefficient;
+        
+        if self.ping_pong {
+            // Input enters on the left, each repeat crosses to the other side
+            self.buffer[self.write_index] = (left + right) * 0.5 + self.damping_filter_right * self.feedback;
+            self.buffer_right[self.write_index] = self.damping_filter * self.feedback;
+        } else {
+            self.buffer[self.write_index] = left + self.damping_filter * self.feedback;
+            self.buffer_right[self.write_index] = right + self.damping_filter_right * self.feedback;
+        }
+        
+        // Advance write index (circular)
+        self.write_index = (self.write_index + 1) % self.buffer.len();
+        
+        (left * (1.0 - self.mix) + wet_left * self.mix,
+         right * (1.0 - self.mix) + wet_right * self.mix)
+    }
+
+    fn process_block(&mut self, buffer: &mut [f32]) {
+        let len = self.buffer.len();
+        let dry = 1.0 - self.mix;
+        let wet = self.mix;
+        let feedback = self.feedback;
+        let damping = self.damping_coefficient;
+        let target_delay = self.target_delay_samples;
+        let glide = self.glide_coefficient;
+
+        let mut delay = self.delay_samples;
+        let mut write_index = self.write_index;
+        let mut damping_left = self.damping_filter;
+        let mut damping_right = self.damping_filter_right;
+
+        for frame in buffer.chunks_exact_mut(2) {
+            let (left, right) = (frame[0], frame[1]);
+            delay = target_delay + (delay - target_delay) * glide;
+
+            let mut wet_left = 0.0;
+            let mut wet_right = 0.0;
+            for (fraction, level) in DELAY_TAPS {
+                wet_left += read_delayed(&self.buffer, write_index, delay * fraction) * level;
+                wet_right += read_delayed(&self.buffer_right, write_index, delay * fraction) * level;
+            }
+
+            damping_left = wet_left * (1.0 - damping) + damping_left * damping;
+            damping_right = wet_right * (1.0 - damping) + damping_right * damping;
+
+            if self.ping_pong {
+                self.buffer[write_index] = (left + right) * 0.5 + damping_right * feedback;
+                self.buffer_right[write_index] = damping_left * feedback;
+            } else {
+                self.buffer[write_index] = left + damping_left * feedback;
+                self.buffer_right[write_index] = right + damping_right * feedback;
+            }
+
+            write_index += 1;
+            if write_index == len {
+                write_index = 0;
+            }
+
+            frame[0] = left * dry + wet_left * wet;
+            frame[1] = right * dry + wet_right * wet;
+        }
+
+        self.delay_samples = delay;
+        self.write_index = write_index;
+        self.damping_filter = damping_left;
+        self.damping_filter_right = damping_right;
+    }
+
     fn reset(&mut self) {
         self.buffer.fill(0.0);
+        self.buffer_right.fill(0.0);
         self.write_index = 0;
+        self.delay_samples = self.target_delay_samples;
         self.damping_filter = 0.0;
+        self.damping_filter_right = 0.0;
+    }
+    
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        // Rebuild the delay lines so the delay time stays the same in milliseconds
+        let ping_pong = self.ping_pong;
+        *self = Self::new(self.delay_time_ms, self.feedback, self.mix, sample_rate);
+        self.ping_pong = ping_pong;
     }
     
     fn name(&self) -> &str {
         "Delay"
     }
+    
+    fn parameter_names(&self) -> &[&'static str] {
+        &["Time", "Feedback", "Mix"]
+    }
+    
+    fn parameter(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(((self.delay_time_ms - MIN_DELAY_TIME_MS) / (MAX_DELAY_TIME_MS - MIN_DELAY_TIME_MS)).clamp(0.0, 1.0)),
+            1 => Some(self.feedback),
+            2 => Some(self.mix),
+            _ => None,
+        }
+    }
+    
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        let value = value.clamp(0.0, 1.0);
+        match index {
+            0 => self.set_delay_time(MIN_DELAY_TIME_MS + value * (MAX_DELAY_TIME_MS - MIN_DELAY_TIME_MS)),
+            1 => self.set_feedback(value),
+            2 => self.set_mix(value),
+            _ => {}
+        }
+    }
+    
+    fn parameter_value(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(self.delay_time_ms),
+            1 => Some(self.feedback),
+            2 => Some(self.mix),
+            _ => None,
+        }
+    }
+    
+    fn set_parameter_value(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_delay_time(value),
+            1 => self.set_feedback(value),
+            2 => self.set_mix(value),
+            _ => {}
+        }
+    }
 }
\ No newline at end of file
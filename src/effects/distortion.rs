@@ -0,0 +1,248 @@
+use super::AudioEffect;
+use super::oversampling::{Oversampler, Oversampling};
+use std::collections::VecDeque;
+use std::fmt;
+
+/// Drive reachable through the normalized drive parameter, in dB of gain before the curve
+const MAX_DRIVE_DB: f32 = 36.0;
+
+/// Input offset of the tube curve, bending the two half-waves differently
+const TUBE_BIAS: f32 = 0.3;
+
+/// Cutoff of the DC blocker removing the offset asymmetric curves leave
+const DC_BLOCKER_HZ: f32 = 10.0;
+
+/// Transfer curve a distortion pushes the driven signal through
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DistortionType {
+    SoftClip,  // Smooth tanh saturation
+    HardClip,  // Flat at full scale
+    Foldback,  // Peaks above full scale fold back down
+    Tube,      // Asymmetric saturation, adding even harmonics
+}
+
+impl DistortionType {
+    /// The next curve in the cycle, wrapping around
+    pub fn next(&self) -> DistortionType {
+        match self {
+            DistortionType::SoftClip => DistortionType::HardClip,
+            DistortionType::HardClip => DistortionType::Foldback,
+            DistortionType::Foldback => DistortionType::Tube,
+            DistortionType::Tube => DistortionType::SoftClip,
+        }
+    }
+
+    /// Output of the curve for a driven sample
+    pub fn shape(&self, sample: f32) -> f32 {
+        match self {
+            DistortionType::SoftClip => sample.tanh(),
+            DistortionType::HardClip => sample.clamp(-1.0, 1.0),
+            DistortionType::Foldback => {
+                // Triangle fold: reflect back from full scale as many times as needed
+                let folded = (sample + 1.0).rem_euclid(4.0);
+                if folded < 2.0 { folded - 1.0 } else { 3.0 - folded }
+            }
+            DistortionType::Tube => (sample + TUBE_BIAS).tanh() - TUBE_BIAS.tanh(),
+        }
+    }
+}
+
+impl fmt::Display for DistortionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DistortionType::SoftClip => write!(f, "Soft Clip"),
+            DistortionType::HardClip => write!(f, "Hard Clip"),
+            DistortionType::Foldback => write!(f, "Foldback"),
+            DistortionType::Tube => write!(f, "Tube"),
+        }
+    }
+}
+
+/// One channel of a nonlinear stage: the oversampled curve followed by a DC blocker
+///
+/// The dry signal is delayed by the oversampling latency so it lines up with the shaped one.
+#[derive(Debug, Clone)]
+pub(super) struct ShapingChannel {
+    oversampler: Oversampler,
+    dry: VecDeque<f32>,
+    dc_input: f32,   // Previous input and output of the DC blocker
+    dc_output: f32,
+}
+
+impl ShapingChannel {
+    pub(super) fn new(oversampling: Oversampling) -> Self {
+        let oversampler = Oversampler::new(oversampling);
+        let dry = VecDeque::from(vec![0.0; oversampler.latency()]);
+        Self {
+            oversampler,
+            dry,
+            dc_input: 0.0,
+            dc_output: 0.0,
+        }
+    }
+
+    /// Run a sample through `shape` at the oversampled rate and remove the DC offset it left
+    ///
+    /// Returns the dry and shaped samples, both delayed by the oversampling latency.
+    pub(super) fn process(&mut self, input: f32, shape: impl FnMut(f32) -> f32, dc_coefficient: f32) -> (f32, f32) {
+        let shaped = self.oversampler.process(input, shape);
+        self.dc_output = shaped - self.dc_input + dc_coefficient * self.dc_output;
+        self.dc_input = shaped;
+
+        self.dry.push_back(input);
+        let dry = self.dry.pop_front().unwrap_or(input);
+        (dry, self.dc_output)
+    }
+
+    pub(super) fn oversampling(&self) -> Oversampling {
+        self.oversampler.oversampling()
+    }
+
+    pub(super) fn reset(&mut self) {
+        self.oversampler.reset();
+        self.dry.iter_mut().for_each(|sample| *sample = 0.0);
+        self.dc_input = 0.0;
+        self.dc_output = 0.0;
+    }
+}
+
+/// Distortion effect: drive gain followed by a clipping, folding or tube curve
+#[derive(Debug, Clone)]
+pub struct DistortionEffect {
+    distortion_type: DistortionType,
+    drive: f32,              // Gain before the curve (0 - MAX_DRIVE_DB dB)
+    mix: f32,                // Dry/wet mix (0.0 - 1.0)
+    channel: ShapingChannel,
+    channel_right: ShapingChannel,  // Right channel for stereo processing
+    dc_coefficient: f32,
+}
+
+impl DistortionEffect {
+    /// Create a new distortion effect, oversampled 2x
+    ///
+    /// # Parameters
+    /// - `distortion_type`: Transfer curve
+    /// - `drive_db`: Gain before the curve in dB (0 - 36)
+    /// - `mix`: Dry/wet mix (0.0 - 1.0)
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(distortion_type: DistortionType, drive_db: f32, mix: f32, sample_rate: u32) -> Self {
+        Self {
+            distortion_type,
+            drive: db_to_gain(drive_db.clamp(0.0, MAX_DRIVE_DB)),
+            mix: mix.clamp(0.0, 1.0),
+            channel: ShapingChannel::new(Oversampling::X2),
+            channel_right: ShapingChannel::new(Oversampling::X2),
+            dc_coefficient: dc_blocker_coefficient(sample_rate),
+        }
+    }
+
+    /// Drive and shape one sample of a channel, mixed with the dry sample
+    fn distort(channel: &mut ShapingChannel, input: f32, distortion_type: DistortionType, drive: f32, mix: f32, dc_coefficient: f32) -> f32 {
+        let (dry, wet) = channel.process(input, |sample| distortion_type.shape(sample * drive), dc_coefficient);
+        dry * (1.0 - mix) + wet * mix
+    }
+
+    pub fn set_distortion_type(&mut self, distortion_type: DistortionType) {
+        self.distortion_type = distortion_type;
+    }
+
+    pub fn distortion_type(&self) -> DistortionType {
+        self.distortion_type
+    }
+
+    /// Set drive in dB (0 - 36)
+    pub fn set_drive_db(&mut self, drive_db: f32) {
+        self.drive = db_to_gain(drive_db.clamp(0.0, MAX_DRIVE_DB));
+    }
+
+    pub fn drive_db(&self) -> f32 {
+        20.0 * self.drive.log10()
+    }
+
+    /// Set dry/wet mix (0.0 - 1.0)
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+
+    /// Run the curve at 1x, 2x or 4x the sample rate
+    pub fn set_oversampling(&mut self, oversampling: Oversampling) {
+        self.channel = ShapingChannel::new(oversampling);
+        self.channel_right = ShapingChannel::new(oversampling);
+    }
+
+    pub fn oversampling(&self) -> Oversampling {
+        self.channel.oversampling()
+    }
+}
+
+impl AudioEffect for DistortionEffect {
+    fn process_sample(&mut self, input: f32) -> f32 {
+        Self::distort(&mut self.channel, input, self.distortion_type, self.drive, self.mix, self.dc_coefficient)
+    }
+
+    fn process_frame(&mut self, left: f32, right: f32) -> (f32, f32) {
+        (Self::distort(&mut self.channel, left, self.distortion_type, self.drive, self.mix, self.dc_coefficient),
+         Self::distort(&mut self.channel_right, right, self.distortion_type, self.drive, self.mix, self.dc_coefficient))
+    }
+
+    fn reset(&mut self) {
+        self.channel.reset();
+        self.channel_right.reset();
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.dc_coefficient = dc_blocker_coefficient(sample_rate);
+        self.reset();
+    }
+
+    fn name(&self) -> &str {
+        "Distortion"
+    }
+
+    fn parameter_names(&self) -> &[&'static str] {
+        &["Drive", "Mix"]
+    }
+
+    fn parameter(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some((self.drive_db() / MAX_DRIVE_DB).clamp(0.0, 1.0)),
+            1 => Some(self.mix),
+            _ => None,
+        }
+    }
+
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        let value = value.clamp(0.0, 1.0);
+        match index {
+            0 => self.set_drive_db(value * MAX_DRIVE_DB),
+            1 => self.set_mix(value),
+            _ => {}
+        }
+    }
+
+    fn parameter_value(&self, index: usize) -> Option<f32> {
+        // The drive stays a linear gain so it comes back without a round trip through dB
+        match index {
+            0 => Some(self.drive),
+            1 => Some(self.mix),
+            _ => None,
+        }
+    }
+
+    fn set_parameter_value(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.drive = value,
+            1 => self.set_mix(value),
+            _ => {}
+        }
+    }
+}
+
+fn db_to_gain(db: f32) -> f32 {
+    10.0f32.powf(db / 20.0)
+}
+
+/// Pole of the one-pole DC blocker at a sample rate
+pub(super) fn dc_blocker_coefficient(sample_rate: u32) -> f32 {
+    1.0 - 2.0 * std::f32::consts::PI * DC_BLOCKER_HZ / sample_rate as f32
+}
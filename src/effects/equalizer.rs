@@ -0,0 +1,356 @@
+use super::AudioEffect;
+use super::compressor::{exponential_position, exponential_value};
+use std::f32::consts::PI;
+use std::fmt;
+
+/// Band frequencies reachable through the normalized frequency parameters
+pub const MIN_EQ_FREQUENCY_HZ: f32 = 20.0;
+pub const MAX_EQ_FREQUENCY_HZ: f32 = 20000.0;
+
+/// Largest boost or cut of a shelf or peak band, in dB
+pub const MAX_EQ_GAIN_DB: f32 = 18.0;
+
+/// Band widths reachable through the normalized Q parameters
+const MIN_Q: f32 = 0.1;
+const MAX_Q: f32 = 10.0;
+
+/// Highest band frequency as a fraction of the sample rate, keeping the filters below Nyquist
+const MAX_FREQUENCY_RATIO: f32 = 0.49;
+
+/// Q of a Butterworth response, no resonant bump at the cutoff
+const BUTTERWORTH_Q: f32 = 0.707;
+
+/// Shape of an equalizer band
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EqBandType {
+    HighPass,   // Low cut
+    LowShelf,
+    Peak,
+    HighShelf,
+    LowPass,    // High cut
+}
+
+impl EqBandType {
+    /// Whether the band's gain shapes its response; the pass bands only use frequency and Q
+    pub fn has_gain(&self) -> bool {
+        !matches!(self, EqBandType::HighPass | EqBandType::LowPass)
+    }
+}
+
+impl fmt::Display for EqBandType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EqBandType::HighPass => write!(f, "Low Cut"),
+            EqBandType::LowShelf => write!(f, "Low Shelf"),
+            EqBandType::Peak => write!(f, "Peak"),
+            EqBandType::HighShelf => write!(f, "High Shelf"),
+            EqBandType::LowPass => write!(f, "High Cut"),
+        }
+    }
+}
+
+/// One band of the equalizer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EqBand {
+    pub band_type: EqBandType,
+    pub frequency: f32,  // Cutoff, corner or center frequency in Hz
+    pub gain_db: f32,    // Boost or cut (-18 - 18 dB), unused by the pass bands
+    pub q: f32,          // Width of a peak, steepness of a shelf or resonance of a pass band (0.1 - 10)
+    pub enabled: bool,
+}
+
+impl EqBand {
+    pub fn new(band_type: EqBandType, frequency: f32, gain_db: f32, q: f32, enabled: bool) -> Self {
+        Self {
+            band_type,
+            frequency: frequency.clamp(MIN_EQ_FREQUENCY_HZ, MAX_EQ_FREQUENCY_HZ),
+            gain_db: gain_db.clamp(-MAX_EQ_GAIN_DB, MAX_EQ_GAIN_DB),
+            q: q.clamp(MIN_Q, MAX_Q),
+            enabled,
+        }
+    }
+}
+
+/// Normalized biquad coefficients (a0 = 1)
+#[derive(Debug, Clone, Copy)]
+struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+}
+
+impl Biquad {
+    /// Coefficients of a band from the RBJ audio EQ cookbook
+    fn design(band: &EqBand, sample_rate: u32) -> Self {
+        let frequency = band.frequency.min(sample_rate as f32 * MAX_FREQUENCY_RATIO);
+        let omega = 2.0 * PI * frequency / sample_rate as f32;
+        let (sin, cos) = omega.sin_cos();
+        let alpha = sin / (2.0 * band.q);
+        let a = 10.0f32.powf(band.gain_db / 40.0);
+        let shelf = 2.0 * a.sqrt() * alpha;
+
+        let (b0, b1, b2, a0, a1, a2) = match band.band_type {
+            EqBandType::HighPass => (
+                (1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0,
+                1.0 + alpha, -2.0 * cos, 1.0 - alpha,
+            ),
+            EqBandType::LowPass => (
+                (1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0,
+                1.0 + alpha, -2.0 * cos, 1.0 - alpha,
+            ),
+            EqBandType::Peak => (
+                1.0 + alpha * a, -2.0 * cos, 1.0 - alpha * a,
+                1.0 + alpha / a, -2.0 * cos, 1.0 - alpha / a,
+            ),
+            EqBandType::LowShelf => (
+                a * ((a + 1.0) - (a - 1.0) * cos + shelf),
+                2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+                a * ((a + 1.0) - (a - 1.0) * cos - shelf),
+                (a + 1.0) + (a - 1.0) * cos + shelf,
+                -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+                (a + 1.0) + (a - 1.0) * cos - shelf,
+            ),
+            EqBandType::HighShelf => (
+                a * ((a + 1.0) + (a - 1.0) * cos + shelf),
+                -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+                a * ((a + 1.0) + (a - 1.0) * cos - shelf),
+                (a + 1.0) - (a - 1.0) * cos + shelf,
+                2.0 * ((a - 1.0) - (a + 1.0) * cos),
+                (a + 1.0) - (a - 1.0) * cos - shelf,
+            ),
+        };
+
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+        }
+    }
+
+    /// Gain of the filter at a frequency, in dB
+    fn magnitude_db(&self, frequency: f32, sample_rate: u32) -> f32 {
+        let omega = 2.0 * PI * frequency / sample_rate as f32;
+        let (sin1, cos1) = omega.sin_cos();
+        let (sin2, cos2) = (2.0 * omega).sin_cos();
+
+        // H(e^jw) with z^-1 = cos w - j sin w
+        let numerator_re = self.b0 + self.b1 * cos1 + self.b2 * cos2;
+        let numerator_im = -(self.b1 * sin1 + self.b2 * sin2);
+        let denominator_re = 1.0 + self.a1 * cos1 + self.a2 * cos2;
+        let denominator_im = -(self.a1 * sin1 + self.a2 * sin2);
+
+        let numerator = numerator_re * numerator_re + numerator_im * numerator_im;
+        let denominator = denominator_re * denominator_re + denominator_im * denominator_im;
+        10.0 * (numerator / denominator.max(f32::MIN_POSITIVE)).max(1e-12).log10()
+    }
+}
+
+/// Delay state of one biquad on one channel (transposed direct form II)
+#[derive(Debug, Clone, Copy, Default)]
+struct BiquadState {
+    z1: f32,
+    z2: f32,
+}
+
+impl BiquadState {
+    fn process(&mut self, filter: &Biquad, input: f32) -> f32 {
+        let output = filter.b0 * input + self.z1;
+        self.z1 = filter.b1 * input - filter.a1 * output + self.z2;
+        self.z2 = filter.b2 * input - filter.a2 * output;
+        output
+    }
+}
+
+/// Parameter names, three per band in band order: frequency, gain, Q
+const PARAMETER_NAMES: [&str; 21] = [
+    "Low Cut Freq", "Low Cut Gain", "Low Cut Q",
+    "Low Shelf Freq", "Low Shelf Gain", "Low Shelf Q",
+    "Peak 1 Freq", "Peak 1 Gain", "Peak 1 Q",
+    "Peak 2 Freq", "Peak 2 Gain", "Peak 2 Q",
+    "Peak 3 Freq", "Peak 3 Gain", "Peak 3 Q",
+    "High Shelf Freq", "High Shelf Gain", "High Shelf Q",
+    "High Cut Freq", "High Cut Gain", "High Cut Q",
+];
+
+/// Parametric equalizer: low cut, low shelf, three peaks, high shelf and high cut biquads in series
+///
+/// The shelves and peaks start flat and the cut bands start bypassed, so a fresh equalizer
+/// passes the signal unchanged.
+#[derive(Debug, Clone)]
+pub struct EqualizerEffect {
+    bands: Vec<EqBand>,
+    filters: Vec<Biquad>,
+    left: Vec<BiquadState>,
+    right: Vec<BiquadState>,  // Right channel for stereo processing
+    sample_rate: u32,
+}
+
+impl EqualizerEffect {
+    /// Create a new equalizer with every band flat or bypassed
+    ///
+    /// # Parameters
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(sample_rate: u32) -> Self {
+        let bands = vec![
+            EqBand::new(EqBandType::HighPass, 30.0, 0.0, BUTTERWORTH_Q, false),
+            EqBand::new(EqBandType::LowShelf, 100.0, 0.0, BUTTERWORTH_Q, true),
+            EqBand::new(EqBandType::Peak, 300.0, 0.0, 1.0, true),
+            EqBand::new(EqBandType::Peak, 1000.0, 0.0, 1.0, true),
+            EqBand::new(EqBandType::Peak, 3000.0, 0.0, 1.0, true),
+            EqBand::new(EqBandType::HighShelf, 8000.0, 0.0, BUTTERWORTH_Q, true),
+            EqBand::new(EqBandType::LowPass, 18000.0, 0.0, BUTTERWORTH_Q, false),
+        ];
+        let filters = bands.iter().map(|band| Biquad::design(band, sample_rate)).collect();
+        let band_count = bands.len();
+
+        Self {
+            bands,
+            filters,
+            left: vec![BiquadState::default(); band_count],
+            right: vec![BiquadState::default(); band_count],
+            sample_rate,
+        }
+    }
+
+    pub fn bands(&self) -> &[EqBand] {
+        &self.bands
+    }
+
+    /// Name of a band, as used by its parameters
+    pub fn band_name(&self, index: usize) -> &'static str {
+        PARAMETER_NAMES.get(index * 3)
+            .map_or("?", |name| name.trim_end_matches(" Freq"))
+    }
+
+    /// Replace a band's settings, keeping its shape
+    fn update_band(&mut self, index: usize, update: impl FnOnce(&mut EqBand)) {
+        if let Some(band) = self.bands.get_mut(index) {
+            update(band);
+            *band = EqBand::new(band.band_type, band.frequency, band.gain_db, band.q, band.enabled);
+            self.filters[index] = Biquad::design(band, self.sample_rate);
+        }
+    }
+
+    /// Set a band's frequency in Hz (20 - 20000)
+    pub fn set_band_frequency(&mut self, index: usize, frequency: f32) {
+        self.update_band(index, |band| band.frequency = frequency);
+    }
+
+    /// Set a band's boost or cut in dB (-18 - 18)
+    pub fn set_band_gain_db(&mut self, index: usize, gain_db: f32) {
+        self.update_band(index, |band| band.gain_db = gain_db);
+    }
+
+    /// Set a band's Q (0.1 - 10)
+    pub fn set_band_q(&mut self, index: usize, q: f32) {
+        self.update_band(index, |band| band.q = q);
+    }
+
+    /// Switch a band in or out of the signal path
+    pub fn set_band_enabled(&mut self, index: usize, enabled: bool) {
+        self.update_band(index, |band| band.enabled = enabled);
+        if let (Some(left), Some(right)) = (self.left.get_mut(index), self.right.get_mut(index)) {
+            *left = BiquadState::default();
+            *right = BiquadState::default();
+        }
+    }
+
+    /// Gain of the whole equalizer at a frequency, in dB
+    pub fn magnitude_db(&self, frequency: f32) -> f32 {
+        self.bands.iter()
+            .zip(&self.filters)
+            .filter(|(band, _)| band.enabled)
+            .map(|(_, filter)| filter.magnitude_db(frequency, self.sample_rate))
+            .sum()
+    }
+}
+
+impl AudioEffect for EqualizerEffect {
+    fn process_sample(&mut self, input: f32) -> f32 {
+        let mut output = input;
+        for ((band, filter), state) in self.bands.iter().zip(&self.filters).zip(&mut self.left) {
+            if band.enabled {
+                output = state.process(filter, output);
+            }
+        }
+        output
+    }
+
+    fn process_frame(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let (mut left, mut right) = (left, right);
+        for (index, (band, filter)) in self.bands.iter().zip(&self.filters).enumerate() {
+            if band.enabled {
+                left = self.left[index].process(filter, left);
+                right = self.right[index].process(filter, right);
+            }
+        }
+        (left, right)
+    }
+
+    fn reset(&mut self) {
+        self.left.fill(BiquadState::default());
+        self.right.fill(BiquadState::default());
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+        self.filters = self.bands.iter().map(|band| Biquad::design(band, sample_rate)).collect();
+        self.reset();
+    }
+
+    fn name(&self) -> &str {
+        "Equalizer"
+    }
+
+    fn parameter_names(&self) -> &[&'static str] {
+        &PARAMETER_NAMES
+    }
+
+    fn is_modulatable(&self, index: usize) -> bool {
+        // The cut bands have no gain, so their gain parameters do nothing
+        self.bands.get(index / 3)
+            .is_some_and(|band| index % 3 != 1 || band.band_type.has_gain())
+    }
+
+    fn parameter(&self, index: usize) -> Option<f32> {
+        // Frequency and Q move exponentially, so each step of the knob is the same musical interval
+        let band = self.bands.get(index / 3)?;
+        match index % 3 {
+            0 => Some(exponential_position(band.frequency, MIN_EQ_FREQUENCY_HZ, MAX_EQ_FREQUENCY_HZ)),
+            1 => Some((band.gain_db + MAX_EQ_GAIN_DB) / (2.0 * MAX_EQ_GAIN_DB)),
+            _ => Some(exponential_position(band.q, MIN_Q, MAX_Q)),
+        }
+    }
+
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        let value = value.clamp(0.0, 1.0);
+        let band = index / 3;
+        match index % 3 {
+            0 => self.set_band_frequency(band, exponential_value(value, MIN_EQ_FREQUENCY_HZ, MAX_EQ_FREQUENCY_HZ)),
+            1 => self.set_band_gain_db(band, value * 2.0 * MAX_EQ_GAIN_DB - MAX_EQ_GAIN_DB),
+            _ => self.set_band_q(band, exponential_value(value, MIN_Q, MAX_Q)),
+        }
+    }
+
+    fn parameter_value(&self, index: usize) -> Option<f32> {
+        let band = self.bands.get(index / 3)?;
+        match index % 3 {
+            0 => Some(band.frequency),
+            1 => Some(band.gain_db),
+            _ => Some(band.q),
+        }
+    }
+
+    fn set_parameter_value(&mut self, index: usize, value: f32) {
+        let band = index / 3;
+        match index % 3 {
+            0 => self.set_band_frequency(band, value),
+            1 => self.set_band_gain_db(band, value),
+            _ => self.set_band_q(band, value),
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use super::AudioEffect;
+use std::f32::consts::PI;
+use std::fmt;
+
+/// Lowest and highest cutoff reachable from the normalized (0.0 - 1.0) cutoff control
+pub const MIN_CUTOFF_HZ: f32 = 20.0;
+pub const MAX_CUTOFF_HZ: f32 = 20000.0;
+
+/// Response of the state-variable filter
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterMode {
+    LowPass,
+    HighPass,
+    BandPass,
+    Notch,
+}
+
+impl FilterMode {
+    /// Next mode in the LP -> HP -> BP -> Notch cycle
+    pub fn next(&self) -> FilterMode {
+        match self {
+            FilterMode::LowPass => FilterMode::HighPass,
+            FilterMode::HighPass => FilterMode::BandPass,
+            FilterMode::BandPass => FilterMode::Notch,
+            FilterMode::Notch => FilterMode::LowPass,
+        }
+    }
+}
+
+impl fmt::Display for FilterMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterMode::LowPass => write!(f, "Low-pass"),
+            FilterMode::HighPass => write!(f, "High-pass"),
+            FilterMode::BandPass => write!(f, "Band-pass"),
+            FilterMode::Notch => write!(f, "Notch"),
+        }
+    }
+}
+
+/// Resonant state-variable filter (trapezoidal / zero-delay-feedback form).
+/// Stays stable when the cutoff is swept quickly, so it can be modulated per sample.
+#[derive(Debug, Clone)]
+pub struct StateVariableFilter {
+    mode: FilterMode,
+    cutoff: f32,      // Cutoff frequency in Hz
+    resonance: f32,   // Resonance (0.0 - 1.0), approaching self-oscillation at the top
+    sample_rate: u32,
+
+    // Coefficients
+    k: f32,           // Damping (1 / Q)
+    a1: f32,
+    a2: f32,
+    a3: f32,
+
+    // Integrator states, the left channel's doubling as the mono state
+    ic1eq: f32,
+    ic2eq: f32,
+    right_ic1eq: f32,
+    right_ic2eq: f32,
+}
+
+impl StateVariableFilter {
+    /// Create a new state-variable filter
+    ///
+    /// # Parameters
+    /// - `mode`: Filter response
+    /// - `cutoff`: Cutoff frequency in Hz
+    /// - `resonance`: Resonance amount (0.0 - 1.0)
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(mode: FilterMode, cutoff: f32, resonance: f32, sample_rate: u32) -> Self {
+        let mut filter = Self {
+            mode,
+            cutoff,
+            resonance: resonance.clamp(0.0, 1.0),
+            sample_rate,
+            k: 0.0,
+            a1: 0.0,
+            a2: 0.0,
+            a3: 0.0,
+            ic1eq: 0.0,
+            ic2eq: 0.0,
+            right_ic1eq: 0.0,
+            right_ic2eq: 0.0,
+        };
+        filter.update_coefficients();
+        filter
+    }
+
+    /// Recalculate the coefficients after a cutoff or resonance change
+    fn update_coefficients(&mut self) {
+        // Keep the cutoff below Nyquist so the prewarp stays finite
+        let nyquist = self.sample_rate as f32 * 0.49;
+        let cutoff = self.cutoff.clamp(MIN_CUTOFF_HZ, nyquist);
+
+        let g = (PI * cutoff / self.sample_rate as f32).tan();
+        self.k = 2.0 - 1.95 * self.resonance; // Q from 0.5 to 20
+        self.a1 = 1.0 / (1.0 + g * (g + self.k));
+        self.a2 = g * self.a1;
+        self.a3 = g * self.a2;
+    }
+
+    pub fn mode(&self) -> FilterMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: FilterMode) {
+        self.mode = mode;
+    }
+
+    /// Cutoff frequency in Hz
+    pub fn cutoff(&self) -> f32 {
+        self.cutoff
+    }
+
+    /// Set cutoff frequency in Hz
+    pub fn set_cutoff(&mut self, cutoff: f32) {
+        self.cutoff = cutoff.clamp(MIN_CUTOFF_HZ, MAX_CUTOFF_HZ);
+        self.update_coefficients();
+    }
+
+    /// Cutoff as a knob position (0.0 - 1.0) on an exponential scale
+    pub fn cutoff_normalized(&self) -> f32 {
+        normalized_from_cutoff(self.cutoff)
+    }
+
+    /// Set cutoff from a knob position (0.0 - 1.0) on an exponential scale
+    pub fn set_cutoff_normalized(&mut self, value: f32) {
+        self.set_cutoff(cutoff_from_normalized(value));
+    }
+
+    pub fn resonance(&self) -> f32 {
+        self.resonance
+    }
+
+    /// Set resonance (0.0 - 1.0)
+    pub fn set_resonance(&mut self, resonance: f32) {
+        self.resonance = resonance.clamp(0.0, 1.0);
+        self.update_coefficients();
+    }
+
+    /// Run one sample through the filter from a pair of integrator states, returning the output and the new states
+    fn tick(&self, input: f32, (ic1eq, ic2eq): (f32, f32)) -> (f32, (f32, f32)) {
+        let v3 = input - ic2eq;
+        let v1 = self.a1 * ic1eq + self.a2 * v3; // Band-pass
+        let v2 = ic2eq + self.a2 * ic1eq + self.a3 * v3; // Low-pass
+
+        let output = match self.mode {
+            FilterMode::LowPass => v2,
+            FilterMode::HighPass => input - self.k * v1 - v2,
+            FilterMode::BandPass => self.k * v1, // Unity gain at the cutoff
+            FilterMode::Notch => input - self.k * v1,
+        };
+
+        (output, (2.0 * v1 - ic1eq, 2.0 * v2 - ic2eq))
+    }
+}
+
+impl AudioEffect for StateVariableFilter {
+    fn process_sample(&mut self, input: f32) -> f32 {
+        let (output, (ic1eq, ic2eq)) = self.tick(input, (self.ic1eq, self.ic2eq));
+        self.ic1eq = ic1eq;
+        self.ic2eq = ic2eq;
+        output
+    }
+
+    /// Filter both channels independently, so stereo voices keep their width
+    fn process_frame(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let left = self.process_sample(left);
+        let (right, (ic1eq, ic2eq)) = self.tick(right, (self.right_ic1eq, self.right_ic2eq));
+        self.right_ic1eq = ic1eq;
+        self.right_ic2eq = ic2eq;
+        (left, right)
+    }
+
+    fn reset(&mut self) {
+        self.ic1eq = 0.0;
+        self.ic2eq = 0.0;
+        self.right_ic1eq = 0.0;
+        self.right_ic2eq = 0.0;
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+        self.update_coefficients();
+        self.reset();
+    }
+
+    fn name(&self) -> &str {
+        "Filter"
+    }
+}
+
+/// Map a knob position (0.0 - 1.0) to a cutoff in Hz, spreading octaves evenly across the knob
+pub fn cutoff_from_normalized(value: f32) -> f32 {
+    MIN_CUTOFF_HZ * (MAX_CUTOFF_HZ / MIN_CUTOFF_HZ).powf(value.clamp(0.0, 1.0))
+}
+
+/// Inverse of [cutoff_from_normalized]
+pub fn normalized_from_cutoff(cutoff: f32) -> f32 {
+    ((cutoff / MIN_CUTOFF_HZ).ln() / (MAX_CUTOFF_HZ / MIN_CUTOFF_HZ).ln()).clamp(0.0, 1.0)
+}
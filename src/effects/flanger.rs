@@ -1,10 +1,18 @@
 use super::AudioEffect;
 use std::f32::consts::PI;
 
+/// LFO phase offset of the right channel, a quarter cycle sweeps the channels apart
+const STEREO_PHASE_OFFSET: f32 = 0.25;
+
+/// LFO rates reachable through the normalized rate parameter
+const MIN_LFO_RATE_HZ: f32 = 0.05;
+const MAX_LFO_RATE_HZ: f32 = 5.0;
+
 /// Flanger effect using modulated delay line
 #[derive(Debug, Clone)]
 pub struct FlangerEffect {
     buffer: Vec<f32>,
+    buffer_right: Vec<f32>,  // Right channel line for stereo processing
     write_index: usize,
     
     // LFO (Low Frequency Oscillator) for modulation
@@ -40,6 +48,7 @@ impl FlangerEffect {
         
         Self {
             buffer: vec![0.0; buffer_size],
+            buffer_right: vec![0.0; buffer_size],
             write_index: 0,
             lfo_phase: 0.0,
             lfo_rate: lfo_rate.max(0.01), // Prevent division by zero
@@ -57,8 +66,8 @@ impl FlangerEffect {
         a + t * (b - a)
     }
     
-    /// Get interpolated sample from delay buffer
-    fn get_delayed_sample(&self, delay_samples: f32) -> f32 {
+    /// Get interpolated sample from a delay buffer
+    fn get_delayed_sample(&self, buffer: &[f32], delay_samples: f32) -> f32 {
         let delay_int = delay_samples as usize;
         let delay_frac = delay_samples - delay_int as f32;
         
@@ -66,22 +75,40 @@ impl FlangerEffect {
         let read_index1 = if self.write_index >= delay_int {
             self.write_index - delay_int
         } else {
-            self.buffer.len() - (delay_int - self.write_index)
+            buffer.len() - (delay_int - self.write_index)
         };
         
         let read_index2 = if read_index1 == 0 {
-            self.buffer.len() - 1
+            buffer.len() - 1
         } else {
             read_index1 - 1
         };
         
         // Linear interpolation between adjacent samples
-        let sample1 = self.buffer[read_index1 % self.buffer.len()];
-        let sample2 = self.buffer[read_index2 % self.buffer.len()];
+        let sample1 = buffer[read_index1 % buffer.len()];
+        let sample2 = buffer[read_index2 % buffer.len()];
         
         self.lerp(sample1, sample2, delay_frac)
     }
     
+    /// Modulated delay time in samples for an LFO phase (0 to 1)
+    fn modulated_delay(&self, lfo_phase: f32) -> f32 {
+        // Generate LFO (sine wave)
+        let lfo_value = (lfo_phase * 2.0 * PI).sin();
+        
+        // Calculate modulated delay time
+        let delay_offset = (lfo_value * 0.5 + 0.5) * self.delay_range * self.depth;
+        self.delay_base + delay_offset
+    }
+    
+    /// Advance the LFO by one sample
+    fn advance_lfo(&mut self) {
+        self.lfo_phase += self.lfo_rate / self.sample_rate as f32;
+        if self.lfo_phase >= 1.0 {
+            self.lfo_phase -= 1.0;
+        }
+    }
+    
     /// Set LFO rate in Hz
     pub fn set_lfo_rate(&mut self, rate: f32) {
         self.lfo_rate = rate.max(0.01);
@@ -105,15 +132,9 @@ impl FlangerEffect {
 
 impl AudioEffect for FlangerEffect {
     fn process_sample(&mut self, input: f32) -> f32 {
-        // Generate LFO (sine wave)
-        let lfo_value = (self.lfo_phase * 2.0 * PI).sin();
-        
-        // Calculate modulated delay time
-        let delay_offset = (lfo_value * 0.5 + 0.5) * self.delay_range * self.depth;
-        let total_delay = self.delay_base + delay_offset;
-        
         // Get delayed sample with interpolation
-        let delayed_sample = self.get_delayed_sample(total_delay);
+        let total_delay = self.modulated_delay(self.lfo_phase);
+        let delayed_sample = self.get_delayed_sample(&self.buffer, total_delay);
         
         // Write input + feedback to buffer
         self.buffer[self.write_index] = input + delayed_sample * self.feedback;
@@ -122,22 +143,125 @@ impl AudioEffect for FlangerEffect {
         self.write_index = (self.write_index + 1) % self.buffer.len();
         
         // Update LFO phase
-        self.lfo_phase += self.lfo_rate / self.sample_rate as f32;
-        if self.lfo_phase >= 1.0 {
-            self.lfo_phase -= 1.0;
-        }
+        self.advance_lfo();
         
         // Mix dry and wet signals
         input * (1.0 - self.mix) + delayed_sample * self.mix
     }
     
+    fn process_frame(&mut self, left: f32, right: f32) -> (f32, f32) {
+        // The right channel sweeps a quarter cycle behind the left
+        let delay_left = self.modulated_delay(self.lfo_phase);
+        let delay_right = self.modulated_delay((self.lfo_phase + STEREO_PHASE_OFFSET) % 1.0);
+        let delayed_left = self.get_delayed_sample(&self.buffer, delay_left);
+        let delayed_right = self.get_delayed_sample(&self.buffer_right, delay_right);
+        
+        // Write input + feedback to buffers
+        self.buffer[self.write_index] = left + delayed_left * self.feedback;
+        self.buffer_right[self.write_index] = right + delayed_right * self.feedback;
+        
+        // Advance write index (circular)
+        self.write_index = (self.write_index + 1) % self.buffer.len();
+        self.advance_lfo();
+        
+        (left * (1.0 - self.mix) + delayed_left * self.mix,
+         right * (1.0 - self.mix) + delayed_right * self.mix)
+    }
+
+    fn process_block(&mut self, buffer: &mut [f32]) {
+        let len = self.buffer.len();
+        let dry = 1.0 - self.mix;
+        let wet = self.mix;
+        let feedback = self.feedback;
+        let phase_increment = self.lfo_rate / self.sample_rate as f32;
+
+        for frame in buffer.chunks_exact_mut(2) {
+            let (left, right) = (frame[0], frame[1]);
+
+            // The right channel sweeps a quarter cycle behind the left
+            let mut right_phase = self.lfo_phase + STEREO_PHASE_OFFSET;
+            if right_phase >= 1.0 {
+                right_phase -= 1.0;
+            }
+            let delayed_left = self.get_delayed_sample(&self.buffer, self.modulated_delay(self.lfo_phase));
+            let delayed_right = self.get_delayed_sample(&self.buffer_right, self.modulated_delay(right_phase));
+
+            self.buffer[self.write_index] = left + delayed_left * feedback;
+            self.buffer_right[self.write_index] = right + delayed_right * feedback;
+
+            self.write_index += 1;
+            if self.write_index == len {
+                self.write_index = 0;
+            }
+
+            self.lfo_phase += phase_increment;
+            if self.lfo_phase >= 1.0 {
+                self.lfo_phase -= 1.0;
+            }
+
+            frame[0] = left * dry + delayed_left * wet;
+            frame[1] = right * dry + delayed_right * wet;
+        }
+    }
+
     fn reset(&mut self) {
         self.buffer.fill(0.0);
+        self.buffer_right.fill(0.0);
         self.write_index = 0;
         self.lfo_phase = 0.0;
     }
     
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        // Rebuild the delay lines so the modulation range stays the same in milliseconds
+        *self = Self::new(self.lfo_rate, self.depth, self.feedback, self.mix, sample_rate);
+    }
+    
     fn name(&self) -> &str {
         "Flanger"
     }
+    
+    fn parameter_names(&self) -> &[&'static str] {
+        &["Rate", "Depth", "Feedback", "Mix"]
+    }
+    
+    fn parameter(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(((self.lfo_rate - MIN_LFO_RATE_HZ) / (MAX_LFO_RATE_HZ - MIN_LFO_RATE_HZ)).clamp(0.0, 1.0)),
+            1 => Some(self.depth),
+            2 => Some(self.feedback),
+            3 => Some(self.mix),
+            _ => None,
+        }
+    }
+    
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        let value = value.clamp(0.0, 1.0);
+        match index {
+            0 => self.set_lfo_rate(MIN_LFO_RATE_HZ + value * (MAX_LFO_RATE_HZ - MIN_LFO_RATE_HZ)),
+            1 => self.set_depth(value),
+            2 => self.set_feedback(value),
+            3 => self.set_mix(value),
+            _ => {}
+        }
+    }
+    
+    fn parameter_value(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(self.lfo_rate),
+            1 => Some(self.depth),
+            2 => Some(self.feedback),
+            3 => Some(self.mix),
+            _ => None,
+        }
+    }
+    
+    fn set_parameter_value(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_lfo_rate(value),
+            1 => self.set_depth(value),
+            2 => self.set_feedback(value),
+            3 => self.set_mix(value),
+            _ => {}
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,207 @@
+use super::AudioEffect;
+use super::compressor::{db_to_gain, exponential_position, exponential_value, gain_to_db, time_coefficient};
+
+/// Ranges reachable through the normalized parameters
+const MIN_THRESHOLD_DB: f32 = -80.0;
+const MAX_RANGE_DB: f32 = 80.0;
+const MIN_ATTACK_MS: f32 = 0.1;
+const MAX_ATTACK_MS: f32 = 50.0;
+const MAX_HOLD_MS: f32 = 500.0;
+const MIN_RELEASE_MS: f32 = 5.0;
+const MAX_RELEASE_MS: f32 = 2000.0;
+
+/// How far below the threshold the level has to fall before the gate closes again
+const HYSTERESIS_DB: f32 = 3.0;
+
+/// Decay of the level detector, long enough to ride over the zero crossings of low notes
+const DETECTOR_RELEASE_MS: f32 = 20.0;
+
+/// Noise gate effect: turns the signal down by a range while it stays below a threshold
+///
+/// The gate opens as soon as the level crosses the threshold, stays open for the hold
+/// time after it falls back, and then closes over the release time.
+#[derive(Debug, Clone)]
+pub struct GateEffect {
+    threshold_db: f32,    // Level that opens the gate (-80 - 0 dB)
+    range_db: f32,        // How far the closed gate turns the signal down (0 - 80 dB)
+    attack_ms: f32,       // Time to open
+    hold_ms: f32,         // Time the gate stays open after the level drops
+    release_ms: f32,      // Time to close
+    attack_coefficient: f32,
+    release_coefficient: f32,
+    detector_coefficient: f32,
+    level: f32,               // Detected peak level
+    open: bool,
+    hold_remaining: usize,    // Samples left before a closing gate starts to release
+    reduction_db: f32,        // Smoothed gain reduction currently applied
+    sample_rate: u32,
+}
+
+impl GateEffect {
+    /// Create a new noise gate effect
+    ///
+    /// # Parameters
+    /// - `threshold_db`: Level that opens the gate (-80 - 0 dB)
+    /// - `range_db`: Attenuation while closed (0 - 80 dB)
+    /// - `attack_ms`: Attack time in milliseconds (0.1 - 50)
+    /// - `hold_ms`: Hold time in milliseconds (0 - 500)
+    /// - `release_ms`: Release time in milliseconds (5 - 2000)
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(threshold_db: f32, range_db: f32, attack_ms: f32, hold_ms: f32, release_ms: f32, sample_rate: u32) -> Self {
+        let mut gate = Self {
+            threshold_db: threshold_db.clamp(MIN_THRESHOLD_DB, 0.0),
+            range_db: range_db.clamp(0.0, MAX_RANGE_DB),
+            attack_ms,
+            hold_ms: hold_ms.clamp(0.0, MAX_HOLD_MS),
+            release_ms,
+            attack_coefficient: 0.0,
+            release_coefficient: 0.0,
+            detector_coefficient: time_coefficient(DETECTOR_RELEASE_MS, sample_rate),
+            level: 0.0,
+            open: false,
+            hold_remaining: 0,
+            reduction_db: 0.0,
+            sample_rate,
+        };
+        gate.set_attack(attack_ms);
+        gate.set_release(release_ms);
+        gate.reduction_db = gate.range_db;
+        gate
+    }
+
+    /// Gain for the next sample from its detected peak
+    fn next_gain(&mut self, peak: f32) -> f32 {
+        self.level = peak.max(self.level * self.detector_coefficient);
+        let level_db = gain_to_db(self.level);
+
+        if level_db >= self.threshold_db {
+            self.open = true;
+            self.hold_remaining = (self.hold_ms / 1000.0 * self.sample_rate as f32) as usize;
+        } else if self.open && level_db < self.threshold_db - HYSTERESIS_DB {
+            if self.hold_remaining > 0 {
+                self.hold_remaining -= 1;
+            } else {
+                self.open = false;
+            }
+        }
+
+        let (target, coefficient) = if self.open {
+            (0.0, self.attack_coefficient)
+        } else {
+            (self.range_db, self.release_coefficient)
+        };
+        self.reduction_db = target + (self.reduction_db - target) * coefficient;
+        db_to_gain(-self.reduction_db)
+    }
+
+    /// Set threshold in dB (-80 - 0)
+    pub fn set_threshold_db(&mut self, threshold_db: f32) {
+        self.threshold_db = threshold_db.clamp(MIN_THRESHOLD_DB, 0.0);
+    }
+
+    /// Set attenuation while closed in dB (0 - 80)
+    pub fn set_range_db(&mut self, range_db: f32) {
+        self.range_db = range_db.clamp(0.0, MAX_RANGE_DB);
+    }
+
+    /// Set attack time in milliseconds
+    pub fn set_attack(&mut self, attack_ms: f32) {
+        self.attack_ms = attack_ms.clamp(MIN_ATTACK_MS, MAX_ATTACK_MS);
+        self.attack_coefficient = time_coefficient(self.attack_ms, self.sample_rate);
+    }
+
+    /// Set hold time in milliseconds (0 - 500)
+    pub fn set_hold(&mut self, hold_ms: f32) {
+        self.hold_ms = hold_ms.clamp(0.0, MAX_HOLD_MS);
+    }
+
+    /// Set release time in milliseconds
+    pub fn set_release(&mut self, release_ms: f32) {
+        self.release_ms = release_ms.clamp(MIN_RELEASE_MS, MAX_RELEASE_MS);
+        self.release_coefficient = time_coefficient(self.release_ms, self.sample_rate);
+    }
+}
+
+impl AudioEffect for GateEffect {
+    fn process_sample(&mut self, input: f32) -> f32 {
+        input * self.next_gain(input.abs())
+    }
+
+    fn process_frame(&mut self, left: f32, right: f32) -> (f32, f32) {
+        // Linked detection, so both channels open and close together
+        let gain = self.next_gain(left.abs().max(right.abs()));
+        (left * gain, right * gain)
+    }
+
+    fn reset(&mut self) {
+        self.level = 0.0;
+        self.open = false;
+        self.hold_remaining = 0;
+        self.reduction_db = self.range_db;
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+        self.detector_coefficient = time_coefficient(DETECTOR_RELEASE_MS, sample_rate);
+        self.set_attack(self.attack_ms);
+        self.set_release(self.release_ms);
+        self.reset();
+    }
+
+    fn name(&self) -> &str {
+        "Gate"
+    }
+
+    fn gain_reduction_db(&self) -> f32 {
+        self.reduction_db
+    }
+
+    fn parameter_names(&self) -> &[&'static str] {
+        &["Threshold", "Range", "Attack", "Hold", "Release"]
+    }
+
+    fn parameter(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(1.0 - self.threshold_db / MIN_THRESHOLD_DB),
+            1 => Some(self.range_db / MAX_RANGE_DB),
+            2 => Some(exponential_position(self.attack_ms, MIN_ATTACK_MS, MAX_ATTACK_MS)),
+            3 => Some(self.hold_ms / MAX_HOLD_MS),
+            4 => Some(exponential_position(self.release_ms, MIN_RELEASE_MS, MAX_RELEASE_MS)),
+            _ => None,
+        }
+    }
+
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        let value = value.clamp(0.0, 1.0);
+        match index {
+            0 => self.set_threshold_db((1.0 - value) * MIN_THRESHOLD_DB),
+            1 => self.set_range_db(value * MAX_RANGE_DB),
+            2 => self.set_attack(exponential_value(value, MIN_ATTACK_MS, MAX_ATTACK_MS)),
+            3 => self.set_hold(value * MAX_HOLD_MS),
+            4 => self.set_release(exponential_value(value, MIN_RELEASE_MS, MAX_RELEASE_MS)),
+            _ => {}
+        }
+    }
+
+    fn parameter_value(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(self.threshold_db),
+            1 => Some(self.range_db),
+            2 => Some(self.attack_ms),
+            3 => Some(self.hold_ms),
+            4 => Some(self.release_ms),
+            _ => None,
+        }
+    }
+
+    fn set_parameter_value(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_threshold_db(value),
+            1 => self.set_range_db(value),
+            2 => self.set_attack(value),
+            3 => self.set_hold(value),
+            4 => self.set_release(value),
+            _ => {}
+        }
+    }
+}
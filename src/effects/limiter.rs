@@ -0,0 +1,287 @@
+use super::AudioEffect;
+use super::compressor::{db_to_gain, exponential_position, exponential_value, time_coefficient};
+use std::collections::VecDeque;
+
+/// Brickwall peak limiter that keeps the output below a ceiling.
+/// Gain drops instantly on peaks above the ceiling and recovers smoothly afterwards.
+#[derive(Debug, Clone)]
+pub struct BrickwallLimiter {
+    ceiling: f32,              // Maximum output level (linear, 0.0 - 1.0)
+    release_ms: f32,           // Time for the gain to recover after a peak
+    release_coefficient: f32,
+    gain: f32,                 // Current gain applied to the signal
+    sample_rate: u32,
+}
+
+impl BrickwallLimiter {
+    /// Create a new brickwall limiter
+    ///
+    /// # Parameters
+    /// - `ceiling`: Maximum output level (linear, 0.0 - 1.0)
+    /// - `release_ms`: Release time in milliseconds
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(ceiling: f32, release_ms: f32, sample_rate: u32) -> Self {
+        let mut limiter = Self {
+            ceiling: ceiling.clamp(0.01, 1.0),
+            release_ms,
+            release_coefficient: 0.0,
+            gain: 1.0,
+            sample_rate,
+        };
+        limiter.set_release(release_ms);
+        limiter
+    }
+
+    /// Set the output ceiling (linear, 0.0 - 1.0)
+    pub fn set_ceiling(&mut self, ceiling: f32) {
+        self.ceiling = ceiling.clamp(0.01, 1.0);
+    }
+
+    /// Set release time in milliseconds
+    pub fn set_release(&mut self, release_ms: f32) {
+        self.release_ms = release_ms.max(1.0);
+        let release_samples = self.release_ms / 1000.0 * self.sample_rate as f32;
+        self.release_coefficient = (-1.0 / release_samples).exp();
+    }
+
+    /// Follow the detected peak level: instant attack, smooth release
+    fn update_gain(&mut self, peak: f32) {
+        let target_gain = if peak > self.ceiling { self.ceiling / peak } else { 1.0 };
+
+        if target_gain < self.gain {
+            // Instant attack so no peak gets through
+            self.gain = target_gain;
+        } else {
+            // Smooth release back towards unity
+            self.gain = target_gain + (self.gain - target_gain) * self.release_coefficient;
+        }
+    }
+}
+
+impl AudioEffect for BrickwallLimiter {
+    fn process_sample(&mut self, input: f32) -> f32 {
+        self.update_gain(input.abs());
+
+        // Clamp as a final safety net
+        (input * self.gain).clamp(-self.ceiling, self.ceiling)
+    }
+
+    fn process_frame(&mut self, left: f32, right: f32) -> (f32, f32) {
+        // Linked detection keeps the stereo image steady while limiting
+        self.update_gain(left.abs().max(right.abs()));
+
+        ((left * self.gain).clamp(-self.ceiling, self.ceiling),
+         (right * self.gain).clamp(-self.ceiling, self.ceiling))
+    }
+
+    fn reset(&mut self) {
+        self.gain = 1.0;
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+        self.set_release(self.release_ms);
+    }
+
+    fn name(&self) -> &str {
+        "Limiter"
+    }
+
+    fn gain_reduction_db(&self) -> f32 {
+        -20.0 * self.gain.log10()
+    }
+}
+
+/// Ranges reachable through the normalized parameters of the look-ahead limiter
+const MIN_CEILING_DB: f32 = -24.0;
+const MIN_LIMITER_RELEASE_MS: f32 = 1.0;
+const MAX_LIMITER_RELEASE_MS: f32 = 1000.0;
+
+/// Look-ahead peak limiter that keeps the output below a ceiling without clipping the peaks.
+///
+/// The signal is delayed by the look-ahead time, so the gain can ramp down smoothly
+/// before a peak arrives instead of dropping on it instantly as `BrickwallLimiter` does.
+#[derive(Debug, Clone)]
+pub struct LimiterEffect {
+    ceiling: f32,               // Maximum output level (linear)
+    lookahead_ms: f32,
+    release_ms: f32,            // Time for the gain to recover after a peak
+    release_coefficient: f32,
+    lookahead: usize,           // Look-ahead in samples
+    delay_left: VecDeque<f32>,  // Signal waiting for the gain to catch up
+    delay_right: VecDeque<f32>,
+    minimum: VecDeque<(usize, f32)>,  // Candidates for the lowest gain needed within the look-ahead, by sample index
+    envelope: f32,                    // Lowest needed gain, released smoothly
+    ramp: VecDeque<f32>,              // Latest envelope values, averaged into the applied gain
+    ramp_sum: f32,
+    position: usize,                  // Index of the next sample
+    gain: f32,                        // Gain currently applied
+    sample_rate: u32,
+}
+
+impl LimiterEffect {
+    /// Create a new look-ahead limiter
+    ///
+    /// # Parameters
+    /// - `ceiling_db`: Maximum output level in dB (-24 - 0)
+    /// - `lookahead_ms`: Look-ahead, and so the delay the limiter adds, in milliseconds
+    /// - `release_ms`: Release time in milliseconds (1 - 1000)
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(ceiling_db: f32, lookahead_ms: f32, release_ms: f32, sample_rate: u32) -> Self {
+        let mut limiter = Self {
+            ceiling: db_to_gain(ceiling_db.clamp(MIN_CEILING_DB, 0.0)),
+            lookahead_ms: lookahead_ms.max(0.1),
+            release_ms,
+            release_coefficient: 0.0,
+            lookahead: 0,
+            delay_left: VecDeque::new(),
+            delay_right: VecDeque::new(),
+            minimum: VecDeque::new(),
+            envelope: 1.0,
+            ramp: VecDeque::new(),
+            ramp_sum: 0.0,
+            position: 0,
+            gain: 1.0,
+            sample_rate,
+        };
+        limiter.set_release(release_ms);
+        limiter.reset();
+        limiter
+    }
+
+    /// Set the output ceiling in dB (-24 - 0)
+    pub fn set_ceiling_db(&mut self, ceiling_db: f32) {
+        self.ceiling = db_to_gain(ceiling_db.clamp(MIN_CEILING_DB, 0.0));
+    }
+
+    pub fn ceiling_db(&self) -> f32 {
+        20.0 * self.ceiling.log10()
+    }
+
+    /// Set release time in milliseconds
+    pub fn set_release(&mut self, release_ms: f32) {
+        self.release_ms = release_ms.clamp(MIN_LIMITER_RELEASE_MS, MAX_LIMITER_RELEASE_MS);
+        self.release_coefficient = time_coefficient(self.release_ms, self.sample_rate);
+    }
+
+    /// Delay the limiter adds, in samples
+    pub fn latency(&self) -> usize {
+        self.lookahead
+    }
+
+    /// Work out the gain for the sample leaving the delay line, given the peak entering it
+    fn update_gain(&mut self, peak: f32) {
+        let needed = if peak > self.ceiling { self.ceiling / peak } else { 1.0 };
+
+        // Lowest gain needed by any sample still inside the look-ahead
+        while self.minimum.back().is_some_and(|&(_, gain)| gain >= needed) {
+            self.minimum.pop_back();
+        }
+        self.minimum.push_back((self.position, needed));
+        while self.minimum.front().is_some_and(|&(index, _)| index + self.lookahead < self.position) {
+            self.minimum.pop_front();
+        }
+        self.position += 1;
+        let lowest = self.minimum.front().map_or(1.0, |&(_, gain)| gain);
+
+        // Drop with the lowest gain straight away, recover smoothly
+        self.envelope = if lowest < self.envelope {
+            lowest
+        } else {
+            lowest + (self.envelope - lowest) * self.release_coefficient
+        };
+
+        // Averaging over the look-ahead turns the drop into a ramp that ends as the peak leaves the delay line
+        self.ramp.push_back(self.envelope);
+        self.ramp_sum += self.envelope;
+        self.ramp_sum -= self.ramp.pop_front().unwrap_or(1.0);
+        self.gain = (self.ramp_sum / self.lookahead as f32).min(1.0);
+    }
+}
+
+impl AudioEffect for LimiterEffect {
+    fn process_sample(&mut self, input: f32) -> f32 {
+        self.update_gain(input.abs());
+        self.delay_left.push_back(input);
+        let delayed = self.delay_left.pop_front().unwrap_or(0.0);
+
+        // Clamp as a final safety net
+        (delayed * self.gain).clamp(-self.ceiling, self.ceiling)
+    }
+
+    fn process_frame(&mut self, left: f32, right: f32) -> (f32, f32) {
+        // Linked detection keeps the stereo image steady while limiting
+        self.update_gain(left.abs().max(right.abs()));
+        self.delay_left.push_back(left);
+        self.delay_right.push_back(right);
+        let delayed_left = self.delay_left.pop_front().unwrap_or(0.0);
+        let delayed_right = self.delay_right.pop_front().unwrap_or(0.0);
+
+        ((delayed_left * self.gain).clamp(-self.ceiling, self.ceiling),
+         (delayed_right * self.gain).clamp(-self.ceiling, self.ceiling))
+    }
+
+    fn reset(&mut self) {
+        self.lookahead = ((self.lookahead_ms / 1000.0 * self.sample_rate as f32).round() as usize).max(1);
+        self.delay_left = VecDeque::from(vec![0.0; self.lookahead]);
+        self.delay_right = VecDeque::from(vec![0.0; self.lookahead]);
+        self.minimum.clear();
+        self.envelope = 1.0;
+        self.ramp = VecDeque::from(vec![1.0; self.lookahead]);
+        self.ramp_sum = self.lookahead as f32;
+        self.position = 0;
+        self.gain = 1.0;
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+        self.set_release(self.release_ms);
+        self.reset();
+    }
+
+    fn name(&self) -> &str {
+        "Limiter"
+    }
+
+    fn gain_reduction_db(&self) -> f32 {
+        -20.0 * self.gain.log10()
+    }
+
+    fn parameter_names(&self) -> &[&'static str] {
+        &["Ceiling", "Release"]
+    }
+
+    fn parameter(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some((1.0 - self.ceiling_db() / MIN_CEILING_DB).clamp(0.0, 1.0)),
+            1 => Some(exponential_position(self.release_ms, MIN_LIMITER_RELEASE_MS, MAX_LIMITER_RELEASE_MS)),
+            _ => None,
+        }
+    }
+
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        let value = value.clamp(0.0, 1.0);
+        match index {
+            0 => self.set_ceiling_db((1.0 - value) * MIN_CEILING_DB),
+            1 => self.set_release(exponential_value(value, MIN_LIMITER_RELEASE_MS, MAX_LIMITER_RELEASE_MS)),
+            _ => {}
+        }
+    }
+
+    fn parameter_value(&self, index: usize) -> Option<f32> {
+        // The ceiling stays a linear gain so it comes back without a round trip through dB
+        match index {
+            0 => Some(self.ceiling),
+            1 => Some(self.release_ms),
+            _ => None,
+        }
+    }
+
+    fn set_parameter_value(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.ceiling = value,
+            1 => self.set_release(value),
+            _ => {}
+        }
+    }
+}
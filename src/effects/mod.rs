@@ -1,26 +1,268 @@
 use rodio::Source;
+use std::any::Any;
+use std::fmt;
 use std::time::Duration;
+use crate::modulation::EffectSlot;
 
 pub mod delay;
 pub mod reverb;
 pub mod flanger;
+pub mod chorus;
+pub mod phaser;
+pub mod tremolo;
+pub mod oversampling;
+pub mod distortion;
+pub mod bitcrusher;
+pub mod waveshaper;
+pub mod compressor;
+pub mod gate;
+pub mod equalizer;
+pub mod filter;
+pub mod limiter;
 
 pub use delay::DelayEffect;
 pub use reverb::ReverbEffect;
 pub use flanger::FlangerEffect;
+pub use chorus::ChorusEffect;
+pub use phaser::PhaserEffect;
+pub use tremolo::TremoloEffect;
+pub use distortion::{DistortionEffect, DistortionType};
+pub use bitcrusher::BitcrusherEffect;
+pub use waveshaper::{WaveshaperEffect, ShaperCurve};
+pub use filter::{StateVariableFilter, FilterMode};
+pub use limiter::{BrickwallLimiter, LimiterEffect};
+pub use compressor::CompressorEffect;
+pub use gate::GateEffect;
+pub use equalizer::EqualizerEffect;
 
 /// Trait that all audio effects must implement
-pub trait AudioEffect: Send + Sync {
+pub trait AudioEffect: EffectObject + fmt::Debug + Send + Sync {
     /// Process a single audio sample
     fn process_sample(&mut self, input: f32) -> f32;
     
+    /// Process a stereo frame
+    ///
+    /// Effects without a true stereo implementation process the mono sum and return it on both channels.
+    fn process_frame(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let output = self.process_sample((left + right) * 0.5);
+        (output, output)
+    }
+    
+    /// Process a block of interleaved left/right frames in place
+    ///
+    /// Falls back to processing frame by frame. Effects override this to keep their
+    /// parameters and delay line positions in locals across the whole block.
+    fn process_block(&mut self, buffer: &mut [f32]) {
+        for frame in buffer.chunks_exact_mut(2) {
+            let (left, right) = self.process_frame(frame[0], frame[1]);
+            frame[0] = left;
+            frame[1] = right;
+        }
+    }
+    
     /// Reset the effect's internal state
     fn reset(&mut self);
     
+    /// Adapt to a new sample rate, rebuilding any delay lines and coefficients
+    fn set_sample_rate(&mut self, sample_rate: u32);
+    
+    /// Names of the parameters the modulation matrix can reach, in index order
+    fn parameter_names(&self) -> &[&'static str] {
+        &[]
+    }
+    
+    /// Value of a parameter on a normalized scale (0.0 - 1.0)
+    fn parameter(&self, _index: usize) -> Option<f32> {
+        None
+    }
+    
+    /// Set a parameter from a normalized value (0.0 - 1.0)
+    fn set_parameter(&mut self, _index: usize, _value: f32) {}
+    
+    /// Whether modulating a parameter changes the sound, so the route editor offers it
+    fn is_modulatable(&self, _index: usize) -> bool {
+        true
+    }
+    
+    /// Value of a parameter as the effect stores it, in its own unit
+    ///
+    /// Unlike the normalized value this keeps settings outside the modulation range, so
+    /// modulation can put a parameter back exactly as it found it. Effects whose
+    /// parameters are stored normalized keep the default.
+    fn parameter_value(&self, index: usize) -> Option<f32> {
+        self.parameter(index)
+    }
+    
+    /// Set a parameter from a value returned by `parameter_value`
+    fn set_parameter_value(&mut self, index: usize, value: f32) {
+        self.set_parameter(index, value);
+    }
+    
+    /// Current gain reduction in dB, for metering (0.0 for effects that are not dynamics processors)
+    fn gain_reduction_db(&self) -> f32 {
+        0.0
+    }
+    
+    /// The effect as a sidechain effect, if its level detector can follow a key signal
+    fn as_sidechain_mut(&mut self) -> Option<&mut dyn SidechainEffect> {
+        None
+    }
+    
     /// Get the effect's name
     fn name(&self) -> &str;
 }
 
+/// Cloning and downcasting of effects kept as trait objects
+///
+/// Implemented for every cloneable effect, so effect chains can be copied and their
+/// effects reached by type for settings the normalized parameters don't cover.
+pub trait EffectObject {
+    fn clone_box(&self) -> Box<dyn AudioEffect>;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<E: AudioEffect + Clone + 'static> EffectObject for E {
+    fn clone_box(&self) -> Box<dyn AudioEffect> {
+        Box::new(self.clone())
+    }
+    
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Clone for Box<dyn AudioEffect> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// Effect in a chain, keyed by its modulation slot and bypassed while disabled
+#[derive(Debug, Clone)]
+pub struct ChainedEffect {
+    pub slot: EffectSlot,
+    pub enabled: bool,
+    pub effect: Box<dyn AudioEffect>,
+}
+
+/// Chain multiple effects together
+#[derive(Debug, Clone, Default)]
+pub struct EffectChain {
+    effects: Vec<ChainedEffect>,
+}
+
+impl EffectChain {
+    pub fn new() -> Self {
+        Self { effects: Vec::new() }
+    }
+    
+    /// Append an effect, disabled until switched on
+    pub fn add_effect(&mut self, slot: EffectSlot, effect: Box<dyn AudioEffect>) {
+        self.effects.push(ChainedEffect { slot, enabled: false, effect });
+    }
+    
+    /// Run a block of interleaved left/right frames through every enabled effect in turn
+    pub fn process_block(&mut self, buffer: &mut [f32]) {
+        for chained in self.effects.iter_mut().filter(|chained| chained.enabled) {
+            chained.effect.process_block(buffer);
+        }
+    }
+    
+    pub fn reset(&mut self) {
+        for chained in &mut self.effects {
+            chained.effect.reset();
+        }
+    }
+    
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        for chained in &mut self.effects {
+            chained.effect.set_sample_rate(sample_rate);
+        }
+    }
+    
+    /// Deepest gain reduction of the enabled effects in dB, for metering
+    pub fn gain_reduction_db(&self) -> f32 {
+        self.effects.iter()
+            .filter(|chained| chained.enabled)
+            .map(|chained| chained.effect.gain_reduction_db())
+            .fold(0.0, f32::max)
+    }
+    
+    /// Effects in processing order
+    pub fn iter(&self) -> impl Iterator<Item = &ChainedEffect> {
+        self.effects.iter()
+    }
+    
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut ChainedEffect> {
+        self.effects.iter_mut()
+    }
+    
+    pub fn slot(&self, slot: EffectSlot) -> Option<&ChainedEffect> {
+        self.effects.iter().find(|chained| chained.slot == slot)
+    }
+    
+    pub fn slot_mut(&mut self, slot: EffectSlot) -> Option<&mut ChainedEffect> {
+        self.effects.iter_mut().find(|chained| chained.slot == slot)
+    }
+    
+    pub fn is_enabled(&self, slot: EffectSlot) -> bool {
+        self.slot(slot).is_some_and(|chained| chained.enabled)
+    }
+    
+    /// Switch an effect on or off, clearing its state when it goes off so it comes back silent
+    ///
+    /// Returns whether the effect is now enabled.
+    pub fn toggle(&mut self, slot: EffectSlot) -> bool {
+        match self.slot_mut(slot) {
+            Some(chained) => {
+                chained.enabled = !chained.enabled;
+                if !chained.enabled {
+                    chained.effect.reset();
+                }
+                chained.enabled
+            }
+            None => false,
+        }
+    }
+    
+    /// The first effect of a type in the chain, for settings beyond its normalized parameters
+    ///
+    /// Panics when the chain holds no effect of the type; track and master chains hold one of each.
+    pub fn get<E: AudioEffect + 'static>(&self) -> &E {
+        self.effects.iter()
+            .find_map(|chained| chained.effect.as_any().downcast_ref::<E>())
+            .expect("effect chain holds every effect type")
+    }
+    
+    pub fn get_mut<E: AudioEffect + 'static>(&mut self) -> &mut E {
+        self.effects.iter_mut()
+            .find_map(|chained| chained.effect.as_any_mut().downcast_mut::<E>())
+            .expect("effect chain holds every effect type")
+    }
+}
+
+/// Effect whose level detector can follow a separate key signal instead of its own input
+///
+/// Used for sidechaining, such as a compressor on one track ducking under another track.
+pub trait SidechainEffect: AudioEffect {
+    /// Process a stereo frame, detecting the level of the key frame
+    fn process_frame_keyed(&mut self, left: f32, right: f32, key_left: f32, key_right: f32) -> (f32, f32);
+    
+    /// Process a block of interleaved left/right frames in place, keyed by a block of the same layout
+    fn process_block_keyed(&mut self, buffer: &mut [f32], key: &[f32]) {
+        for (frame, key_frame) in buffer.chunks_exact_mut(2).zip(key.chunks_exact(2)) {
+            let (left, right) = self.process_frame_keyed(frame[0], frame[1], key_frame[0], key_frame[1]);
+            frame[0] = left;
+            frame[1] = right;
+        }
+    }
+}
+
 /// Wrapper that applies an effect to any audio source
 pub struct EffectWrapper<S, E> 
 where
@@ -73,34 +315,4 @@ where
     fn total_duration(&self) -> Option<Duration> {
         self.source.total_duration()
     }
-}
-
-/// Chain multiple effects together
-pub struct EffectChain {
-    effects: Vec<Box<dyn AudioEffect>>,
-}
-
-impl EffectChain {
-    pub fn new() -> Self {
-        Self {
-            effects: Vec::new(),
-        }
-    }
-    
-    pub fn add_effect(&mut self, effect: Box<dyn AudioEffect>) {
-        self.effects.push(effect);
-    }
-    
-    pub fn process_sample(&mut self, mut input: f32) -> f32 {
-        for effect in &mut self.effects {
-            input = effect.process_sample(input);
-        }
-        input
-    }
-    
-    pub fn reset(&mut self) {
-        for effect in &mut self.effects {
-            effect.reset();
-        }
-    }
 }
\ No newline at end of file
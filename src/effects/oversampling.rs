@@ -0,0 +1,133 @@
+use std::f32::consts::PI;
+use std::fmt;
+
+/// Filter taps per step of the oversampling factor, also the latency in samples at the engine rate
+const TAPS_PER_FACTOR: usize = 16;
+
+/// Cutoff of the anti-aliasing filter as a fraction of the original Nyquist frequency
+const CUTOFF: f32 = 0.9;
+
+/// Rate a nonlinear stage runs at, relative to the engine rate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Oversampling {
+    Off,
+    X2,
+    X4,
+}
+
+impl Oversampling {
+    pub fn factor(&self) -> usize {
+        match self {
+            Oversampling::Off => 1,
+            Oversampling::X2 => 2,
+            Oversampling::X4 => 4,
+        }
+    }
+
+    /// The next setting in the cycle, wrapping around
+    pub fn next(&self) -> Oversampling {
+        match self {
+            Oversampling::Off => Oversampling::X2,
+            Oversampling::X2 => Oversampling::X4,
+            Oversampling::X4 => Oversampling::Off,
+        }
+    }
+}
+
+impl fmt::Display for Oversampling {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Oversampling::Off => write!(f, "off"),
+            Oversampling::X2 => write!(f, "2x"),
+            Oversampling::X4 => write!(f, "4x"),
+        }
+    }
+}
+
+/// Runs a nonlinear function at a multiple of the sample rate for one channel
+///
+/// The input is upsampled through a windowed-sinc lowpass, shaped, then filtered again
+/// and decimated, so the harmonics the shaping adds above Nyquist are removed instead of
+/// folding back as aliasing. Costs a delay of `latency()` samples at the engine rate.
+#[derive(Debug, Clone)]
+pub struct Oversampler {
+    oversampling: Oversampling,
+    taps: Vec<f32>,
+    input_history: Vec<f32>,   // Latest input samples, newest first
+    output_history: Vec<f32>,  // Latest shaped samples at the oversampled rate, newest first
+}
+
+impl Oversampler {
+    pub fn new(oversampling: Oversampling) -> Self {
+        let factor = oversampling.factor();
+        let length = TAPS_PER_FACTOR * factor + 1; // Odd, so the filters delay by whole samples
+        let taps = if factor > 1 { lowpass_taps(length, CUTOFF * 0.5 / factor as f32) } else { Vec::new() };
+
+        Self {
+            oversampling,
+            taps,
+            input_history: vec![0.0; TAPS_PER_FACTOR + 1],
+            output_history: vec![0.0; length],
+        }
+    }
+
+    pub fn oversampling(&self) -> Oversampling {
+        self.oversampling
+    }
+
+    /// Delay of the filters in samples at the engine rate
+    pub fn latency(&self) -> usize {
+        if self.oversampling == Oversampling::Off { 0 } else { TAPS_PER_FACTOR }
+    }
+
+    /// Shape one input sample, running `shape` once per oversampled sample
+    pub fn process(&mut self, input: f32, mut shape: impl FnMut(f32) -> f32) -> f32 {
+        let factor = self.oversampling.factor();
+        if factor == 1 {
+            return shape(input);
+        }
+
+        self.input_history.rotate_right(1);
+        self.input_history[0] = input;
+
+        let mut output = 0.0;
+        for phase in 0..factor {
+            // Polyphase interpolation: only every factor-th tap meets a sample of the zero-stuffed input
+            let upsampled: f32 = self.input_history.iter()
+                .zip(self.taps.iter().skip(phase).step_by(factor))
+                .map(|(sample, tap)| sample * tap)
+                .sum();
+
+            self.output_history.rotate_right(1);
+            self.output_history[0] = shape(upsampled * factor as f32);
+
+            // Decimate on the phase aligned with the input sample
+            if phase == 0 {
+                output = self.output_history.iter().zip(&self.taps).map(|(sample, tap)| sample * tap).sum();
+            }
+        }
+        output
+    }
+
+    pub fn reset(&mut self) {
+        self.input_history.fill(0.0);
+        self.output_history.fill(0.0);
+    }
+}
+
+/// Blackman-windowed sinc lowpass with unity gain at DC, `cutoff` in cycles per sample
+fn lowpass_taps(length: usize, cutoff: f32) -> Vec<f32> {
+    let center = (length - 1) as f32 / 2.0;
+    let taps: Vec<f32> = (0..length)
+        .map(|i| {
+            let x = i as f32 - center;
+            let sinc = if x == 0.0 { 2.0 * cutoff } else { (2.0 * PI * cutoff * x).sin() / (PI * x) };
+            let window_phase = 2.0 * PI * i as f32 / (length - 1) as f32;
+            let window = 0.42 - 0.5 * window_phase.cos() + 0.08 * (2.0 * window_phase).cos();
+            sinc * window
+        })
+        .collect();
+
+    let sum: f32 = taps.iter().sum();
+    taps.into_iter().map(|tap| tap / sum).collect()
+}
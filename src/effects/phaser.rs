@@ -0,0 +1,211 @@
+use super::AudioEffect;
+use std::f32::consts::PI;
+use crate::waveforms::sine_wave::sine_at;
+
+/// Most all-pass stages a phaser can cascade
+pub const MAX_PHASER_STAGES: usize = 12;
+
+/// LFO phase offset of the right channel, a quarter cycle sweeps the channels apart
+const STEREO_PHASE_OFFSET: f32 = 0.25;
+
+/// Range the all-pass break frequency sweeps over at full depth
+const MIN_SWEEP_HZ: f32 = 200.0;
+const MAX_SWEEP_HZ: f32 = 4000.0;
+
+/// LFO rates reachable through the normalized rate parameter
+const MIN_LFO_RATE_HZ: f32 = 0.05;
+const MAX_LFO_RATE_HZ: f32 = 5.0;
+
+/// Phaser effect using a cascade of first-order all-pass stages with feedback
+///
+/// Mixing the phase-shifted signal with the dry one cuts a notch for every two stages,
+/// and an LFO sweeps the notches up and down the spectrum.
+#[derive(Debug, Clone)]
+pub struct PhaserEffect {
+    stages: usize,                           // All-pass stages in the cascade (2 - MAX_PHASER_STAGES)
+    states: [f32; MAX_PHASER_STAGES],        // All-pass state of each stage
+    states_right: [f32; MAX_PHASER_STAGES],  // Right channel stages for stereo processing
+    last_output: f32,                        // Cascade output fed back into its input
+    last_output_right: f32,
+
+    // LFO (Low Frequency Oscillator) sweeping the stages
+    lfo_phase: f32,
+    lfo_rate: f32,        // LFO frequency in Hz
+
+    // Parameters
+    depth: f32,           // Sweep depth (0.0 - 1.0)
+    feedback: f32,        // Feedback amount (0.0 - 0.95)
+    mix: f32,             // Dry/wet mix (0.0 - 1.0)
+
+    sample_rate: u32,
+}
+
+impl PhaserEffect {
+    /// Create a new phaser effect with six stages
+    ///
+    /// # Parameters
+    /// - `lfo_rate`: LFO rate in Hz (typically 0.1 - 2.0)
+    /// - `depth`: Sweep depth (0.0 - 1.0)
+    /// - `feedback`: Feedback amount (0.0 - 0.95)
+    /// - `mix`: Dry/wet mix (0.0 - 1.0), deepest notches at 0.5
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(lfo_rate: f32, depth: f32, feedback: f32, mix: f32, sample_rate: u32) -> Self {
+        Self {
+            stages: 6,
+            states: [0.0; MAX_PHASER_STAGES],
+            states_right: [0.0; MAX_PHASER_STAGES],
+            last_output: 0.0,
+            last_output_right: 0.0,
+            lfo_phase: 0.0,
+            lfo_rate: lfo_rate.max(0.01),
+            depth: depth.clamp(0.0, 1.0),
+            feedback: feedback.clamp(0.0, 0.95),
+            mix: mix.clamp(0.0, 1.0),
+            sample_rate,
+        }
+    }
+
+    /// All-pass coefficient for the break frequency at an LFO phase (0 to 1)
+    ///
+    /// The frequency sweeps exponentially, so the notches move evenly in pitch.
+    fn coefficient(&self, lfo_phase: f32) -> f32 {
+        let sweep = (sine_at(lfo_phase) * 0.5 + 0.5) * self.depth;
+        let frequency = MIN_SWEEP_HZ * (MAX_SWEEP_HZ / MIN_SWEEP_HZ).powf(sweep);
+        let tangent = (PI * frequency.min(self.sample_rate as f32 * 0.45) / self.sample_rate as f32).tan();
+        (tangent - 1.0) / (tangent + 1.0)
+    }
+
+    /// Run a sample through the all-pass cascade of one channel
+    fn cascade(states: &mut [f32], coefficient: f32, input: f32) -> f32 {
+        states.iter_mut().fold(input, |sample, state| {
+            let output = coefficient * sample + *state;
+            *state = sample - coefficient * output;
+            output
+        })
+    }
+
+    /// Advance the LFO by one sample
+    fn advance_lfo(&mut self) {
+        self.lfo_phase += self.lfo_rate / self.sample_rate as f32;
+        if self.lfo_phase >= 1.0 {
+            self.lfo_phase -= 1.0;
+        }
+    }
+
+    /// Set LFO rate in Hz
+    pub fn set_lfo_rate(&mut self, rate: f32) {
+        self.lfo_rate = rate.max(0.01);
+    }
+
+    /// Set sweep depth (0.0 - 1.0)
+    pub fn set_depth(&mut self, depth: f32) {
+        self.depth = depth.clamp(0.0, 1.0);
+    }
+
+    /// Set feedback amount (0.0 - 0.95)
+    pub fn set_feedback(&mut self, feedback: f32) {
+        self.feedback = feedback.clamp(0.0, 0.95);
+    }
+
+    /// Set dry/wet mix (0.0 - 1.0)
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+
+    /// Set the number of all-pass stages, rounded down to an even count (2 - 12)
+    pub fn set_stages(&mut self, stages: usize) {
+        self.stages = (stages.clamp(2, MAX_PHASER_STAGES) / 2) * 2;
+    }
+
+    pub fn stages(&self) -> usize {
+        self.stages
+    }
+}
+
+impl AudioEffect for PhaserEffect {
+    fn process_sample(&mut self, input: f32) -> f32 {
+        let coefficient = self.coefficient(self.lfo_phase);
+        let wet = Self::cascade(&mut self.states[..self.stages], coefficient, input + self.last_output * self.feedback);
+        self.last_output = wet;
+        self.advance_lfo();
+
+        input * (1.0 - self.mix) + wet * self.mix
+    }
+
+    fn process_frame(&mut self, left: f32, right: f32) -> (f32, f32) {
+        // The right channel sweeps a quarter cycle behind the left
+        let coefficient_left = self.coefficient(self.lfo_phase);
+        let coefficient_right = self.coefficient((self.lfo_phase + STEREO_PHASE_OFFSET) % 1.0);
+
+        let wet_left = Self::cascade(&mut self.states[..self.stages], coefficient_left, left + self.last_output * self.feedback);
+        let wet_right = Self::cascade(&mut self.states_right[..self.stages], coefficient_right, right + self.last_output_right * self.feedback);
+        self.last_output = wet_left;
+        self.last_output_right = wet_right;
+        self.advance_lfo();
+
+        (left * (1.0 - self.mix) + wet_left * self.mix,
+         right * (1.0 - self.mix) + wet_right * self.mix)
+    }
+
+    fn reset(&mut self) {
+        self.states = [0.0; MAX_PHASER_STAGES];
+        self.states_right = [0.0; MAX_PHASER_STAGES];
+        self.last_output = 0.0;
+        self.last_output_right = 0.0;
+        self.lfo_phase = 0.0;
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+        self.reset();
+    }
+
+    fn name(&self) -> &str {
+        "Phaser"
+    }
+
+    fn parameter_names(&self) -> &[&'static str] {
+        &["Rate", "Depth", "Feedback", "Mix"]
+    }
+
+    fn parameter(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(((self.lfo_rate - MIN_LFO_RATE_HZ) / (MAX_LFO_RATE_HZ - MIN_LFO_RATE_HZ)).clamp(0.0, 1.0)),
+            1 => Some(self.depth),
+            2 => Some(self.feedback / 0.95),
+            3 => Some(self.mix),
+            _ => None,
+        }
+    }
+
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        let value = value.clamp(0.0, 1.0);
+        match index {
+            0 => self.set_lfo_rate(MIN_LFO_RATE_HZ + value * (MAX_LFO_RATE_HZ - MIN_LFO_RATE_HZ)),
+            1 => self.set_depth(value),
+            2 => self.set_feedback(value * 0.95),
+            3 => self.set_mix(value),
+            _ => {}
+        }
+    }
+
+    fn parameter_value(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(self.lfo_rate),
+            1 => Some(self.depth),
+            2 => Some(self.feedback),
+            3 => Some(self.mix),
+            _ => None,
+        }
+    }
+
+    fn set_parameter_value(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_lfo_rate(value),
+            1 => self.set_depth(value),
+            2 => self.set_feedback(value),
+            3 => self.set_mix(value),
+            _ => {}
+        }
+    }
+}
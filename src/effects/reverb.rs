@@ -1,88 +1,59 @@
 use super::AudioEffect;
 
-/// Simple reverb effect using multiple delay lines (Schroeder reverb)
+/// Number of comb filters in each tank
+const COMB_COUNT: usize = 8;
+
+/// Comb filter delay times - carefully tuned for musical intervals
+const COMB_DELAYS_MS: [f32; COMB_COUNT] = [29.7, 37.1, 41.1, 43.7, 47.0, 50.3, 53.5, 56.3];
+
+/// All-pass filter delay times for diffusion
+const ALLPASS_DELAYS_MS: [f32; 6] = [5.0, 1.7, 12.9, 9.3, 15.1, 8.2];
+
+/// Extra delay of the right channel's lines, spreading the two tanks apart for a wide stereo image
+const STEREO_SPREAD_MS: f32 = 0.52;
+
+/// Comb and all-pass delay lines of one reverb channel
 #[derive(Debug, Clone)]
-pub struct ReverbEffect {
+struct ReverbTank {
     // Comb filters (feedback delay lines)
     comb_delays: Vec<Vec<f32>>,
     comb_indices: Vec<usize>,
-    comb_feedback: Vec<f32>,
     
     // All-pass filters
     allpass_delays: Vec<Vec<f32>>,
     allpass_indices: Vec<usize>,
-    allpass_feedback: f32,
-    
-    // Parameters
-    room_size: f32,   // 0.0 - 1.0
-    damping: f32,     // 0.0 - 1.0
-    mix: f32,         // 0.0 - 1.0
     
     // Low-pass filter for damping
     damping_filter: f32,
 }
 
-impl ReverbEffect {
-    /// Create a new reverb effect
-    /// 
-    /// # Parameters
-    /// - `room_size`: Size of the room (0.0 - 1.0)
-    /// - `damping`: High frequency damping (0.0 - 1.0)
-    /// - `mix`: Dry/wet mix (0.0 - 1.0)
-    /// - `sample_rate`: Audio sample rate
-    pub fn new(room_size: f32, damping: f32, mix: f32, sample_rate: u32) -> Self {
-        // Enhanced comb filter delay times - carefully tuned for musical intervals
-        let comb_delays_ms = [29.7, 37.1, 41.1, 43.7, 47.0, 50.3, 53.5, 56.3];
-        let base_feedback = 0.6 + room_size * 0.35; // Increased feedback for fuller sound
-        
-        let mut comb_delays = Vec::new();
-        let mut comb_feedback = Vec::new();
-        
-        for (i, &delay_ms) in comb_delays_ms.iter().enumerate() {
-            let delay_samples = ((delay_ms / 1000.0) * sample_rate as f32) as usize;
-            comb_delays.push(vec![0.0; delay_samples.max(1)]);
-            // Vary feedback slightly for each comb filter
-            let feedback_variation = 1.0 + (i as f32 * 0.02 - 0.07);
-            comb_feedback.push(base_feedback * feedback_variation);
-        }
-        
-        // Enhanced all-pass filter delays for better diffusion
-        let allpass_delays_ms = [5.0, 1.7, 12.9, 9.3, 15.1, 8.2];
-        let mut allpass_delays = Vec::new();
-        
-        for &delay_ms in &allpass_delays_ms {
-            let delay_samples = ((delay_ms / 1000.0) * sample_rate as f32) as usize;
-            allpass_delays.push(vec![0.0; delay_samples.max(1)]);
-        }
+impl ReverbTank {
+    fn new(comb_delays_ms: &[f32], allpass_delays_ms: &[f32], spread_ms: f32, sample_rate: u32) -> Self {
+        let to_samples = |delay_ms: f32| (((delay_ms + spread_ms) / 1000.0) * sample_rate as f32) as usize;
         
         Self {
-            comb_delays,
+            comb_delays: comb_delays_ms.iter().map(|&ms| vec![0.0; to_samples(ms).max(1)]).collect(),
             comb_indices: vec![0; comb_delays_ms.len()],
-            comb_feedback,
-            allpass_delays,
+            allpass_delays: allpass_delays_ms.iter().map(|&ms| vec![0.0; to_samples(ms).max(1)]).collect(),
             allpass_indices: vec![0; allpass_delays_ms.len()],
-            allpass_feedback: 0.618, // Golden ratio for more natural sound
-            room_size: room_size.clamp(0.0, 1.0),
-            damping: damping.clamp(0.0, 1.0),
-            mix: mix.clamp(0.0, 1.0),
             damping_filter: 0.0,
         }
     }
     
     /// Process sample through comb filters with improved damping
-    fn process_comb_filters(&mut self, input: f32) -> f32 {
+    fn process_comb_filters(&mut self, input: f32, comb_feedback: &[f32], damping: f32) -> f32 {
         let mut output = 0.0;
         
         for i in 0..self.comb_delays.len() {
             let delay_line = &mut self.comb_delays[i];
             let index = &mut self.comb_indices[i];
-            let feedback = self.comb_feedback[i];
+            let feedback = comb_feedback[i];
             
             // Read delayed sample
             let delayed = delay_line[*index];
             
             // Apply consistent damping across all comb filters
-            self.damping_filter = delayed * (1.0 - self.damping) + self.damping_filter * self.damping;
+            self.damping_filter = delayed * (1.0 - damping) + self.damping_filter * damping;
             
             // Write new sample with feedback
             delay_line[*index] = input + self.damping_filter * feedback;
@@ -98,7 +69,7 @@ impl ReverbEffect {
     }
     
     /// Process sample through all-pass filters
-    fn process_allpass_filters(&mut self, mut input: f32) -> f32 {
+    fn process_allpass_filters(&mut self, mut input: f32, allpass_feedback: f32) -> f32 {
         for i in 0..self.allpass_delays.len() {
             let delay_line = &mut self.allpass_delays[i];
             let index = &mut self.allpass_indices[i];
@@ -108,7 +79,7 @@ impl ReverbEffect {
             
             // All-pass filter calculation
             let output = -input + delayed;
-            delay_line[*index] = input + delayed * self.allpass_feedback;
+            delay_line[*index] = input + delayed * allpass_feedback;
             
             // Advance index (circular)
             *index = (*index + 1) % delay_line.len();
@@ -119,14 +90,126 @@ impl ReverbEffect {
         input
     }
     
+    /// Process a block of mono input into the tank's wet output
+    ///
+    /// The combs share the damping filter so they run sample by sample, while each
+    /// all-pass stage is independent and runs over the whole block before the next.
+    fn process_block(&mut self, input: &[f32], output: &mut [f32], comb_feedback: &[f32], damping: f32, allpass_feedback: f32) {
+        let comb_count = self.comb_delays.len() as f32;
+        let mut damping_filter = self.damping_filter;
+
+        for (out, &sample) in output.iter_mut().zip(input) {
+            let mut sum = 0.0;
+
+            for ((delay_line, index), &feedback) in self.comb_delays.iter_mut().zip(self.comb_indices.iter_mut()).zip(comb_feedback) {
+                let delayed = delay_line[*index];
+                damping_filter = delayed * (1.0 - damping) + damping_filter * damping;
+                delay_line[*index] = sample + damping_filter * feedback;
+
+                *index += 1;
+                if *index == delay_line.len() {
+                    *index = 0;
+                }
+
+                sum += delayed;
+            }
+
+            *out = sum / comb_count;
+        }
+
+        self.damping_filter = damping_filter;
+
+        for (delay_line, index) in self.allpass_delays.iter_mut().zip(self.allpass_indices.iter_mut()) {
+            for sample in output.iter_mut() {
+                let delayed = delay_line[*index];
+                let allpass_output = -*sample + delayed;
+                delay_line[*index] = *sample + delayed * allpass_feedback;
+
+                *index += 1;
+                if *index == delay_line.len() {
+                    *index = 0;
+                }
+
+                *sample = allpass_output;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        // Clear all delay lines
+        for delay_line in &mut self.comb_delays {
+            delay_line.fill(0.0);
+        }
+        for delay_line in &mut self.allpass_delays {
+            delay_line.fill(0.0);
+        }
+        
+        // Reset indices
+        self.comb_indices.fill(0);
+        self.allpass_indices.fill(0);
+        
+        // Reset damping filter
+        self.damping_filter = 0.0;
+    }
+}
+
+/// Feedback of each comb filter for a room size (0.0 - 1.0)
+fn comb_feedback_for(room_size: f32) -> [f32; COMB_COUNT] {
+    let base_feedback = 0.6 + room_size * 0.35; // Increased feedback for fuller sound
+    
+    // Vary feedback slightly for each comb filter
+    std::array::from_fn(|i| base_feedback * (1.0 + (i as f32 * 0.02 - 0.07)))
+}
+
+/// Simple reverb effect using multiple delay lines (Schroeder reverb)
+#[derive(Debug, Clone)]
+pub struct ReverbEffect {
+    // Left (and mono) tank, plus a slightly longer right tank for stereo width
+    left: ReverbTank,
+    right: ReverbTank,
+    comb_feedback: [f32; COMB_COUNT],
+    allpass_feedback: f32,
+    
+    // Block buffers, grown to the largest block seen so processing doesn't allocate
+    block_input: Vec<f32>,
+    block_wet_left: Vec<f32>,
+    block_wet_right: Vec<f32>,
+    
+    // Parameters
+    room_size: f32,   // 0.0 - 1.0
+    damping: f32,     // 0.0 - 1.0
+    mix: f32,         // 0.0 - 1.0
+    width: f32,       // Stereo width (0.0 = mono, 1.0 = fully decorrelated)
+}
+
+impl ReverbEffect {
+    /// Create a new reverb effect
+    /// 
+    /// # Parameters
+    /// - `room_size`: Size of the room (0.0 - 1.0)
+    /// - `damping`: High frequency damping (0.0 - 1.0)
+    /// - `mix`: Dry/wet mix (0.0 - 1.0)
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(room_size: f32, damping: f32, mix: f32, sample_rate: u32) -> Self {
+        Self {
+            left: ReverbTank::new(&COMB_DELAYS_MS, &ALLPASS_DELAYS_MS, 0.0, sample_rate),
+            right: ReverbTank::new(&COMB_DELAYS_MS, &ALLPASS_DELAYS_MS, STEREO_SPREAD_MS, sample_rate),
+            comb_feedback: comb_feedback_for(room_size),
+            allpass_feedback: 0.618, // Golden ratio for more natural sound
+            block_input: Vec::new(),
+            block_wet_left: Vec::new(),
+            block_wet_right: Vec::new(),
+            room_size: room_size.clamp(0.0, 1.0),
+            damping: damping.clamp(0.0, 1.0),
+            mix: mix.clamp(0.0, 1.0),
+            width: 1.0,
+        }
+    }
+    
     /// Set room size (0.0 - 1.0)
     pub fn set_room_size(&mut self, room_size: f32) {
         self.room_size = room_size.clamp(0.0, 1.0);
-        let base_feedback = 0.5 + self.room_size * 0.28;
-        
-        for feedback in &mut self.comb_feedback {
-            *feedback = base_feedback;
-        }
+        self.comb_feedback = comb_feedback_for(self.room_size);
     }
     
     /// Set damping (0.0 - 1.0)
@@ -138,38 +221,101 @@ impl ReverbEffect {
     pub fn set_mix(&mut self, mix: f32) {
         self.mix = mix.clamp(0.0, 1.0);
     }
+    
+    /// Set stereo width (0.0 - 1.0)
+    pub fn set_width(&mut self, width: f32) {
+        self.width = width.clamp(0.0, 1.0);
+    }
 }
 
 impl AudioEffect for ReverbEffect {
     fn process_sample(&mut self, input: f32) -> f32 {
         // Process through comb filters
-        let comb_output = self.process_comb_filters(input);
+        let comb_output = self.left.process_comb_filters(input, &self.comb_feedback, self.damping);
         
         // Process through all-pass filters
-        let reverb_output = self.process_allpass_filters(comb_output);
+        let reverb_output = self.left.process_allpass_filters(comb_output, self.allpass_feedback);
         
         // Mix dry and wet signals
         input * (1.0 - self.mix) + reverb_output * self.mix
     }
     
-    fn reset(&mut self) {
-        // Clear all delay lines
-        for delay_line in &mut self.comb_delays {
-            delay_line.fill(0.0);
-        }
-        for delay_line in &mut self.allpass_delays {
-            delay_line.fill(0.0);
-        }
+    fn process_frame(&mut self, left: f32, right: f32) -> (f32, f32) {
+        // Both tanks are fed the mono sum, their different lengths decorrelate the tails
+        let input = (left + right) * 0.5;
         
-        // Reset indices
-        self.comb_indices.fill(0);
-        self.allpass_indices.fill(0);
+        let comb_left = self.left.process_comb_filters(input, &self.comb_feedback, self.damping);
+        let wet_left = self.left.process_allpass_filters(comb_left, self.allpass_feedback);
+        let comb_right = self.right.process_comb_filters(input, &self.comb_feedback, self.damping);
+        let wet_right = self.right.process_allpass_filters(comb_right, self.allpass_feedback);
         
-        // Reset damping filter
-        self.damping_filter = 0.0;
+        // Blend the tanks across channels according to the width
+        let direct = 0.5 + self.width * 0.5;
+        let cross = 0.5 - self.width * 0.5;
+        
+        (left * (1.0 - self.mix) + (wet_left * direct + wet_right * cross) * self.mix,
+         right * (1.0 - self.mix) + (wet_right * direct + wet_left * cross) * self.mix)
+    }
+
+    fn process_block(&mut self, buffer: &mut [f32]) {
+        let frames = buffer.len() / 2;
+        self.block_input.resize(frames, 0.0);
+        self.block_wet_left.resize(frames, 0.0);
+        self.block_wet_right.resize(frames, 0.0);
+
+        for (input, frame) in self.block_input.iter_mut().zip(buffer.chunks_exact(2)) {
+            *input = (frame[0] + frame[1]) * 0.5;
+        }
+
+        self.left.process_block(&self.block_input, &mut self.block_wet_left, &self.comb_feedback, self.damping, self.allpass_feedback);
+        self.right.process_block(&self.block_input, &mut self.block_wet_right, &self.comb_feedback, self.damping, self.allpass_feedback);
+
+        let dry = 1.0 - self.mix;
+        let direct = 0.5 + self.width * 0.5;
+        let cross = 0.5 - self.width * 0.5;
+
+        for ((frame, &wet_left), &wet_right) in buffer.chunks_exact_mut(2).zip(&self.block_wet_left).zip(&self.block_wet_right) {
+            frame[0] = frame[0] * dry + (wet_left * direct + wet_right * cross) * self.mix;
+            frame[1] = frame[1] * dry + (wet_right * direct + wet_left * cross) * self.mix;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.left.reset();
+        self.right.reset();
+    }
+    
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        // Rebuild the delay lines so the reverb times stay the same in milliseconds
+        self.left = ReverbTank::new(&COMB_DELAYS_MS, &ALLPASS_DELAYS_MS, 0.0, sample_rate);
+        self.right = ReverbTank::new(&COMB_DELAYS_MS, &ALLPASS_DELAYS_MS, STEREO_SPREAD_MS, sample_rate);
     }
     
     fn name(&self) -> &str {
         "Reverb"
     }
-}
\ No newline at end of file
+    
+    fn parameter_names(&self) -> &[&'static str] {
+        &["Room Size", "Damping", "Mix", "Width"]
+    }
+    
+    fn parameter(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(self.room_size),
+            1 => Some(self.damping),
+            2 => Some(self.mix),
+            3 => Some(self.width),
+            _ => None,
+        }
+    }
+    
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_room_size(value),
+            1 => self.set_damping(value),
+            2 => self.set_mix(value),
+            3 => self.set_width(value),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use super::AudioEffect;
+use crate::modulation::lfo::{LfoRate, LfoShape};
+use crate::modulation::Lfo;
+
+/// LFO rates reachable through the normalized rate parameter
+const MIN_LFO_RATE_HZ: f32 = 0.5;
+const MAX_LFO_RATE_HZ: f32 = 15.0;
+
+/// Seed of the sample-and-hold shape's random values
+const LFO_SEED: u32 = 1;
+
+/// Time the gain takes to follow a jump of the LFO, so square and sample-and-hold shapes don't click
+const GAIN_SMOOTHING_MS: f32 = 2.0;
+
+/// Tremolo effect: the volume follows an LFO of any of the modulation LFO shapes
+///
+/// Both channels share the LFO, so the stereo image stays in place.
+#[derive(Debug, Clone)]
+pub struct TremoloEffect {
+    lfo: Lfo,
+    depth: f32,            // How far the volume dips at the bottom of the LFO (0.0 - 1.0)
+    gain: f32,             // Smoothed gain currently applied
+    smoothing: f32,        // One-pole coefficient of the gain smoothing
+    sample_rate: u32,
+}
+
+impl TremoloEffect {
+    /// Create a new tremolo effect
+    ///
+    /// # Parameters
+    /// - `lfo_rate`: LFO rate in Hz (typically 2.0 - 10.0)
+    /// - `depth`: Volume dip at the bottom of the LFO (0.0 - 1.0)
+    /// - `shape`: LFO shape
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(lfo_rate: f32, depth: f32, shape: LfoShape, sample_rate: u32) -> Self {
+        Self {
+            lfo: Lfo::new(shape, LfoRate::Hertz(lfo_rate.max(0.01)), LFO_SEED),
+            depth: depth.clamp(0.0, 1.0),
+            gain: 1.0,
+            smoothing: Self::smoothing_coefficient(sample_rate),
+            sample_rate,
+        }
+    }
+
+    fn smoothing_coefficient(sample_rate: u32) -> f32 {
+        (-1.0 / (GAIN_SMOOTHING_MS / 1000.0 * sample_rate as f32)).exp()
+    }
+
+    /// Gain for the next sample, moving the LFO on by one sample
+    fn next_gain(&mut self) -> f32 {
+        // Full volume at the top of the LFO, dipping by the depth at the bottom
+        let target = 1.0 - self.depth * (0.5 - self.lfo.value() * 0.5);
+        self.gain = target + (self.gain - target) * self.smoothing;
+
+        // The rate is always in Hz, so the tempo is unused
+        self.lfo.advance(1, self.sample_rate, 0.0);
+        self.gain
+    }
+
+    fn lfo_rate(&self) -> f32 {
+        self.lfo.frequency(0.0)
+    }
+
+    /// Set LFO rate in Hz
+    pub fn set_lfo_rate(&mut self, rate: f32) {
+        self.lfo.rate = LfoRate::Hertz(rate.max(0.01));
+    }
+
+    /// Set volume dip depth (0.0 - 1.0)
+    pub fn set_depth(&mut self, depth: f32) {
+        self.depth = depth.clamp(0.0, 1.0);
+    }
+
+    pub fn set_shape(&mut self, shape: LfoShape) {
+        self.lfo.shape = shape;
+    }
+
+    pub fn shape(&self) -> LfoShape {
+        self.lfo.shape
+    }
+}
+
+impl AudioEffect for TremoloEffect {
+    fn process_sample(&mut self, input: f32) -> f32 {
+        input * self.next_gain()
+    }
+
+    fn process_frame(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let gain = self.next_gain();
+        (left * gain, right * gain)
+    }
+
+    fn reset(&mut self) {
+        self.lfo = Lfo::new(self.lfo.shape, self.lfo.rate, LFO_SEED);
+        self.gain = 1.0;
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+        self.smoothing = Self::smoothing_coefficient(sample_rate);
+        self.reset();
+    }
+
+    fn name(&self) -> &str {
+        "Tremolo"
+    }
+
+    fn parameter_names(&self) -> &[&'static str] {
+        &["Rate", "Depth"]
+    }
+
+    fn parameter(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(((self.lfo_rate() - MIN_LFO_RATE_HZ) / (MAX_LFO_RATE_HZ - MIN_LFO_RATE_HZ)).clamp(0.0, 1.0)),
+            1 => Some(self.depth),
+            _ => None,
+        }
+    }
+
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        let value = value.clamp(0.0, 1.0);
+        match index {
+            0 => self.set_lfo_rate(MIN_LFO_RATE_HZ + value * (MAX_LFO_RATE_HZ - MIN_LFO_RATE_HZ)),
+            1 => self.set_depth(value),
+            _ => {}
+        }
+    }
+
+    fn parameter_value(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(self.lfo_rate()),
+            1 => Some(self.depth),
+            _ => None,
+        }
+    }
+
+    fn set_parameter_value(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_lfo_rate(value),
+            1 => self.set_depth(value),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,261 @@
+use super::AudioEffect;
+use super::distortion::{dc_blocker_coefficient, ShapingChannel};
+use super::oversampling::Oversampling;
+use crate::audio::wav::{read_wav_file, wav_files_in};
+use std::f32::consts::PI;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Points in the tables built from the curves
+pub const TABLE_SIZE: usize = 1024;
+
+/// Directory custom transfer tables are loaded from, one WAV file per table
+pub const WAVESHAPER_TABLES_DIRECTORY: &str = "waveshapers";
+
+/// Input gains reachable through the normalized drive parameter
+const MIN_DRIVE: f32 = 0.25;
+const MAX_DRIVE: f32 = 8.0;
+
+/// Curves a waveshaper table can be built from, over inputs of -1.0 to 1.0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShaperCurve {
+    Sine,       // Quarter sine, rounding peaks off like tape
+    Chebyshev,  // Third Chebyshev polynomial, turning a full-scale sine into its third harmonic
+    Staircase,  // Eight flat steps, a coarse chiptune quantizer
+    Rectifier,  // Full-wave rectifier, an octave up
+}
+
+impl ShaperCurve {
+    /// The next curve in the cycle, wrapping around
+    pub fn next(&self) -> ShaperCurve {
+        match self {
+            ShaperCurve::Sine => ShaperCurve::Chebyshev,
+            ShaperCurve::Chebyshev => ShaperCurve::Staircase,
+            ShaperCurve::Staircase => ShaperCurve::Rectifier,
+            ShaperCurve::Rectifier => ShaperCurve::Sine,
+        }
+    }
+
+    /// Output of the curve for an input (-1.0 to 1.0)
+    pub fn value(&self, x: f32) -> f32 {
+        match self {
+            ShaperCurve::Sine => (x * PI / 2.0).sin(),
+            ShaperCurve::Chebyshev => 4.0 * x * x * x - 3.0 * x,
+            ShaperCurve::Staircase => ((x * 4.0).floor().min(3.0) + 0.5) / 4.0,
+            ShaperCurve::Rectifier => 2.0 * x.abs() - 1.0,
+        }
+    }
+
+    /// Table of the curve, TABLE_SIZE points spread evenly from -1.0 to 1.0
+    pub fn table(&self) -> Vec<f32> {
+        (0..TABLE_SIZE)
+            .map(|i| self.value(i as f32 / (TABLE_SIZE - 1) as f32 * 2.0 - 1.0))
+            .collect()
+    }
+}
+
+impl fmt::Display for ShaperCurve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaperCurve::Sine => write!(f, "Sine"),
+            ShaperCurve::Chebyshev => write!(f, "Chebyshev"),
+            ShaperCurve::Staircase => write!(f, "Staircase"),
+            ShaperCurve::Rectifier => write!(f, "Rectifier"),
+        }
+    }
+}
+
+/// Waveshaper effect: maps each sample through a transfer table
+///
+/// The table spans inputs from -1.0 to 1.0 and is read with linear interpolation; driven
+/// inputs beyond full scale hold the end values. Any table can be loaded, the curves are
+/// just ready-made ones.
+#[derive(Debug, Clone)]
+pub struct WaveshaperEffect {
+    table: Arc<Vec<f32>>,
+    table_name: String,
+    curve: Option<ShaperCurve>,  // Curve the table was built from, None for a custom table
+    drive: f32,                  // Gain before the table (MIN_DRIVE - MAX_DRIVE)
+    mix: f32,                    // Dry/wet mix (0.0 - 1.0)
+    channel: ShapingChannel,
+    channel_right: ShapingChannel,  // Right channel for stereo processing
+    dc_coefficient: f32,
+}
+
+impl WaveshaperEffect {
+    /// Create a new waveshaper from a curve, oversampled 2x
+    ///
+    /// # Parameters
+    /// - `curve`: Curve to build the table from
+    /// - `drive`: Gain before the table (0.25 - 8.0)
+    /// - `mix`: Dry/wet mix (0.0 - 1.0)
+    /// - `sample_rate`: Audio sample rate
+    pub fn new(curve: ShaperCurve, drive: f32, mix: f32, sample_rate: u32) -> Self {
+        Self {
+            table: Arc::new(curve.table()),
+            table_name: curve.to_string(),
+            curve: Some(curve),
+            drive: drive.clamp(MIN_DRIVE, MAX_DRIVE),
+            mix: mix.clamp(0.0, 1.0),
+            channel: ShapingChannel::new(Oversampling::X2),
+            channel_right: ShapingChannel::new(Oversampling::X2),
+            dc_coefficient: dc_blocker_coefficient(sample_rate),
+        }
+    }
+
+    /// Value of a table at an input, interpolating linearly and holding the ends
+    fn lookup(table: &[f32], input: f32) -> f32 {
+        let position = (input.clamp(-1.0, 1.0) * 0.5 + 0.5) * (table.len() - 1) as f32;
+        let index = (position as usize).min(table.len() - 2);
+        let fraction = position - index as f32;
+        table[index] + (table[index + 1] - table[index]) * fraction
+    }
+
+    /// Drive and shape one sample of a channel, mixed with the dry sample
+    fn shape(channel: &mut ShapingChannel, table: &[f32], input: f32, drive: f32, mix: f32, dc_coefficient: f32) -> f32 {
+        let (dry, wet) = channel.process(input, |sample| Self::lookup(table, sample * drive), dc_coefficient);
+        dry * (1.0 - mix) + wet * mix
+    }
+
+    /// Load a custom transfer table spanning inputs from -1.0 to 1.0, at least two points long
+    pub fn set_table(&mut self, name: String, table: Vec<f32>) {
+        if table.len() >= 2 {
+            self.table = Arc::new(table);
+            self.table_name = name;
+            self.curve = None;
+        }
+    }
+
+    /// Load a custom transfer table from a WAV file, its samples read from the lowest input up
+    pub fn load_table(&mut self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let table = read_wav_file(&path.to_string_lossy())?.to_mono();
+        if table.len() < 2 {
+            return Err(format!("{} has fewer than two samples", path.display()).into());
+        }
+
+        let name = path.file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+        self.set_table(name, table);
+        Ok(())
+    }
+
+    /// Switch to the table following this one in the cycle of the curves and the files of a directory
+    ///
+    /// Comes back to the first curve after the last file, or straight after the last curve
+    /// when the directory has no WAV files.
+    pub fn next_table_in(&mut self, directory: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let first_curve = ShaperCurve::Sine;
+        if let Some(next) = self.curve.map(|curve| curve.next()).filter(|&next| next != first_curve) {
+            self.set_curve(next);
+            return Ok(());
+        }
+
+        let paths = wav_files_in(directory).unwrap_or_default();
+        let current = match self.curve {
+            Some(_) => 0,
+            None => paths.iter()
+                .position(|path| path.file_stem().is_some_and(|stem| stem.to_string_lossy() == self.table_name))
+                .map_or(0, |index| index + 1),
+        };
+
+        match paths.get(current) {
+            Some(path) => self.load_table(path),
+            None => {
+                self.set_curve(first_curve);
+                Ok(())
+            },
+        }
+    }
+
+    /// Build the table from one of the curves
+    pub fn set_curve(&mut self, curve: ShaperCurve) {
+        self.table = Arc::new(curve.table());
+        self.table_name = curve.to_string();
+        self.curve = Some(curve);
+    }
+
+    /// Name of the curve or file the table came from
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    /// Set gain before the table (0.25 - 8.0)
+    pub fn set_drive(&mut self, drive: f32) {
+        self.drive = drive.clamp(MIN_DRIVE, MAX_DRIVE);
+    }
+
+    /// Set dry/wet mix (0.0 - 1.0)
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+
+    /// Run the table at 1x, 2x or 4x the sample rate
+    pub fn set_oversampling(&mut self, oversampling: Oversampling) {
+        self.channel = ShapingChannel::new(oversampling);
+        self.channel_right = ShapingChannel::new(oversampling);
+    }
+}
+
+impl AudioEffect for WaveshaperEffect {
+    fn process_sample(&mut self, input: f32) -> f32 {
+        Self::shape(&mut self.channel, &self.table, input, self.drive, self.mix, self.dc_coefficient)
+    }
+
+    fn process_frame(&mut self, left: f32, right: f32) -> (f32, f32) {
+        (Self::shape(&mut self.channel, &self.table, left, self.drive, self.mix, self.dc_coefficient),
+         Self::shape(&mut self.channel_right, &self.table, right, self.drive, self.mix, self.dc_coefficient))
+    }
+
+    fn reset(&mut self) {
+        self.channel.reset();
+        self.channel_right.reset();
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.dc_coefficient = dc_blocker_coefficient(sample_rate);
+        self.reset();
+    }
+
+    fn name(&self) -> &str {
+        "Waveshaper"
+    }
+
+    fn parameter_names(&self) -> &[&'static str] {
+        &["Drive", "Mix"]
+    }
+
+    fn parameter(&self, index: usize) -> Option<f32> {
+        // The drive moves exponentially, as a gain knob in dB would
+        let drive_range = (MAX_DRIVE / MIN_DRIVE).ln();
+        match index {
+            0 => Some(((self.drive / MIN_DRIVE).ln() / drive_range).clamp(0.0, 1.0)),
+            1 => Some(self.mix),
+            _ => None,
+        }
+    }
+
+    fn set_parameter(&mut self, index: usize, value: f32) {
+        let value = value.clamp(0.0, 1.0);
+        match index {
+            0 => self.set_drive(MIN_DRIVE * (value * (MAX_DRIVE / MIN_DRIVE).ln()).exp()),
+            1 => self.set_mix(value),
+            _ => {}
+        }
+    }
+
+    fn parameter_value(&self, index: usize) -> Option<f32> {
+        match index {
+            0 => Some(self.drive),
+            1 => Some(self.mix),
+            _ => None,
+        }
+    }
+
+    fn set_parameter_value(&mut self, index: usize, value: f32) {
+        match index {
+            0 => self.set_drive(value),
+            1 => self.set_mix(value),
+            _ => {}
+        }
+    }
+}
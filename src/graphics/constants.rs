@@ -7,6 +7,9 @@ pub const WAVEFORM_SINE: usize = 0;
 pub const WAVEFORM_SQUARE: usize = 1;
 pub const WAVEFORM_TRIANGLE: usize = 2;
 pub const WAVEFORM_SAWTOOTH: usize = 3;
+pub const WAVEFORM_NOISE: usize = 4;
+pub const WAVEFORM_FM: usize = 5;
+pub const WAVEFORM_WAVETABLE: usize = 6;
 
 // Constants for keys
 pub const KEY_IDLE: usize = 0;
@@ -2,7 +2,9 @@ use std::collections::HashMap;
 use minifb::Window;
 use crate::graphics::constants::{KEY_IDLE, KEY_PRESSED, TANGENT_IDLE, TANGENT_PRESSED, WINDOW_HEIGHT, WINDOW_WIDTH};
 use crate::graphics::sprites::{draw_sprite, Sprite, Sprites};
+use crate::effects::equalizer::{MAX_EQ_FREQUENCY_HZ, MAX_EQ_GAIN_DB, MIN_EQ_FREQUENCY_HZ};
 use crate::state::State;
+use crate::waveforms::breakpoint_envelope::{EnvelopeMode, MAX_BREAKPOINTS};
 
 /// Draws the text sprite.
 ///
@@ -71,53 +73,63 @@ pub fn draw_buffer(window: &mut Window, window_buffer: &mut Vec<u32>) {
     window.update_with_buffer(&window_buffer, WINDOW_WIDTH, WINDOW_HEIGHT).unwrap();
 }
 
-/// Draws idle knobs.
+/// Draws the filter bulb, lit while the current track's filter is on.
 ///
 /// # Parameters
 /// - `state`: Reference to the current `State` containing the state of the synthesizer.
 /// - `sprites`: A reference to the `Sprites` struct containing all the sprite images.
 /// - `window_buffer`: A mutable reference to the buffer representing the window's pixels.
 pub fn draw_bulb_sprite(state: &State, sprites: &Sprites, window_buffer: &mut Vec<u32>) {
-    draw_sprite(6 * sprites.knob[0].width as usize,
-                5 * sprites.knob[0].height as usize + 10,
-                &sprites.bulb[state.lpf_active], window_buffer, WINDOW_WIDTH);
+    draw_sprite(6 * sprites.knob[0].width as usize - sprites.bulb[0].width as usize - 4,
+                filter_knob_y(sprites) + (sprites.knob[0].height - sprites.bulb[0].height) as usize / 2,
+                &sprites.bulb[state.current_track().filter_enabled as usize], window_buffer, WINDOW_WIDTH);
 }
 
-/// Draws idle knobs.
+/// Draws the filter cutoff knob of the current track.
 ///
 /// # Parameters
 /// - `state`: Reference to the current `State` containing the state of the synthesizer.
 /// - `sprites`: A reference to the `Sprites` struct containing all the sprite images.
 /// - `window_buffer`: A mutable reference to the buffer representing the window's pixels.
 pub fn draw_filter_cutoff_knob_sprite(state: &State, sprites: &Sprites, window_buffer: &mut Vec<u32>) {
-    let filter_cutoff = state.filter_factor;
-
-    // Assigns the appropriate sprite index based on cutoff float value threshold
-    let knob_sprite_index = match filter_cutoff {
-        v if (0.0..=0.14).contains(&v) => 0,
-        v if (0.14..=0.28).contains(&v) => 1,
-        v if (0.28..=0.42).contains(&v) => 2,
-        v if (0.42..=0.57).contains(&v) => 3,
-        v if (0.57..=0.71).contains(&v) => 4,
-        v if (0.71..=0.85).contains(&v) => 5,
-        v if (0.85..=0.99).contains(&v) => 6,
-        _ => 7 // Last knob for ~0.99
-    };
+    let filter_cutoff = state.current_track().filter.cutoff_normalized();
 
     draw_sprite(6 * sprites.knob[0].width as usize,
-                5 * sprites.knob[0].height as usize - 10,
-                &sprites.knob[knob_sprite_index], window_buffer, WINDOW_WIDTH);
+                filter_knob_y(sprites),
+                &sprites.knob[knob_sprite_index(filter_cutoff)], window_buffer, WINDOW_WIDTH);
 }
 
-/// Draws idle knob.
+/// Draws the filter resonance knob of the current track to the right of the cutoff knob.
 ///
 /// # Parameters
+/// - `state`: Reference to the current `State` containing the state of the synthesizer.
 /// - `sprites`: A reference to the `Sprites` struct containing all the sprite images.
 /// - `window_buffer`: A mutable reference to the buffer representing the window's pixels.
-pub fn draw_idle_knob_sprite(sprites: &Sprites, window_buffer: &mut Vec<u32>) {
+pub fn draw_filter_resonance_knob_sprite(state: &State, sprites: &Sprites, window_buffer: &mut Vec<u32>) {
+    let filter_resonance = state.current_track().filter.resonance();
+
     draw_sprite(7 * sprites.knob[0].width as usize,
-                5 * sprites.knob[0].height as usize - 10,
-                &sprites.knob[0], window_buffer, WINDOW_WIDTH);
+                filter_knob_y(sprites),
+                &sprites.knob[knob_sprite_index(filter_resonance)], window_buffer, WINDOW_WIDTH);
+}
+
+/// Knobs sit directly above the waveform display row
+fn filter_knob_y(sprites: &Sprites) -> usize {
+    4 * 51 + 17 - sprites.knob[0].height as usize
+}
+
+/// Assigns the appropriate knob sprite index based on a 0.0 - 1.0 value threshold
+fn knob_sprite_index(value: f32) -> usize {
+    match value {
+        v if (0.0..=0.14).contains(&v) => 0,
+        v if (0.14..=0.28).contains(&v) => 1,
+        v if (0.28..=0.42).contains(&v) => 2,
+        v if (0.42..=0.57).contains(&v) => 3,
+        v if (0.57..=0.71).contains(&v) => 4,
+        v if (0.71..=0.85).contains(&v) => 5,
+        v if (0.85..=0.99).contains(&v) => 6,
+        _ => 7 // Last knob for ~0.99
+    }
 }
 
 /// Draws the note sprite for the given note sprite index.
@@ -177,17 +189,21 @@ pub fn draw_idle_key_sprites(sprites: &Sprites, window_buffer: &mut Vec<u32>) {
     }
 }
 
-/// Draws ADSR faders with custom vertical bars and numerical values (0-99).
+/// Draws the envelope faders of the current track with custom vertical bars.
+///
+/// In DAHDSR mode the six faders show delay, attack, hold, decay, sustain and release (0-99).
+/// In breakpoint mode each fader shows the level of one breakpoint, labelled with its number,
+/// or `L`/`S` for the loop start and sustain point, with the selected point highlighted.
 ///
 /// # Parameters
-/// - `state`: Reference to the current `State` containing ADSR values.
+/// - `state`: Reference to the current `State` containing the current track's envelope.
 /// - `sprites`: A reference to the `Sprites` struct containing all the sprite images.
 /// - `window_buffer`: A mutable reference to the buffer representing the window's pixels.
 pub fn draw_adsr_faders(state: &State, sprites: &Sprites, window_buffer: &mut Vec<u32>) {
-    // Compact fader dimensions to fit all 4 ADSR faders
-    let fader_width = 25;
+    // Compact fader dimensions to fit all 6 envelope faders
+    let fader_width = 17;
     let fader_height = 50;
-    let fader_spacing = 30; // Minimal spacing between faders
+    let fader_spacing = 22; // Minimal spacing between faders
 
     // Position faders directly to the right of waveform visualizer
     // Display is positioned at: x = 1 * 164 = 164, y = 4 * 51 + 17 = 221
@@ -198,27 +214,130 @@ pub fn draw_adsr_faders(state: &State, sprites: &Sprites, window_buffer: &mut Ve
     let base_x = display_x + display_width + 104; // Start right after display (164 + 164 + 5 = 333px)
     let base_y = display_y; // Same y as display
 
-    // ADSR values
-    let adsr_values = [state.attack, state.decay, state.sustain, state.release];
-    let labels = ["A", "D", "S", "R"];
+    let fill_color = 0xFF00AA00;     // Green
+    let selected_color = 0xFFFFCC00; // Amber for the selected breakpoint
+
+    // Fader levels (0.0 - 1.0), fill colors and labels
+    let track = state.current_track();
+    let faders: Vec<(f32, u32, &str)> = match track.envelope_mode {
+        EnvelopeMode::Dahdsr => {
+            let values = [track.delay, track.attack, track.hold, track.decay, track.sustain, track.release];
+            let labels = ["D", "A", "H", "D", "S", "R"];
+            values.iter()
+                .zip(labels)
+                .map(|(&value, label)| (value as f32 / 99.0, fill_color, label))
+                .collect()
+        },
+        EnvelopeMode::Breakpoint => {
+            let envelope = &track.breakpoint_envelope;
+            let numbers = ["1", "2", "3", "4", "5", "6"];
+            envelope.points.iter()
+                .take(MAX_BREAKPOINTS)
+                .enumerate()
+                .map(|(i, point)| {
+                    let color = if i == state.selected_breakpoint { selected_color } else { fill_color };
+                    let label = if envelope.sustain_point == Some(i) {
+                        "S"
+                    } else if envelope.loop_start == Some(i) {
+                        "L"
+                    } else {
+                        numbers[i]
+                    };
+                    (point.level, color, label)
+                })
+                .collect()
+        },
+    };
 
-    // Draw each ADSR fader
-    for (i, (&value, &label)) in adsr_values.iter().zip(labels.iter()).enumerate() {
+    // Draw each envelope fader
+    for (i, &(level, color, label)) in faders.iter().enumerate() {
         let x = base_x + i * fader_spacing;
         let y = base_y;
 
         // Draw fader background (dark gray border)
         draw_fader_background(x, y, fader_width, fader_height, window_buffer);
 
-        // Draw fader fill (based on value 0-99)
-        let fill_height = (value as f32 / 99.0 * (fader_height - 4) as f32) as usize;
-        draw_fader_fill(x + 2, y + (fader_height - 2 - fill_height), fader_width - 4, fill_height, window_buffer);
+        // Draw fader fill (based on level 0.0-1.0)
+        let fill_height = (level.clamp(0.0, 1.0) * (fader_height - 4) as f32) as usize;
+        draw_fader_fill(x + 2, y + (fader_height - 2 - fill_height), fader_width - 4, fill_height, color, window_buffer);
 
-        // Draw label below fader (A, D, S, R) - centered for smaller width
+        // Draw label below fader - centered for smaller width
         draw_fader_label(x + fader_width / 2 - 2, y + fader_height + 3, label, window_buffer);
     }
 }
 
+/// Draws the frequency response of the edited equalizer, the current track's or the master
+/// bus one, while it is on
+///
+/// Frequency runs logarithmically from 20 Hz to 20 kHz across the panel and gain from
+/// -18 dB at the bottom to +18 dB at the top, with grid lines at 100 Hz, 1 kHz, 10 kHz and 0 dB.
+/// The selected band is marked on the curve, which is cyan for the master bus.
+///
+/// # Parameters
+/// - `state`: Reference to the current `State` containing the equalizers.
+/// - `window_buffer`: A mutable reference to the buffer representing the window's pixels.
+pub fn draw_eq_response_curve(state: &State, window_buffer: &mut Vec<u32>) {
+    let (equalizer, enabled) = state.edited_equalizer();
+    if !enabled {
+        return;
+    }
+
+    // Empty space right of the track list and master meter
+    let panel_x = 290;
+    let panel_y = 10;
+    let panel_width = 270;
+    let panel_height = 95;
+
+    let curve_color = if state.eq_on_master { 0xFF00AAAA } else { 0xFF00AA00 }; // Cyan for the master bus, green for a track
+    let selected_color = 0xFFFFCC00; // Amber for the selected band
+    let grid_color = 0xFF303030;
+
+    draw_fader_background(panel_x, panel_y, panel_width, panel_height, window_buffer);
+
+    let inner_x = panel_x + 1;
+    let inner_y = panel_y + 1;
+    let inner_width = panel_width - 2;
+    let inner_height = panel_height - 2;
+
+    let frequency_position = |frequency: f32| {
+        (frequency / MIN_EQ_FREQUENCY_HZ).ln() / (MAX_EQ_FREQUENCY_HZ / MIN_EQ_FREQUENCY_HZ).ln()
+    };
+    let gain_row = |gain_db: f32| {
+        let position = (0.5 - gain_db / (2.0 * MAX_EQ_GAIN_DB)).clamp(0.0, 1.0);
+        inner_y + (position * (inner_height - 1) as f32).round() as usize
+    };
+
+    // Grid
+    for frequency in [100.0, 1000.0, 10000.0] {
+        let column = inner_x + (frequency_position(frequency) * (inner_width - 1) as f32) as usize;
+        draw_fader_fill(column, inner_y, 1, inner_height, grid_color, window_buffer);
+    }
+    draw_fader_fill(inner_x, gain_row(0.0), inner_width, 1, grid_color, window_buffer);
+
+    // Curve, joining each column to the previous one so steep slopes stay connected
+    let mut previous_row = None;
+    for column in 0..inner_width {
+        let position = column as f32 / (inner_width - 1) as f32;
+        let frequency = MIN_EQ_FREQUENCY_HZ * (MAX_EQ_FREQUENCY_HZ / MIN_EQ_FREQUENCY_HZ).powf(position);
+        let row = gain_row(equalizer.magnitude_db(frequency));
+        let (top, bottom) = match previous_row {
+            Some(previous) => (row.min(previous), row.max(previous)),
+            None => (row, row),
+        };
+        draw_fader_fill(inner_x + column, top, 1, bottom - top + 1, curve_color, window_buffer);
+        previous_row = Some(row);
+    }
+
+    // Selected band marker on the curve
+    if let Some(band) = equalizer.bands().get(state.selected_eq_band) {
+        let column = inner_x + (frequency_position(band.frequency) * (inner_width - 1) as f32) as usize;
+        let row = gain_row(equalizer.magnitude_db(band.frequency));
+        let x = column.saturating_sub(1).max(inner_x).min(inner_x + inner_width - 3);
+        let y = row.saturating_sub(1).max(inner_y).min(inner_y + inner_height - 3);
+        draw_fader_fill(x, y, 3, 3, selected_color, window_buffer);
+    }
+}
+
 /// Draws a fader background rectangle
 fn draw_fader_background(x: usize, y: usize, width: usize, height: usize, buffer: &mut Vec<u32>) {
     let border_color = 0xFF404040; // Dark gray
@@ -243,9 +362,7 @@ fn draw_fader_background(x: usize, y: usize, width: usize, height: usize, buffer
 }
 
 /// Draws the fader fill based on value
-fn draw_fader_fill(x: usize, y: usize, width: usize, height: usize, buffer: &mut Vec<u32>) {
-    let fill_color = 0xFF00AA00; // Green
-
+fn draw_fader_fill(x: usize, y: usize, width: usize, height: usize, fill_color: u32, buffer: &mut Vec<u32>) {
     for dy in 0..height {
         for dx in 0..width {
             let pixel_x = x + dx;
@@ -394,7 +511,7 @@ fn draw_simple_text(x: usize, y: usize, text: &str, color: u32, buffer: &mut Vec
 fn draw_fader_label(x: usize, y: usize, label: &str, buffer: &mut Vec<u32>) {
     let text_color = 0xFFFFFFFF; // White
 
-    // Simple 5x7 pixel font for A, D, S, R
+    // Simple 5x7 pixel font for the envelope stage letters and breakpoint numbers
     let patterns = match label {
         "A" => vec![ // A
                      0b01110,
@@ -432,6 +549,78 @@ fn draw_fader_label(x: usize, y: usize, label: &str, buffer: &mut Vec<u32>) {
                      0b10010,
                      0b10001,
         ],
+        "H" => vec![ // H
+                     0b10001,
+                     0b10001,
+                     0b10001,
+                     0b11111,
+                     0b10001,
+                     0b10001,
+                     0b10001,
+        ],
+        "L" => vec![ // L
+                     0b10000,
+                     0b10000,
+                     0b10000,
+                     0b10000,
+                     0b10000,
+                     0b10000,
+                     0b11111,
+        ],
+        "1" => vec![ // 1
+                     0b00100,
+                     0b01100,
+                     0b00100,
+                     0b00100,
+                     0b00100,
+                     0b00100,
+                     0b01110,
+        ],
+        "2" => vec![ // 2
+                     0b01110,
+                     0b10001,
+                     0b00001,
+                     0b00010,
+                     0b00100,
+                     0b01000,
+                     0b11111,
+        ],
+        "3" => vec![ // 3
+                     0b11110,
+                     0b00001,
+                     0b00001,
+                     0b01110,
+                     0b00001,
+                     0b00001,
+                     0b11110,
+        ],
+        "4" => vec![ // 4
+                     0b00010,
+                     0b00110,
+                     0b01010,
+                     0b10010,
+                     0b11111,
+                     0b00010,
+                     0b00010,
+        ],
+        "5" => vec![ // 5
+                     0b11111,
+                     0b10000,
+                     0b11110,
+                     0b00001,
+                     0b00001,
+                     0b10001,
+                     0b01110,
+        ],
+        "6" => vec![ // 6
+                     0b00110,
+                     0b01000,
+                     0b10000,
+                     0b11110,
+                     0b10001,
+                     0b10001,
+                     0b01110,
+        ],
         _ => return,
     };
 
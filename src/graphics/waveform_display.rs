@@ -1,17 +1,25 @@
 use crate::graphics::sprites::Sprite;
+use crate::state::Track;
 use crate::waveforms::sine_wave::calculate_sine;
+use crate::waveforms::square_wave::calculate_square;
 use crate::waveforms::triangle_wave::calculate_triangle;
 use crate::waveforms::sawtooth_wave::calculate_sawtooth;
+use crate::waveforms::polyblep::{calculate_polyblep_square, calculate_polyblep_sawtooth};
+use crate::waveforms::noise::{NoiseColor, NoiseGenerator};
+use crate::waveforms::fm::calculate_fm;
+use crate::waveforms::wavetable::calculate_wavetable;
 use crate::waveforms::{Waveform, SAMPLE_RATE};
 
 const DISPLAY_WIDTH: u32 = 164;
 const DISPLAY_HEIGHT: u32 = 51;
 const DISPLAY_CENTER_Y: u32 = DISPLAY_HEIGHT / 2;
 
-/// Generates a real-time animated waveform visualization sprite for the given frequency and waveform type.
+/// Generates a real-time animated waveform visualization sprite for the given frequency and the track's waveform.
 /// The animation_time parameter creates a phase shift that makes the wave appear to oscillate.
 /// The amplitude parameter controls the fade-out effect (0.0 = invisible, 1.0 = full brightness).
-pub fn generate_waveform_display(frequency: f32, waveform: Waveform, animation_time: f32, amplitude: f32) -> Sprite {
+/// Band-limited tracks draw the same PolyBLEP square and sawtooth shapes the audio engine plays.
+/// The FM waveform draws the track's FM patch and the wavetable waveform the frame of the wavetable at its current position.
+pub fn generate_waveform_display(frequency: f32, track: &Track, animation_time: f32, amplitude: f32) -> Sprite {
     let mut pixel_data = vec![0x00000000u32; (DISPLAY_WIDTH * DISPLAY_HEIGHT) as usize]; // Transparent background
     
     // Draw display frame
@@ -27,6 +35,13 @@ pub fn generate_waveform_display(frequency: f32, waveform: Waveform, animation_t
         // Calculate phase offset for animation (makes the wave appear to move)
         let phase_offset = (animation_time * frequency * 2.0 * std::f32::consts::PI) as usize;
         
+        // Noise is drawn one sample per pixel, reseeded as the animation moves
+        let noise_color = match track.waveform {
+            Waveform::NOISE(color) => color,
+            _ => NoiseColor::White,
+        };
+        let mut noise = NoiseGenerator::new(noise_color, phase_offset as u32 + 1, SAMPLE_RATE as u32);
+        
         // Generate waveform points
         let mut previous_y = DISPLAY_CENTER_Y;
         
@@ -34,14 +49,16 @@ pub fn generate_waveform_display(frequency: f32, waveform: Waveform, animation_t
             let sample_index = (x as f32 / DISPLAY_WIDTH as f32 * total_samples as f32) as usize + phase_offset;
             
             // Calculate waveform value (-1.0 to 1.0)
-            let waveform_value = match waveform {
+            let waveform_value = match track.waveform {
                 Waveform::SINE => calculate_sine(frequency, sample_index),
-                Waveform::SQUARE => {
-                    let sine_val = calculate_sine(frequency, sample_index);
-                    sine_val.signum() // Convert to square wave
-                },
+                Waveform::SQUARE if track.band_limited => calculate_polyblep_square(frequency, sample_index),
+                Waveform::SQUARE => calculate_square(frequency, sample_index),
                 Waveform::TRIANGLE => calculate_triangle(frequency, sample_index),
+                Waveform::SAWTOOTH if track.band_limited => calculate_polyblep_sawtooth(frequency, sample_index),
                 Waveform::SAWTOOTH => calculate_sawtooth(frequency, sample_index),
+                Waveform::NOISE(_) => noise.next_sample(),
+                Waveform::FM => calculate_fm(&track.fm_patch, frequency, sample_index),
+                Waveform::WAVETABLE => calculate_wavetable(&track.wavetable, track.current_wavetable_position, frequency, sample_index),
             };
             
             // Convert waveform value to y coordinate (flip because screen coordinates)
@@ -1,8 +1,18 @@
 use minifb::{Key, KeyRepeat, Window};
 use rodio::Sink;
 use crate::state::State;
+use crate::waveforms::breakpoint_envelope::EnvelopeMode;
 use super::super::InputCommand;
 
+/// Curvature change per key press when bending an envelope stage
+const CURVE_STEP: f32 = 1.0;
+
+/// Breakpoint time change per key press, one step of a 0-99 fader
+const BREAKPOINT_TIME_STEP: f32 = 2.0 / 99.0;
+
+/// Breakpoint level change per key press, one step of a 0-99 fader
+const BREAKPOINT_LEVEL_STEP: f32 = 1.0 / 99.0;
+
 /// Command for controlling ADSR parameters
 pub struct ADSRControlCommand {
     parameter: ADSRParameter,
@@ -33,6 +43,40 @@ impl ADSRControlCommand {
     pub fn new_release(increase: bool) -> Self {
         Self { parameter: ADSRParameter::Release, increase }
     }
+    
+    /// Edit the selected point of the current track's breakpoint envelope
+    ///
+    /// - Attack keys: time to reach the point (with shift: bend its curve)
+    /// - Decay keys: select the previous / next point
+    /// - Sustain keys: toggle the point as loop start / sustain point
+    /// - Release keys: level of the point
+    fn edit_breakpoint(&self, state: &mut State, shift_down: bool, first_press: bool) {
+        let direction = if self.increase { 1.0 } else { -1.0 };
+        
+        match self.parameter {
+            ADSRParameter::Attack if shift_down => state.bend_selected_breakpoint_curve(direction * CURVE_STEP),
+            ADSRParameter::Attack => state.adjust_selected_breakpoint_time(direction * BREAKPOINT_TIME_STEP),
+            ADSRParameter::Decay if first_press => state.step_selected_breakpoint(self.increase),
+            ADSRParameter::Sustain if first_press => {
+                if self.increase {
+                    state.toggle_selected_breakpoint_sustain();
+                } else {
+                    state.toggle_selected_breakpoint_loop_start();
+                }
+            },
+            ADSRParameter::Release => state.adjust_selected_breakpoint_level(direction * BREAKPOINT_LEVEL_STEP),
+            _ => return, // Selection and toggles ignore key repeat
+        }
+        
+        let index = state.selected_breakpoint;
+        let envelope = &state.current_track().breakpoint_envelope;
+        if let Some(point) = envelope.points.get(index) {
+            println!("Breakpoint {}: level {:.2}, time {:.2}s, curve {}{}{}",
+                     index + 1, point.level, point.time, point.curve,
+                     if envelope.loop_start == Some(index) { ", loop start" } else { "" },
+                     if envelope.sustain_point == Some(index) { ", sustain" } else { "" });
+        }
+    }
 }
 
 impl InputCommand for ADSRControlCommand {
@@ -49,6 +93,31 @@ impl InputCommand for ADSRControlCommand {
         };
         
         if window.is_key_pressed(key, KeyRepeat::Yes) {
+            let shift_down = window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift);
+            
+            // In breakpoint mode the same keys edit the selected breakpoint
+            if state.current_track().envelope_mode == EnvelopeMode::Breakpoint {
+                let first_press = window.is_key_pressed(key, KeyRepeat::No);
+                self.edit_breakpoint(state, shift_down, first_press);
+                return;
+            }
+            
+            // Holding shift bends the stage's curve instead of changing its time
+            if shift_down {
+                let delta = if self.increase { CURVE_STEP } else { -CURVE_STEP };
+                match self.parameter {
+                    ADSRParameter::Attack => state.bend_current_track_attack_curve(delta),
+                    ADSRParameter::Decay => state.bend_current_track_decay_curve(delta),
+                    ADSRParameter::Sustain => return, // Sustain is a level, it has no curve
+                    ADSRParameter::Release => state.bend_current_track_release_curve(delta),
+                }
+                
+                let track = state.current_track();
+                println!("Track {} ({}) envelope curves: attack {}, decay {}, release {}",
+                         track.id, track.name, track.attack_curve, track.decay_curve, track.release_curve);
+                return;
+            }
+            
             match (self.parameter, self.increase) {
                 (ADSRParameter::Attack, true) => state.increase_current_track_attack(),
                 (ADSRParameter::Attack, false) => state.decrease_current_track_attack(),
@@ -61,4 +130,4 @@ impl InputCommand for ADSRControlCommand {
             }
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,32 @@
+use minifb::{Key, Window};
+use rodio::Sink;
+use crate::audio::OfflineRenderer;
+use crate::audio::wav::WavFormat;
+use crate::state::State;
+use super::super::InputCommand;
+
+/// Command for bouncing all recorded tracks to a WAV file
+///
+/// Bounces 16-bit PCM; holding Shift bounces 24-bit PCM and Ctrl 32-bit float.
+pub struct BounceCommand;
+
+impl InputCommand for BounceCommand {
+    fn execute(&self, state: &mut State, window: &mut Window, _sink: &mut Sink) {
+        let format = if window.is_key_down(Key::LeftCtrl) || window.is_key_down(Key::RightCtrl) {
+            WavFormat::Float32
+        } else if window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift) {
+            WavFormat::Pcm24
+        } else {
+            WavFormat::Pcm16
+        };
+
+        let filename = "bounce.wav";
+        let renderer = OfflineRenderer::new(state.audio_config);
+
+        match renderer.render_to_wav(state, filename, format) {
+            Ok(()) => println!("Bounced project to {} ({}-bit{})", filename, format.bits_per_sample(),
+                               if format == WavFormat::Float32 { " float" } else { "" }),
+            Err(e) => println!("Bounce failed: {}", e),
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,21 @@
+use minifb::{Key, KeyRepeat, Window};
+use rodio::Sink;
+use crate::state::State;
+use super::super::InputCommand;
+
+/// Command for switching the current track between its waveform and the drum kit
+pub struct DrumModeCommand;
+
+impl InputCommand for DrumModeCommand {
+    fn execute(&self, state: &mut State, window: &mut Window, _sink: &mut Sink) {
+        if window.is_key_pressed(Key::D, KeyRepeat::No) {
+            state.toggle_current_track_drum_mode();
+            let track = state.current_track();
+            if track.drum_mode {
+                println!("{}: drum kit (C kick, D snare, D# clap, F# closed hat, A# open hat)", track.name);
+            } else {
+                println!("{}: {} waveform", track.name, track.waveform);
+            }
+        }
+    }
+}
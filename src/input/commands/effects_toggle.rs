@@ -1,10 +1,20 @@
 use minifb::{Key, KeyRepeat, Window};
 use rodio::Sink;
-use crate::effects::AudioEffect;
+use crate::effects::{BitcrusherEffect, ChorusEffect, DelayEffect, DistortionEffect, PhaserEffect, TremoloEffect, WaveshaperEffect};
+use crate::modulation::EffectSlot;
 use crate::state::State;
 use super::super::InputCommand;
 
 /// Command for toggling audio effects
+///
+/// Shift toggles the effect on the master bus; Ctrl with the delay key switches the delay between
+/// true stereo and ping-pong echoes, Ctrl with the chorus or phaser key adds chorus voices or
+/// phaser stages, Ctrl with the tremolo, distortion or waveshaper key cycles the tremolo's LFO
+/// shape, the distortion curve or the waveshaper curve, and Ctrl with the compressor key
+/// picks the track keying the compressor. The oversampling key steps the oversampling of
+/// the current track's distortion and waveshaper. The equalizer's bands are edited by
+/// `EqualizerControlCommand`, Ctrl with the equalizer key switching it between the current
+/// track's equalizer and the master bus one.
 pub struct EffectsToggleCommand {
     effect_type: EffectType,
 }
@@ -14,6 +24,17 @@ enum EffectType {
     Delay,
     Reverb,
     Flanger,
+    Chorus,
+    Phaser,
+    Tremolo,
+    Distortion,
+    Bitcrusher,
+    Waveshaper,
+    Oversampling,
+    Gate,
+    Compressor,
+    Limiter,
+    Equalizer,
 }
 
 impl EffectsToggleCommand {
@@ -28,6 +49,72 @@ impl EffectsToggleCommand {
     pub fn new_flanger() -> Self {
         Self { effect_type: EffectType::Flanger }
     }
+    
+    pub fn new_chorus() -> Self {
+        Self { effect_type: EffectType::Chorus }
+    }
+    
+    pub fn new_phaser() -> Self {
+        Self { effect_type: EffectType::Phaser }
+    }
+    
+    pub fn new_tremolo() -> Self {
+        Self { effect_type: EffectType::Tremolo }
+    }
+    
+    pub fn new_distortion() -> Self {
+        Self { effect_type: EffectType::Distortion }
+    }
+    
+    pub fn new_bitcrusher() -> Self {
+        Self { effect_type: EffectType::Bitcrusher }
+    }
+    
+    pub fn new_waveshaper() -> Self {
+        Self { effect_type: EffectType::Waveshaper }
+    }
+    
+    pub fn new_oversampling() -> Self {
+        Self { effect_type: EffectType::Oversampling }
+    }
+    
+    pub fn new_gate() -> Self {
+        Self { effect_type: EffectType::Gate }
+    }
+    
+    pub fn new_compressor() -> Self {
+        Self { effect_type: EffectType::Compressor }
+    }
+    
+    pub fn new_limiter() -> Self {
+        Self { effect_type: EffectType::Limiter }
+    }
+    
+    pub fn new_equalizer() -> Self {
+        Self { effect_type: EffectType::Equalizer }
+    }
+}
+
+impl EffectType {
+    /// Slot of the effect the key switches on and off, if it switches one
+    fn slot(self) -> Option<EffectSlot> {
+        match self {
+            EffectType::Delay => Some(EffectSlot::Delay),
+            EffectType::Reverb => Some(EffectSlot::Reverb),
+            EffectType::Flanger => Some(EffectSlot::Flanger),
+            EffectType::Chorus => Some(EffectSlot::Chorus),
+            EffectType::Phaser => Some(EffectSlot::Phaser),
+            EffectType::Tremolo => Some(EffectSlot::Tremolo),
+            EffectType::Distortion => Some(EffectSlot::Distortion),
+            EffectType::Bitcrusher => Some(EffectSlot::Bitcrusher),
+            EffectType::Waveshaper => Some(EffectSlot::Waveshaper),
+            EffectType::Gate => Some(EffectSlot::Gate),
+            EffectType::Compressor => Some(EffectSlot::Compressor),
+            EffectType::Limiter => Some(EffectSlot::Limiter),
+            EffectType::Equalizer => Some(EffectSlot::Equalizer),
+            EffectType::Oversampling => None,
+        }
+    }
 }
 
 impl InputCommand for EffectsToggleCommand {
@@ -36,31 +123,104 @@ impl InputCommand for EffectsToggleCommand {
             EffectType::Delay => Key::F10,
             EffectType::Reverb => Key::F11,
             EffectType::Flanger => Key::F12,
+            EffectType::Chorus => Key::NumPad1,
+            EffectType::Phaser => Key::NumPad2,
+            EffectType::Tremolo => Key::NumPad3,
+            EffectType::Distortion => Key::NumPad4,
+            EffectType::Bitcrusher => Key::NumPad5,
+            EffectType::Waveshaper => Key::NumPad6,
+            EffectType::Oversampling => Key::NumPadDot,
+            EffectType::Gate => Key::NumPad7,
+            EffectType::Compressor => Key::NumPad8,
+            EffectType::Limiter => Key::NumPad9,
+            EffectType::Equalizer => Key::NumPad0,
         };
         
         if window.is_key_pressed(key, KeyRepeat::No) {
-            match self.effect_type {
-                EffectType::Delay => {
-                    state.toggle_current_track_delay();
-                    let current_track_id = state.current_track_id;
-                    if !state.tracks[current_track_id].delay_enabled {
-                        state.tracks[current_track_id].delay_effect.reset();
-                    }
-                },
-                EffectType::Reverb => {
-                    state.toggle_current_track_reverb();
-                    let current_track_id = state.current_track_id;
-                    if !state.tracks[current_track_id].reverb_enabled {
-                        state.tracks[current_track_id].reverb_effect.reset();
-                    }
-                },
-                EffectType::Flanger => {
-                    state.toggle_current_track_flanger();
-                    let current_track_id = state.current_track_id;
-                    if !state.tracks[current_track_id].flanger_enabled {
-                        state.tracks[current_track_id].flanger_effect.reset();
-                    }
-                },
+            if matches!(self.effect_type, EffectType::Oversampling) {
+                state.cycle_current_track_oversampling();
+                let track = state.current_track();
+                println!("{}: oversampling {}", track.name, track.effects.get::<DistortionEffect>().oversampling());
+                return;
+            }
+            
+            // Holding shift toggles the effect on the master bus instead of the current track
+            if window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift) {
+                if let Some(slot) = self.effect_type.slot() {
+                    let enabled = state.toggle_master_effect(slot);
+                    println!("Master: {} {}", slot.to_string().to_lowercase(), if enabled { "on" } else { "off" });
+                }
+                return;
+            }
+            
+            if window.is_key_down(Key::LeftCtrl) || window.is_key_down(Key::RightCtrl) {
+                match self.effect_type {
+                    EffectType::Delay => {
+                        state.toggle_current_track_delay_ping_pong();
+                        let track = state.current_track();
+                        println!("{}: delay {}", track.name, if track.effects.get::<DelayEffect>().ping_pong() { "ping-pong" } else { "stereo" });
+                        return;
+                    },
+                    EffectType::Chorus => {
+                        state.cycle_current_track_chorus_voices();
+                        let track = state.current_track();
+                        println!("{}: chorus voices {}", track.name, track.effects.get::<ChorusEffect>().voices());
+                        return;
+                    },
+                    EffectType::Phaser => {
+                        state.cycle_current_track_phaser_stages();
+                        let track = state.current_track();
+                        println!("{}: phaser stages {}", track.name, track.effects.get::<PhaserEffect>().stages());
+                        return;
+                    },
+                    EffectType::Tremolo => {
+                        state.cycle_current_track_tremolo_shape();
+                        let track = state.current_track();
+                        println!("{}: tremolo shape {}", track.name, track.effects.get::<TremoloEffect>().shape());
+                        return;
+                    },
+                    EffectType::Distortion => {
+                        state.cycle_current_track_distortion_type();
+                        let track = state.current_track();
+                        println!("{}: distortion {}", track.name, track.effects.get::<DistortionEffect>().distortion_type());
+                        return;
+                    },
+                    EffectType::Compressor => {
+                        state.cycle_current_track_sidechain_source();
+                        let track = state.current_track();
+                        match track.sidechain_source {
+                            Some(source) => println!("{}: compressor keyed by {}", track.name, state.tracks[source].name),
+                            None => println!("{}: compressor keyed by itself", track.name),
+                        }
+                        return;
+                    },
+                    EffectType::Equalizer => {
+                        state.toggle_eq_target();
+                        println!("{}", state.describe_eq_selection());
+                        return;
+                    },
+                    EffectType::Waveshaper => {
+                        let result = state.cycle_current_track_waveshaper_curve();
+                        let track = state.current_track();
+                        match result {
+                            Ok(()) => println!("{}: waveshaper curve {}", track.name, track.effects.get::<WaveshaperEffect>().table_name()),
+                            Err(e) => println!("{}: could not load waveshaper table: {}", track.name, e),
+                        }
+                        return;
+                    },
+                    _ => {}
+                }
+            }
+            
+            if let Some(slot) = self.effect_type.slot() {
+                let enabled = state.toggle_current_track_effect(slot);
+                let track = state.current_track();
+                if enabled && slot == EffectSlot::Bitcrusher {
+                    let bitcrusher = track.effects.get::<BitcrusherEffect>();
+                    println!("{}: bitcrusher on ({:.0} bits, {:.0} Hz)", track.name, bitcrusher.bits(), bitcrusher.crush_rate());
+                } else {
+                    println!("{}: {} {}", track.name, slot.to_string().to_lowercase(), if enabled { "on" } else { "off" });
+                }
             }
         }
     }
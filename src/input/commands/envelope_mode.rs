@@ -0,0 +1,17 @@
+use minifb::{Key, KeyRepeat, Window};
+use rodio::Sink;
+use crate::state::State;
+use super::super::InputCommand;
+
+/// Command for switching the current track between its DAHDSR and breakpoint envelopes
+pub struct EnvelopeModeCommand;
+
+impl InputCommand for EnvelopeModeCommand {
+    fn execute(&self, state: &mut State, window: &mut Window, _sink: &mut Sink) {
+        if window.is_key_pressed(Key::G, KeyRepeat::No) {
+            state.toggle_current_track_envelope_mode();
+            let track = state.current_track();
+            println!("{} envelope: {}", track.name, track.envelope_mode);
+        }
+    }
+}
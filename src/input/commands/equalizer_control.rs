@@ -0,0 +1,52 @@
+use minifb::{Key, Window};
+use rodio::Sink;
+use crate::state::State;
+use super::super::InputCommand;
+
+/// Gain change of the selected band per key press in dB
+const GAIN_STEP_DB: f32 = 1.0;
+
+/// Command for editing the bands of the current track's equalizer, or of the master bus
+/// equalizer once Ctrl with the equalizer toggle key has switched to it
+///
+/// The band key selects the next band, with Shift the previous one and with Ctrl it
+/// bypasses the selected band. The gain keys boost and cut the band, with Ctrl they
+/// narrow and widen it instead. The frequency keys move it a third of an octave.
+pub struct EqualizerControlCommand {
+    action: EqualizerAction,
+}
+
+#[derive(Debug, Clone)]
+pub enum EqualizerAction {
+    SelectBand,
+    GainUp,
+    GainDown,
+    FrequencyUp,
+    FrequencyDown,
+}
+
+impl EqualizerControlCommand {
+    pub fn new(action: EqualizerAction) -> Self {
+        Self { action }
+    }
+}
+
+impl InputCommand for EqualizerControlCommand {
+    fn execute(&self, state: &mut State, window: &mut Window, _sink: &mut Sink) {
+        let shift_down = window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift);
+        let ctrl_down = window.is_key_down(Key::LeftCtrl) || window.is_key_down(Key::RightCtrl);
+
+        match (&self.action, ctrl_down) {
+            (EqualizerAction::SelectBand, false) => state.select_eq_band(!shift_down),
+            (EqualizerAction::SelectBand, true) => state.toggle_selected_eq_band(),
+            (EqualizerAction::GainUp, false) => state.adjust_selected_eq_band_gain(GAIN_STEP_DB),
+            (EqualizerAction::GainUp, true) => state.step_selected_eq_band_q(true),
+            (EqualizerAction::GainDown, false) => state.adjust_selected_eq_band_gain(-GAIN_STEP_DB),
+            (EqualizerAction::GainDown, true) => state.step_selected_eq_band_q(false),
+            (EqualizerAction::FrequencyUp, _) => state.step_selected_eq_band_frequency(true),
+            (EqualizerAction::FrequencyDown, _) => state.step_selected_eq_band_frequency(false),
+        }
+
+        println!("{}", state.describe_eq_selection());
+    }
+}
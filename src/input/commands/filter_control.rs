@@ -0,0 +1,57 @@
+use minifb::Window;
+use rodio::Sink;
+use crate::state::State;
+use super::super::InputCommand;
+
+/// Resonance change per key press
+const RESONANCE_STEP: f32 = 0.1;
+
+/// Command for controlling the current track's filter
+pub struct FilterControlCommand {
+    action: FilterAction,
+}
+
+#[derive(Debug, Clone)]
+pub enum FilterAction {
+    Toggle,
+    CycleMode,
+    CutoffUp,
+    CutoffDown,
+    ResonanceUp,
+    ResonanceDown,
+}
+
+impl FilterControlCommand {
+    pub fn new(action: FilterAction) -> Self {
+        Self { action }
+    }
+}
+
+impl InputCommand for FilterControlCommand {
+    fn execute(&self, state: &mut State, _window: &mut Window, _sink: &mut Sink) {
+        match &self.action {
+            FilterAction::Toggle => {
+                state.toggle_current_track_filter();
+                let track = state.current_track();
+                println!("Track {} ({}) filter: {}", track.id, track.name, track.filter_enabled);
+            },
+            FilterAction::CycleMode => {
+                state.cycle_current_track_filter_mode();
+                let track = state.current_track();
+                println!("Track {} ({}) filter mode: {}", track.id, track.name, track.filter.mode());
+            },
+            FilterAction::CutoffUp => {
+                state.increase_current_track_filter_cutoff();
+            },
+            FilterAction::CutoffDown => {
+                state.decrease_current_track_filter_cutoff();
+            },
+            FilterAction::ResonanceUp => {
+                state.adjust_current_track_filter_resonance(RESONANCE_STEP);
+            },
+            FilterAction::ResonanceDown => {
+                state.adjust_current_track_filter_resonance(-RESONANCE_STEP);
+            },
+        }
+    }
+}
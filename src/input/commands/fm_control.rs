@@ -0,0 +1,46 @@
+use minifb::{Key, Window};
+use rodio::Sink;
+use crate::state::State;
+use super::super::InputCommand;
+
+/// Command for editing the current track's FM patch
+///
+/// Does nothing but report when the track plays another waveform. Shift picks the
+/// alternative action of a key.
+pub struct FmControlCommand {
+    action: FmAction,
+}
+
+#[derive(Debug, Clone)]
+pub enum FmAction {
+    CycleAlgorithm,    // Shift: change the number of operators
+    PreviousParameter, // Shift: previous operator
+    NextParameter,     // Shift: next operator
+    ParameterDown,
+    ParameterUp,
+}
+
+impl FmControlCommand {
+    pub fn new(action: FmAction) -> Self {
+        Self { action }
+    }
+}
+
+impl InputCommand for FmControlCommand {
+    fn execute(&self, state: &mut State, window: &mut Window, _sink: &mut Sink) {
+        let shift_down = window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift);
+
+        match &self.action {
+            FmAction::CycleAlgorithm if shift_down => state.cycle_current_track_fm_operator_count(),
+            FmAction::CycleAlgorithm => state.cycle_current_track_fm_algorithm(),
+            FmAction::PreviousParameter if shift_down => state.select_fm_operator(false),
+            FmAction::PreviousParameter => state.select_fm_parameter(false),
+            FmAction::NextParameter if shift_down => state.select_fm_operator(true),
+            FmAction::NextParameter => state.select_fm_parameter(true),
+            FmAction::ParameterDown => state.step_selected_fm_parameter(false),
+            FmAction::ParameterUp => state.step_selected_fm_parameter(true),
+        }
+
+        println!("{}", state.describe_fm_selection());
+    }
+}
@@ -6,6 +6,20 @@ pub mod adsr_control;
 pub mod recording_control;
 pub mod effects_toggle;
 pub mod track_control;
+pub mod bounce;
+pub mod oscillator_mode;
+pub mod filter_control;
+pub mod envelope_mode;
+pub mod modulation_control;
+pub mod drum_mode;
+pub mod sample_load;
+pub mod fm_control;
+pub mod wavetable_control;
+pub mod unison_control;
+pub mod play_mode;
+pub mod equalizer_control;
+pub mod sample_rate;
+pub mod sample_edit;
 
 pub use keyboard_input::KeyboardInputCommand;
 pub use mouse_input::MouseInputCommand;
@@ -14,4 +28,18 @@ pub use octave_adjust::OctaveAdjustCommand;
 pub use adsr_control::ADSRControlCommand;
 pub use recording_control::RecordingControlCommand;
 pub use effects_toggle::EffectsToggleCommand;
-pub use track_control::{TrackControlCommand, TrackAction};
\ No newline at end of file
+pub use track_control::{TrackControlCommand, TrackAction};
+pub use bounce::BounceCommand;
+pub use oscillator_mode::OscillatorModeCommand;
+pub use filter_control::{FilterControlCommand, FilterAction};
+pub use envelope_mode::EnvelopeModeCommand;
+pub use modulation_control::{ModulationControlCommand, ModulationAction};
+pub use drum_mode::DrumModeCommand;
+pub use sample_load::SampleLoadCommand;
+pub use fm_control::{FmControlCommand, FmAction};
+pub use wavetable_control::{WavetableControlCommand, WavetableAction};
+pub use unison_control::{UnisonControlCommand, UnisonAction};
+pub use play_mode::{PlayModeCommand, PlayModeAction};
+pub use equalizer_control::{EqualizerControlCommand, EqualizerAction};
+pub use sample_rate::SampleRateCommand;
+pub use sample_edit::{SampleEditCommand, SampleEditAction};
\ No newline at end of file
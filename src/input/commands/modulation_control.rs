@@ -0,0 +1,70 @@
+use minifb::{Key, Window};
+use rodio::Sink;
+use crate::modulation::matrix::MAX_ROUTES;
+use crate::state::State;
+use super::super::InputCommand;
+
+/// Route amount change per key press
+const AMOUNT_STEP: f32 = 0.05;
+
+/// Tempo change per key press in BPM
+const TEMPO_STEP: f32 = 5.0;
+
+/// Command for editing the current track's modulation matrix
+///
+/// Every action works on the selected route; Shift picks the alternative action of a key.
+pub struct ModulationControlCommand {
+    action: ModulationAction,
+}
+
+#[derive(Debug, Clone)]
+pub enum ModulationAction {
+    AddRoute,          // Shift: remove the selected route
+    CycleSource,       // Shift: select the next route
+    CycleDestination,  // Shift: previous destination
+    AmountDown,
+    AmountUp,
+    CycleLfoShape,     // Shift: toggle tempo sync of the route's LFO
+    LfoRateDown,       // Shift: slower tempo
+    LfoRateUp,         // Shift: faster tempo
+}
+
+impl ModulationControlCommand {
+    pub fn new(action: ModulationAction) -> Self {
+        Self { action }
+    }
+}
+
+impl InputCommand for ModulationControlCommand {
+    fn execute(&self, state: &mut State, window: &mut Window, _sink: &mut Sink) {
+        let shift_down = window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift);
+
+        match &self.action {
+            ModulationAction::AddRoute if shift_down => state.remove_selected_route(),
+            ModulationAction::AddRoute => {
+                if state.current_track().modulation.routes.len() >= MAX_ROUTES {
+                    println!("{}: modulation matrix is full ({} routes)", state.current_track().name, MAX_ROUTES);
+                    return;
+                }
+                state.add_current_track_route();
+            },
+            ModulationAction::CycleSource if shift_down => state.select_next_route(),
+            ModulationAction::CycleSource => state.cycle_selected_route_source(),
+            ModulationAction::CycleDestination => state.cycle_selected_route_destination(!shift_down),
+            ModulationAction::AmountDown => state.adjust_selected_route_amount(-AMOUNT_STEP),
+            ModulationAction::AmountUp => state.adjust_selected_route_amount(AMOUNT_STEP),
+            ModulationAction::CycleLfoShape if shift_down => state.toggle_selected_route_lfo_sync(),
+            ModulationAction::CycleLfoShape => state.cycle_selected_route_lfo_shape(),
+            ModulationAction::LfoRateDown | ModulationAction::LfoRateUp if shift_down => {
+                let step = if matches!(self.action, ModulationAction::LfoRateUp) { TEMPO_STEP } else { -TEMPO_STEP };
+                state.set_tempo(state.tempo_bpm + step);
+                println!("Tempo: {:.0} BPM", state.tempo_bpm);
+                return;
+            },
+            ModulationAction::LfoRateDown => state.step_selected_route_lfo_rate(false),
+            ModulationAction::LfoRateUp => state.step_selected_route_lfo_rate(true),
+        }
+
+        println!("{}", state.describe_selected_route());
+    }
+}
@@ -2,8 +2,9 @@ use minifb::{Key, MouseButton, MouseMode, Window};
 use rodio::Sink;
 use crate::music_theory::note::Note;
 use crate::state::State;
-use crate::state::utils::{get_key_mappings, handle_musical_note};
-use crate::effects::AudioEffect;
+use crate::state::utils::{get_key_mappings, handle_musical_note, release_musical_note};
+use crate::modulation::EffectSlot;
+use crate::waveforms::breakpoint_envelope::EnvelopeMode;
 use super::super::InputCommand;
 
 /// Command for handling all mouse interactions
@@ -24,6 +25,13 @@ impl InputCommand for MouseInputCommand {
         state.mouse.left_clicked = mouse_clicked;
         state.mouse.left_pressed = mouse_pressed;
 
+        // Release the note played with the mouse once the button is let go
+        if !mouse_pressed {
+            if let Some(held) = state.mouse_held_note.take() {
+                release_musical_note(state, held);
+            }
+        }
+
         // Handle dragging
         if mouse_clicked {
             state.mouse.drag_start = Some((state.mouse.x, state.mouse.y));
@@ -49,12 +57,12 @@ impl InputCommand for MouseInputCommand {
         handle_adsr_fader_mouse(state, sink);
         
         // Handle tangent (sharp) key interactions FIRST (they have priority over regular keys)
-        if handle_tangent_mouse(state, sink) {
+        if handle_tangent_mouse(state) {
             return; // Exit if a tangent was clicked
         }
         
         // Handle regular keyboard key interactions
-        handle_keyboard_mouse(state, sink);
+        handle_keyboard_mouse(state);
         
         // Handle octave fader interactions
         handle_octave_fader_mouse(state);
@@ -76,20 +84,23 @@ impl InputCommand for MouseInputCommand {
     }
 }
 
-/// Handle mouse interactions with ADSR faders
+/// Handle mouse interactions with the envelope faders
+///
+/// In DAHDSR mode the faders set delay, attack, hold, decay, sustain and release.
+/// In breakpoint mode clicking a fader selects that breakpoint and dragging sets its level.
 pub fn handle_adsr_fader_mouse(state: &mut State, sink: &mut Sink) {
-    // ADSR fader positions (matching the draw_adsr_faders function)
+    // Envelope fader positions (matching the draw_adsr_faders function)
     let display_x = 164;
     let display_width = 164;
     let display_y = 4 * 51 + 17;
     let base_x = display_x + display_width + 104;
     let base_y = display_y;
 
-    let fader_width = 25;
+    let fader_width = 17;
     let fader_height = 50;
-    let fader_spacing = 30;
+    let fader_spacing = 22;
 
-    let adsr_params = ["attack", "decay", "sustain", "release"];
+    let adsr_params = ["delay", "attack", "hold", "decay", "sustain", "release"];
 
     for (i, param) in adsr_params.iter().enumerate() {
         let fader_x = base_x + i * fader_spacing;
@@ -105,12 +116,29 @@ pub fn handle_adsr_fader_mouse(state: &mut State, sink: &mut Sink) {
                 let normalized_value = 1.0 - (relative_y / fader_height as f32).clamp(0.0, 1.0);
                 let new_value = (normalized_value * 99.0) as u8;
 
-                // Update the appropriate ADSR parameter on current track
+                // Faders edit the breakpoints one by one in breakpoint mode
+                if state.current_track().envelope_mode == EnvelopeMode::Breakpoint {
+                    if state.mouse.left_clicked {
+                        state.select_breakpoint(i);
+                    }
+                    if state.selected_breakpoint == i {
+                        state.set_selected_breakpoint_level(normalized_value);
+                    }
+                    continue;
+                }
+
+                // Update the appropriate DAHDSR parameter on current track
                 match *param {
+                    "delay" => {
+                        state.tracks[state.current_track_id].delay = new_value;
+                    },
                     "attack" => {
                         state.tracks[state.current_track_id].attack = new_value;
                         state.attack = new_value; // Sync legacy state
                     },
+                    "hold" => {
+                        state.tracks[state.current_track_id].hold = new_value;
+                    },
                     "decay" => {
                         state.tracks[state.current_track_id].decay = new_value;
                         state.decay = new_value; // Sync legacy state
@@ -132,7 +160,7 @@ pub fn handle_adsr_fader_mouse(state: &mut State, sink: &mut Sink) {
 
 /// Handle mouse interactions with tangent (sharp) keys
 /// Returns true if a tangent was clicked, false otherwise
-pub fn handle_tangent_mouse(state: &mut State, sink: &mut Sink) -> bool {
+pub fn handle_tangent_mouse(state: &mut State) -> bool {
     let key_width = 64; // sprites.keys[KEY_IDLE].width as i32
     let key_height = 144; // sprites.keys[KEY_IDLE].height
     let tangent_width = 30; // sprites.tangents[TANGENT_IDLE].width as i32
@@ -163,32 +191,10 @@ pub fn handle_tangent_mouse(state: &mut State, sink: &mut Sink) -> bool {
             state.mouse.y <= (key_y + tangent_height as usize) as f32 {
 
             if state.mouse.left_clicked {
-                // Trigger the note
-                handle_musical_note(state, sink, note);
+                // Trigger the note, it sustains until the mouse button is released
+                let held = handle_musical_note(state, note);
                 state.pressed_key = Some((key, note));
-
-                // Record note if recording - record to current track
-                if state.recording_state == crate::state::RecordingState::Recording {
-                    // Finish previous note if there was one
-                    if let Some((start_time, prev_note, prev_octave)) = state.current_note_start.take() {
-                        let duration = start_time.elapsed().as_secs_f32();
-                        let timestamp = state.recording_start_time
-                            .map(|start| start.elapsed().as_secs_f32() - duration)
-                            .unwrap_or(0.0);
-
-                        // Add to current track instead of global recorded_notes
-                        state.add_note_to_current_track(crate::state::RecordedNote {
-                            note: prev_note,
-                            octave: prev_octave,
-                            timestamp,
-                            duration,
-                        });
-                    }
-
-                    // Start recording new note using current track's octave
-                    let current_track_octave = state.tracks[state.current_track_id].octave;
-                    state.current_note_start = Some((std::time::Instant::now(), note, current_track_octave));
-                }
+                state.mouse_held_note = Some(held);
                 return true; // Return true to indicate a tangent was clicked
             }
         }
@@ -197,7 +203,7 @@ pub fn handle_tangent_mouse(state: &mut State, sink: &mut Sink) -> bool {
 }
 
 /// Handle mouse interactions with keyboard keys
-pub fn handle_keyboard_mouse(state: &mut State, sink: &mut Sink) {
+pub fn handle_keyboard_mouse(state: &mut State) {
     // Virtual keyboard positioning (matching draw_idle_key_sprites exactly)
     // Keys are drawn from i=1 to i=7, at positions i * key_width
     let key_width = 64; // sprites.keys[KEY_IDLE].width
@@ -213,33 +219,10 @@ pub fn handle_keyboard_mouse(state: &mut State, sink: &mut Sink) {
             state.mouse.y >= key_y as f32 && state.mouse.y <= (key_y + key_height) as f32 {
 
             if state.mouse.left_clicked {
-                // Trigger the note
-                handle_musical_note(state, sink, note);
+                // Trigger the note, it sustains until the mouse button is released
+                let held = handle_musical_note(state, note);
                 state.pressed_key = Some((key, note));
-
-
-                // Record note if recording - record to current track
-                if state.recording_state == crate::state::RecordingState::Recording {
-                    // Finish previous note if there was one
-                    if let Some((start_time, prev_note, prev_octave)) = state.current_note_start.take() {
-                        let duration = start_time.elapsed().as_secs_f32();
-                        let timestamp = state.recording_start_time
-                            .map(|start| start.elapsed().as_secs_f32() - duration)
-                            .unwrap_or(0.0);
-
-                        // Add to current track instead of global recorded_notes
-                        state.add_note_to_current_track(crate::state::RecordedNote {
-                            note: prev_note,
-                            octave: prev_octave,
-                            timestamp,
-                            duration,
-                        });
-                    }
-
-                    // Start recording new note using current track's octave
-                    let current_track_octave = state.tracks[state.current_track_id].octave;
-                    state.current_note_start = Some((std::time::Instant::now(), note, current_track_octave));
-                }
+                state.mouse_held_note = Some(held);
                 return; // Exit after handling one key to avoid multiple triggers
             }
         }
@@ -345,6 +328,7 @@ pub fn handle_control_buttons_mouse(state: &mut State, sink: &mut Sink) {
         if state.mouse.left_clicked {
             // Stop all audio immediately
             sink.stop();
+            state.stop_all_voices();
 
             // Stop recording and playback
             state.stop_recording();
@@ -387,27 +371,15 @@ pub fn handle_effects_buttons_mouse(state: &mut State, sink: &mut Sink) {
                 match i {
                     0 => {
                         // Delay button
-                        state.toggle_current_track_delay();
-                        let current_track_id = state.current_track_id;
-                        if !state.tracks[current_track_id].delay_enabled {
-                            state.tracks[current_track_id].delay_effect.reset();
-                        }
+                        state.toggle_current_track_effect(EffectSlot::Delay);
                     },
                     1 => {
                         // Reverb button
-                        state.toggle_current_track_reverb();
-                        let current_track_id = state.current_track_id;
-                        if !state.tracks[current_track_id].reverb_enabled {
-                            state.tracks[current_track_id].reverb_effect.reset();
-                        }
+                        state.toggle_current_track_effect(EffectSlot::Reverb);
                     },
                     2 => {
                         // Flanger button
-                        state.toggle_current_track_flanger();
-                        let current_track_id = state.current_track_id;
-                        if !state.tracks[current_track_id].flanger_enabled {
-                            state.tracks[current_track_id].flanger_effect.reset();
-                        }
+                        state.toggle_current_track_effect(EffectSlot::Flanger);
                     },
                     _ => {}
                 }
@@ -539,6 +511,7 @@ pub fn handle_track_selection_mouse(state: &mut State, sink: &mut Sink) {
             if state.mouse.left_clicked {
                 // Stop everything
                 sink.stop(); // Stop all audio immediately
+                state.stop_all_voices();
                 state.stop_recording();
                 state.stop_playback();
                 state.stop_all_track_playback(); // Stop individual track playback
@@ -572,9 +545,9 @@ pub fn handle_track_selection_mouse(state: &mut State, sink: &mut Sink) {
                 state.decay = track.decay;
                 state.sustain = track.sustain;
                 state.release = track.release;
-                state.delay_enabled = track.delay_enabled;
-                state.reverb_enabled = track.reverb_enabled;
-                state.flanger_enabled = track.flanger_enabled;
+                state.delay_enabled = track.effects.is_enabled(EffectSlot::Delay);
+                state.reverb_enabled = track.effects.is_enabled(EffectSlot::Reverb);
+                state.flanger_enabled = track.effects.is_enabled(EffectSlot::Flanger);
                 
                 println!("Switched to track {}: {}", i + 1, track.name);
                 return; // Exit after handling one track
@@ -0,0 +1,18 @@
+use minifb::{Key, KeyRepeat, Window};
+use rodio::Sink;
+use crate::state::State;
+use super::super::InputCommand;
+
+/// Command for switching the current track between band-limited and raw (lo-fi) oscillators
+pub struct OscillatorModeCommand;
+
+impl InputCommand for OscillatorModeCommand {
+    fn execute(&self, state: &mut State, window: &mut Window, _sink: &mut Sink) {
+        if window.is_key_pressed(Key::L, KeyRepeat::No) {
+            state.toggle_current_track_band_limiting();
+            let track = state.current_track();
+            let mode = if track.band_limited { "band-limited" } else { "raw (lo-fi)" };
+            println!("{} oscillators: {}", track.name, mode);
+        }
+    }
+}
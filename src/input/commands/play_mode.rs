@@ -0,0 +1,56 @@
+use minifb::{Key, Window};
+use rodio::Sink;
+use crate::state::State;
+use super::super::InputCommand;
+
+/// Glide time change per key press in seconds
+const GLIDE_STEP: f32 = 0.02;
+
+/// Command for the current track's play mode and glide
+///
+/// The mode key cycles poly, mono and legato, with Shift the glide mode instead.
+/// The glide key lengthens the glide, Shift shortens it. The voice key adds a voice,
+/// Shift removes one, and the stealing key cycles which voice a new note takes over.
+pub struct PlayModeCommand {
+    action: PlayModeAction,
+}
+
+#[derive(Debug, Clone)]
+pub enum PlayModeAction {
+    CycleMode,
+    GlideTime,
+    VoiceCount,
+    VoiceStealing,
+}
+
+impl PlayModeCommand {
+    pub fn new(action: PlayModeAction) -> Self {
+        Self { action }
+    }
+}
+
+impl InputCommand for PlayModeCommand {
+    fn execute(&self, state: &mut State, window: &mut Window, _sink: &mut Sink) {
+        let shift_down = window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift);
+
+        match (&self.action, shift_down) {
+            (PlayModeAction::CycleMode, false) => state.cycle_current_track_play_mode(),
+            (PlayModeAction::CycleMode, true) => state.cycle_current_track_glide_mode(),
+            (PlayModeAction::GlideTime, false) => state.adjust_current_track_glide_time(GLIDE_STEP),
+            (PlayModeAction::GlideTime, true) => state.adjust_current_track_glide_time(-GLIDE_STEP),
+            (PlayModeAction::VoiceCount, false) => state.adjust_current_track_voice_count(1),
+            (PlayModeAction::VoiceCount, true) => state.adjust_current_track_voice_count(-1),
+            (PlayModeAction::VoiceStealing, _) => state.cycle_current_track_voice_stealing(),
+        }
+
+        let track = state.current_track();
+        let glide = track.voices.glide();
+        println!("{}: {} play mode, glide {:.2}s ({}), {} voices, steals the {}",
+                 track.name,
+                 track.voices.play_mode(),
+                 glide.time,
+                 glide.mode,
+                 track.voices.max_voices(),
+                 track.voices.stealing());
+    }
+}
@@ -1,54 +1,37 @@
 use minifb::{Key, Window};
 use rodio::Sink;
 use crate::state::State;
-use crate::state::utils::{handle_musical_note};
+use crate::music_theory::note::Note;
+use crate::state::utils::release_musical_note;
 use super::super::InputCommand;
 
 /// Command for handling recording and playback controls
 pub struct RecordingControlCommand;
 
 impl InputCommand for RecordingControlCommand {
-    fn execute(&self, state: &mut State, window: &mut Window, sink: &mut Sink) {
+    fn execute(&self, state: &mut State, window: &mut Window, _sink: &mut Sink) {
         // Handle playback logic
-        handle_playback(state, sink);
+        handle_playback(state);
         
-        // Handle key release timing and fade effects
-        let mut key_pressed = false;
-        
-        // Check if any musical key is currently pressed
-        if let Some(_) = state.pressed_key {
-            for (key, _, _, _) in crate::state::utils::get_key_mappings() {
-                if window.is_key_pressed(key, minifb::KeyRepeat::No) {
-                    key_pressed = true;
-                    break;
-                }
-            }
+        // Send a note-off for every musical key that has been let go
+        let (held_keys, released_keys): (Vec<_>, Vec<_>) = std::mem::take(&mut state.held_keys)
+            .into_iter()
+            .partition(|(key, _)| window.is_key_down(*key));
+        state.held_keys = held_keys;
+        for (_, held) in released_keys {
+            release_musical_note(state, held);
         }
         
-        // If no musical key is pressed, handle key release based on ADSR settings
-        if !key_pressed && state.pressed_key.is_some() && state.key_release_time.is_none() {
-            // For very quick release settings (0-10), stop immediately
-            if state.release <= 10 {
-                sink.stop(); // Immediate stop for instant release
-            }
-            // For other settings, let ADSR envelope handle the release naturally
-            // The ADSR envelope will auto-release after max_sustain_samples 
+        // Start the display fade-out once nothing is held any more
+        let note_held = !state.held_keys.is_empty() || state.mouse_held_note.is_some();
+        if !note_held && state.current_frequency.is_some() && state.key_release_time.is_none() {
             state.key_release_time = Some(std::time::Instant::now());
         }
-        
-        // Clear visual display quickly after audio has stopped
-        if let Some(release_time) = state.key_release_time {
-            let visual_clear_time = (state.release_normalized() * 2.0).max(0.1); // Minimum 100ms for visual feedback
-            if release_time.elapsed().as_secs_f32() > visual_clear_time {
-                state.current_frequency = None;
-                state.key_release_time = None;
-            }
-        }
     }
 }
 
 /// Handle multi-track playback of recorded loops during playback mode
-pub fn handle_playback(state: &mut State, sink: &mut Sink) {
+pub fn handle_playback(state: &mut State) {
     if state.recording_state != crate::state::RecordingState::Playing {
         return;
     }
@@ -103,25 +86,26 @@ pub fn handle_playback(state: &mut State, sink: &mut Sink) {
 
         // Play notes from all playing tracks
         for &track_id in &playing_tracks {
-            let track = &state.tracks[track_id];
-            
-            for recorded_note in &track.recorded_notes {
-                let note_start = recorded_note.timestamp;
+            let triggered_notes: Vec<(Note, i32, f32)> = state.tracks[track_id].recorded_notes.iter()
+                .filter(|recorded_note| {
+                    let note_start = recorded_note.timestamp;
+
+                    // Check if this note should start playing now
+                    (LAST_LOOP_TIME < note_start && loop_time >= note_start) ||
+                        (LAST_LOOP_TIME < 0.0 && loop_time >= note_start && loop_time < note_start + frame_time_threshold)
+                })
+                .map(|recorded_note| (recorded_note.note, recorded_note.octave, recorded_note.duration))
+                .collect();
 
-                // Check if this note should start playing now
-                let should_trigger = (LAST_LOOP_TIME < note_start && loop_time >= note_start) ||
-                    (LAST_LOOP_TIME < 0.0 && loop_time >= note_start && loop_time < note_start + frame_time_threshold);
+            for (note, octave, duration) in triggered_notes {
+                // Create mixer and play note on one of this track's voices, held for its recorded duration
+                let mixer = crate::audio::MultiTrackMixer::new(state.audio_config.sample_rate);
+                mixer.note_on_for(&mut state.tracks[track_id], note, octave, duration);
 
-                if should_trigger {
-                    // Create mixer and play note on this specific track
-                    let mixer = crate::audio::MultiTrackMixer::new(44100);
-                    mixer.play_note_on_track(track, recorded_note.note, sink);
-                    
-                    // Set visual feedback for any playing track
-                    state.pressed_key = Some((Key::Q, recorded_note.note));
-                    state.current_frequency = Some(recorded_note.note.frequency(recorded_note.octave));
-                    state.animation_start_time = std::time::Instant::now();
-                }
+                // Set visual feedback for any playing track
+                state.pressed_key = Some((Key::Q, note));
+                state.current_frequency = Some(note.frequency(octave));
+                state.animation_start_time = std::time::Instant::now();
             }
         }
 
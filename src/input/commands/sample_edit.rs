@@ -0,0 +1,45 @@
+use minifb::{Key, Window};
+use rodio::Sink;
+use crate::state::State;
+use super::super::InputCommand;
+
+/// Share of a sample's length a point moves per key press, and with Shift
+const POINT_STEP: f32 = 0.01;
+const POINT_FINE_STEP: f32 = 0.001;
+
+/// Command for trimming the current track's samples and setting their loops
+///
+/// The point key selects the next of start, end, loop start and loop end, with Shift the
+/// previous one. The earlier and later keys move the selected point in every loaded
+/// sample by a hundredth of its length, with Shift a thousandth.
+pub struct SampleEditCommand {
+    action: SampleEditAction,
+}
+
+#[derive(Debug, Clone)]
+pub enum SampleEditAction {
+    SelectPoint,
+    PointEarlier,
+    PointLater,
+}
+
+impl SampleEditCommand {
+    pub fn new(action: SampleEditAction) -> Self {
+        Self { action }
+    }
+}
+
+impl InputCommand for SampleEditCommand {
+    fn execute(&self, state: &mut State, window: &mut Window, _sink: &mut Sink) {
+        let shift_down = window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift);
+        let step = if shift_down { POINT_FINE_STEP } else { POINT_STEP };
+
+        match self.action {
+            SampleEditAction::SelectPoint => state.select_sample_point(!shift_down),
+            SampleEditAction::PointEarlier => state.move_current_track_sample_point(-step),
+            SampleEditAction::PointLater => state.move_current_track_sample_point(step),
+        }
+
+        println!("{}", state.describe_sample_point());
+    }
+}
@@ -0,0 +1,44 @@
+use minifb::{Key, KeyRepeat, Window};
+use rodio::Sink;
+use crate::state::State;
+use crate::waveforms::sampler::SampleMap;
+use super::super::InputCommand;
+
+/// Command for loading or unloading the current track's samples
+///
+/// Samples are read from samples/<track name>/*.wav. Shift switches loaded samples
+/// between one-shot and looped playback.
+pub struct SampleLoadCommand;
+
+impl InputCommand for SampleLoadCommand {
+    fn execute(&self, state: &mut State, window: &mut Window, _sink: &mut Sink) {
+        if !window.is_key_pressed(Key::P, KeyRepeat::No) {
+            return;
+        }
+
+        let shift_down = window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift);
+        let track_name = state.current_track().name.clone();
+
+        if shift_down {
+            match state.toggle_current_track_sample_looping() {
+                Some(true) => println!("{}: samples loop", track_name),
+                Some(false) => println!("{}: samples play once", track_name),
+                None => println!("{}: no samples loaded", track_name),
+            }
+            return;
+        }
+
+        match state.toggle_current_track_samples() {
+            Ok(true) => {
+                if let Some(map) = &state.current_track().sample_map {
+                    println!("{}: {} samples loaded from {}", track_name, map.zones.len(), SampleMap::directory_for_track(&track_name));
+                    for zone in &map.zones {
+                        println!("  {} (root key {}, {:.2}s)", zone.name, zone.root_key, zone.duration());
+                    }
+                }
+            },
+            Ok(false) => println!("{}: samples unloaded", track_name),
+            Err(e) => println!("{}: could not load samples: {}", track_name, e),
+        }
+    }
+}
@@ -0,0 +1,18 @@
+use minifb::{Key, KeyRepeat, Window};
+use rodio::Sink;
+use crate::state::State;
+use super::super::InputCommand;
+
+/// Command for stepping the engine through the supported sample rates
+///
+/// Every track and master effect is rebuilt for the new rate and sounding voices are stopped.
+pub struct SampleRateCommand;
+
+impl InputCommand for SampleRateCommand {
+    fn execute(&self, state: &mut State, window: &mut Window, _sink: &mut Sink) {
+        if window.is_key_pressed(Key::Key1, KeyRepeat::No) {
+            state.cycle_sample_rate();
+            println!("Sample rate: {} Hz", state.audio_config.sample_rate);
+        }
+    }
+}
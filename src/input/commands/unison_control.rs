@@ -0,0 +1,53 @@
+use minifb::{Key, Window};
+use rodio::Sink;
+use crate::state::State;
+use super::super::InputCommand;
+
+/// Detune change per key press in cents
+const DETUNE_STEP: f32 = 5.0;
+
+/// Stereo spread change per key press
+const SPREAD_STEP: f32 = 0.1;
+
+/// Command for the current track's unison stack
+///
+/// Each key raises its setting, Shift lowers it.
+pub struct UnisonControlCommand {
+    action: UnisonAction,
+}
+
+#[derive(Debug, Clone)]
+pub enum UnisonAction {
+    Voices,
+    Detune,
+    Spread,
+    ToggleRandomPhase,
+}
+
+impl UnisonControlCommand {
+    pub fn new(action: UnisonAction) -> Self {
+        Self { action }
+    }
+}
+
+impl InputCommand for UnisonControlCommand {
+    fn execute(&self, state: &mut State, window: &mut Window, _sink: &mut Sink) {
+        let shift_down = window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift);
+        let direction = if shift_down { -1.0 } else { 1.0 };
+
+        match &self.action {
+            UnisonAction::Voices => state.adjust_current_track_unison_voices(direction as i32),
+            UnisonAction::Detune => state.adjust_current_track_unison_detune(direction * DETUNE_STEP),
+            UnisonAction::Spread => state.adjust_current_track_unison_spread(direction * SPREAD_STEP),
+            UnisonAction::ToggleRandomPhase => state.toggle_current_track_unison_random_phase(),
+        }
+
+        let track = state.current_track();
+        println!("{}: unison {} voices, detune {:.0} cents, stereo spread {:.1}, {} phase",
+                 track.name,
+                 track.unison.voices,
+                 track.unison.detune,
+                 track.unison.stereo_spread,
+                 if track.unison.random_phase { "random" } else { "fixed" });
+    }
+}
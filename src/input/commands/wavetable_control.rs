@@ -0,0 +1,47 @@
+use minifb::Window;
+use rodio::Sink;
+use crate::state::State;
+use super::super::InputCommand;
+
+/// Table position change per key press
+const POSITION_STEP: f32 = 0.05;
+
+/// Command for choosing the current track's wavetable and moving through it
+pub struct WavetableControlCommand {
+    action: WavetableAction,
+}
+
+#[derive(Debug, Clone)]
+pub enum WavetableAction {
+    NextTable,
+    PositionDown,
+    PositionUp,
+}
+
+impl WavetableControlCommand {
+    pub fn new(action: WavetableAction) -> Self {
+        Self { action }
+    }
+}
+
+impl InputCommand for WavetableControlCommand {
+    fn execute(&self, state: &mut State, _window: &mut Window, _sink: &mut Sink) {
+        match &self.action {
+            WavetableAction::NextTable => {
+                if let Err(e) = state.cycle_current_track_wavetable() {
+                    println!("Could not load wavetable: {}", e);
+                    return;
+                }
+            },
+            WavetableAction::PositionDown => state.adjust_current_track_wavetable_position(-POSITION_STEP),
+            WavetableAction::PositionUp => state.adjust_current_track_wavetable_position(POSITION_STEP),
+        }
+
+        let track = state.current_track();
+        println!("{}: wavetable {} ({} frames), position {:.2}",
+                 track.name,
+                 track.wavetable.name,
+                 track.wavetable.frame_count(),
+                 track.wavetable_position);
+    }
+}
@@ -42,6 +42,12 @@ impl InputHandler {
         
         // Waveform toggle
         self.register_keyboard_command(Key::Tab, Arc::new(WaveformToggleCommand));
+        self.register_keyboard_command(Key::L, Arc::new(OscillatorModeCommand)); // band-limited / lo-fi
+        self.register_keyboard_command(Key::D, Arc::new(DrumModeCommand));       // waveform / drum kit
+        self.register_keyboard_command(Key::P, Arc::new(SampleLoadCommand));     // load / unload samples
+        self.register_keyboard_command(Key::Backquote, Arc::new(SampleEditCommand::new(SampleEditAction::SelectPoint)));  // Shift: previous point
+        self.register_keyboard_command(Key::Backspace, Arc::new(SampleEditCommand::new(SampleEditAction::PointEarlier))); // Shift: finer
+        self.register_keyboard_command(Key::Enter, Arc::new(SampleEditCommand::new(SampleEditAction::PointLater)));       // Shift: finer
         
         // Octave controls
         self.register_keyboard_command(Key::F1, Arc::new(OctaveAdjustCommand::new(false))); // decrease
@@ -56,11 +62,30 @@ impl InputHandler {
         self.register_keyboard_command(Key::F8, Arc::new(ADSRControlCommand::new_sustain(true)));   // increase sustain
         self.register_keyboard_command(Key::F9, Arc::new(ADSRControlCommand::new_release(false)));  // decrease release
         self.register_keyboard_command(Key::Key0, Arc::new(ADSRControlCommand::new_release(true))); // increase release
+        self.register_keyboard_command(Key::G, Arc::new(EnvelopeModeCommand)); // DAHDSR / breakpoint envelope
         
         // Effects controls
-        self.register_keyboard_command(Key::F10, Arc::new(EffectsToggleCommand::new_delay()));   // toggle delay
+        self.register_keyboard_command(Key::F10, Arc::new(EffectsToggleCommand::new_delay()));   // toggle delay (Ctrl: ping-pong)
         self.register_keyboard_command(Key::F11, Arc::new(EffectsToggleCommand::new_reverb()));  // toggle reverb
         self.register_keyboard_command(Key::F12, Arc::new(EffectsToggleCommand::new_flanger())); // toggle flanger
+        self.register_keyboard_command(Key::NumPad1, Arc::new(EffectsToggleCommand::new_chorus()));  // toggle chorus (Ctrl: voices)
+        self.register_keyboard_command(Key::NumPad2, Arc::new(EffectsToggleCommand::new_phaser()));  // toggle phaser (Ctrl: stages)
+        self.register_keyboard_command(Key::NumPad3, Arc::new(EffectsToggleCommand::new_tremolo())); // toggle tremolo (Ctrl: shape)
+        self.register_keyboard_command(Key::NumPad4, Arc::new(EffectsToggleCommand::new_distortion()));  // toggle distortion (Ctrl: curve)
+        self.register_keyboard_command(Key::NumPad5, Arc::new(EffectsToggleCommand::new_bitcrusher()));  // toggle bitcrusher
+        self.register_keyboard_command(Key::NumPad6, Arc::new(EffectsToggleCommand::new_waveshaper()));  // toggle waveshaper (Ctrl: curve)
+        self.register_keyboard_command(Key::NumPadDot, Arc::new(EffectsToggleCommand::new_oversampling())); // distortion/waveshaper oversampling
+        self.register_keyboard_command(Key::NumPad7, Arc::new(EffectsToggleCommand::new_gate()));        // toggle gate
+        self.register_keyboard_command(Key::NumPad8, Arc::new(EffectsToggleCommand::new_compressor()));  // toggle compressor (Ctrl: sidechain source)
+        self.register_keyboard_command(Key::NumPad9, Arc::new(EffectsToggleCommand::new_limiter()));     // toggle limiter
+        self.register_keyboard_command(Key::NumPad0, Arc::new(EffectsToggleCommand::new_equalizer()));   // toggle equalizer (Ctrl: edit track / master)
+        
+        // Equalizer band controls for current track
+        self.register_keyboard_command(Key::NumPadEnter, Arc::new(EqualizerControlCommand::new(EqualizerAction::SelectBand)));    // Ctrl: bypass band
+        self.register_keyboard_command(Key::NumPadPlus, Arc::new(EqualizerControlCommand::new(EqualizerAction::GainUp)));         // Ctrl: narrower
+        self.register_keyboard_command(Key::NumPadMinus, Arc::new(EqualizerControlCommand::new(EqualizerAction::GainDown)));      // Ctrl: wider
+        self.register_keyboard_command(Key::NumPadAsterisk, Arc::new(EqualizerControlCommand::new(EqualizerAction::FrequencyUp)));
+        self.register_keyboard_command(Key::NumPadSlash, Arc::new(EqualizerControlCommand::new(EqualizerAction::FrequencyDown)));
         
         // Track control commands (no keyboard switching - mouse only)
         self.register_keyboard_command(Key::M, Arc::new(TrackControlCommand::new(TrackAction::ToggleMute)));
@@ -69,6 +94,54 @@ impl InputHandler {
         self.register_keyboard_command(Key::Minus, Arc::new(TrackControlCommand::new(TrackAction::VolumeDown)));   // - key
         self.register_keyboard_command(Key::LeftBracket, Arc::new(TrackControlCommand::new(TrackAction::PanLeft)));  // [ key
         self.register_keyboard_command(Key::RightBracket, Arc::new(TrackControlCommand::new(TrackAction::PanRight))); // ] key
+        
+        // Filter controls for current track
+        self.register_keyboard_command(Key::Z, Arc::new(FilterControlCommand::new(FilterAction::Toggle)));
+        self.register_keyboard_command(Key::X, Arc::new(FilterControlCommand::new(FilterAction::CycleMode)));
+        self.register_keyboard_command(Key::C, Arc::new(FilterControlCommand::new(FilterAction::CutoffDown)));
+        self.register_keyboard_command(Key::V, Arc::new(FilterControlCommand::new(FilterAction::CutoffUp)));
+        self.register_keyboard_command(Key::Comma, Arc::new(FilterControlCommand::new(FilterAction::ResonanceDown)));  // , key
+        self.register_keyboard_command(Key::Period, Arc::new(FilterControlCommand::new(FilterAction::ResonanceUp)));   // . key
+        
+        // Modulation matrix of current track
+        self.register_keyboard_command(Key::N, Arc::new(ModulationControlCommand::new(ModulationAction::AddRoute)));
+        self.register_keyboard_command(Key::K, Arc::new(ModulationControlCommand::new(ModulationAction::CycleSource)));
+        self.register_keyboard_command(Key::J, Arc::new(ModulationControlCommand::new(ModulationAction::CycleDestination)));
+        self.register_keyboard_command(Key::I, Arc::new(ModulationControlCommand::new(ModulationAction::AmountDown)));
+        self.register_keyboard_command(Key::O, Arc::new(ModulationControlCommand::new(ModulationAction::AmountUp)));
+        self.register_keyboard_command(Key::H, Arc::new(ModulationControlCommand::new(ModulationAction::CycleLfoShape)));
+        self.register_keyboard_command(Key::Semicolon, Arc::new(ModulationControlCommand::new(ModulationAction::LfoRateDown)));   // ; key
+        self.register_keyboard_command(Key::Apostrophe, Arc::new(ModulationControlCommand::new(ModulationAction::LfoRateUp)));   // ' key
+        
+        // FM patch of current track
+        self.register_keyboard_command(Key::A, Arc::new(FmControlCommand::new(FmAction::CycleAlgorithm)));
+        self.register_keyboard_command(Key::Left, Arc::new(FmControlCommand::new(FmAction::PreviousParameter)));
+        self.register_keyboard_command(Key::Right, Arc::new(FmControlCommand::new(FmAction::NextParameter)));
+        self.register_keyboard_command(Key::Down, Arc::new(FmControlCommand::new(FmAction::ParameterDown)));
+        self.register_keyboard_command(Key::Up, Arc::new(FmControlCommand::new(FmAction::ParameterUp)));
+        
+        // Wavetable of current track
+        self.register_keyboard_command(Key::Home, Arc::new(WavetableControlCommand::new(WavetableAction::NextTable)));
+        self.register_keyboard_command(Key::PageDown, Arc::new(WavetableControlCommand::new(WavetableAction::PositionDown)));
+        self.register_keyboard_command(Key::PageUp, Arc::new(WavetableControlCommand::new(WavetableAction::PositionUp)));
+        
+        // Unison stack of current track (Shift lowers)
+        self.register_keyboard_command(Key::Insert, Arc::new(UnisonControlCommand::new(UnisonAction::Voices)));
+        self.register_keyboard_command(Key::Delete, Arc::new(UnisonControlCommand::new(UnisonAction::Detune)));
+        self.register_keyboard_command(Key::End, Arc::new(UnisonControlCommand::new(UnisonAction::Spread)));
+        self.register_keyboard_command(Key::Backslash, Arc::new(UnisonControlCommand::new(UnisonAction::ToggleRandomPhase)));
+        
+        // Play mode and glide of current track
+        self.register_keyboard_command(Key::F, Arc::new(PlayModeCommand::new(PlayModeAction::CycleMode)));  // Shift: glide mode
+        self.register_keyboard_command(Key::Slash, Arc::new(PlayModeCommand::new(PlayModeAction::GlideTime))); // / key, Shift shortens
+        self.register_keyboard_command(Key::Key8, Arc::new(PlayModeCommand::new(PlayModeAction::VoiceCount)));  // Shift: fewer voices
+        self.register_keyboard_command(Key::Key9, Arc::new(PlayModeCommand::new(PlayModeAction::VoiceStealing)));
+        
+        // Engine sample rate
+        self.register_keyboard_command(Key::Key1, Arc::new(SampleRateCommand));
+        
+        // Offline render of all recorded tracks
+        self.register_keyboard_command(Key::B, Arc::new(BounceCommand));  // 16-bit (Shift: 24-bit, Ctrl: 32-bit float)
     }
     
     /// Register a keyboard command for a specific key
@@ -80,7 +153,7 @@ impl InputHandler {
     pub fn handle_keyboard_input(&self, state: &mut State, window: &mut Window, sink: &mut Sink) {
         for (key, command) in &self.keyboard_commands {
             if window.is_key_pressed(*key, minifb::KeyRepeat::No) || 
-               (matches!(key, Key::F3 | Key::F4 | Key::F5 | Key::F6 | Key::F7 | Key::F8 | Key::F9 | Key::Key0) && 
+               (matches!(key, Key::F3 | Key::F4 | Key::F5 | Key::F6 | Key::F7 | Key::F8 | Key::F9 | Key::Key0 | Key::C | Key::V | Key::Comma | Key::Period | Key::I | Key::O | Key::Semicolon | Key::Apostrophe | Key::Up | Key::Down | Key::PageUp | Key::PageDown | Key::Delete | Key::End | Key::Slash | Key::NumPadPlus | Key::NumPadMinus | Key::NumPadAsterisk | Key::NumPadSlash | Key::Backspace | Key::Enter) && 
                 window.is_key_pressed(*key, minifb::KeyRepeat::Yes)) {
                 command.execute(state, window, sink);
                 // For musical note keys, return early to prevent multiple keys being processed
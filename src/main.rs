@@ -13,6 +13,7 @@ mod input;
 mod effects;
 mod audio;
 mod midi;
+mod modulation;
 
 fn main() {
 
@@ -0,0 +1,215 @@
+use std::fmt;
+use crate::waveforms::random::Random;
+use crate::waveforms::sine_wave::sine_at;
+use crate::waveforms::triangle_wave::triangle_at;
+use crate::waveforms::sawtooth_wave::sawtooth_at;
+use crate::waveforms::square_wave::square_at;
+
+/// Slowest and fastest free-running LFO rates in Hz
+pub const MIN_LFO_RATE_HZ: f32 = 0.05;
+pub const MAX_LFO_RATE_HZ: f32 = 20.0;
+
+/// Factor applied to a free-running rate per adjustment step
+const RATE_STEP_FACTOR: f32 = 1.25;
+
+/// Shape of a low frequency oscillator
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LfoShape {
+    Sine,
+    Triangle,
+    Sawtooth,
+    Square,
+    SampleAndHold,  // New random value at the start of every cycle
+}
+
+impl LfoShape {
+    /// The next shape in the cycle, wrapping around
+    pub fn next(&self) -> LfoShape {
+        match self {
+            LfoShape::Sine => LfoShape::Triangle,
+            LfoShape::Triangle => LfoShape::Sawtooth,
+            LfoShape::Sawtooth => LfoShape::Square,
+            LfoShape::Square => LfoShape::SampleAndHold,
+            LfoShape::SampleAndHold => LfoShape::Sine,
+        }
+    }
+}
+
+impl fmt::Display for LfoShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LfoShape::Sine => write!(f, "Sine"),
+            LfoShape::Triangle => write!(f, "Triangle"),
+            LfoShape::Sawtooth => write!(f, "Sawtooth"),
+            LfoShape::Square => write!(f, "Square"),
+            LfoShape::SampleAndHold => write!(f, "Sample & Hold"),
+        }
+    }
+}
+
+/// Note length of one LFO cycle when synced to the tempo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SyncDivision {
+    TwoBars,
+    Whole,
+    Half,
+    Quarter,
+    Eighth,
+    Sixteenth,
+}
+
+impl SyncDivision {
+    /// Length of one cycle in beats (quarter notes)
+    pub fn beats(&self) -> f32 {
+        match self {
+            SyncDivision::TwoBars => 8.0,
+            SyncDivision::Whole => 4.0,
+            SyncDivision::Half => 2.0,
+            SyncDivision::Quarter => 1.0,
+            SyncDivision::Eighth => 0.5,
+            SyncDivision::Sixteenth => 0.25,
+        }
+    }
+
+    /// The next shorter division, staying at the shortest
+    pub fn faster(&self) -> SyncDivision {
+        match self {
+            SyncDivision::TwoBars => SyncDivision::Whole,
+            SyncDivision::Whole => SyncDivision::Half,
+            SyncDivision::Half => SyncDivision::Quarter,
+            SyncDivision::Quarter => SyncDivision::Eighth,
+            SyncDivision::Eighth | SyncDivision::Sixteenth => SyncDivision::Sixteenth,
+        }
+    }
+
+    /// The next longer division, staying at the longest
+    pub fn slower(&self) -> SyncDivision {
+        match self {
+            SyncDivision::TwoBars | SyncDivision::Whole => SyncDivision::TwoBars,
+            SyncDivision::Half => SyncDivision::Whole,
+            SyncDivision::Quarter => SyncDivision::Half,
+            SyncDivision::Eighth => SyncDivision::Quarter,
+            SyncDivision::Sixteenth => SyncDivision::Eighth,
+        }
+    }
+}
+
+impl fmt::Display for SyncDivision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncDivision::TwoBars => write!(f, "2/1"),
+            SyncDivision::Whole => write!(f, "1/1"),
+            SyncDivision::Half => write!(f, "1/2"),
+            SyncDivision::Quarter => write!(f, "1/4"),
+            SyncDivision::Eighth => write!(f, "1/8"),
+            SyncDivision::Sixteenth => write!(f, "1/16"),
+        }
+    }
+}
+
+/// Speed of an LFO, either free-running or locked to the tempo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LfoRate {
+    Hertz(f32),
+    Synced(SyncDivision),
+}
+
+impl fmt::Display for LfoRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LfoRate::Hertz(rate) => write!(f, "{:.2} Hz", rate),
+            LfoRate::Synced(division) => write!(f, "{} (synced)", division),
+        }
+    }
+}
+
+/// Low frequency oscillator producing bipolar values (-1.0 to 1.0) for the modulation matrix
+#[derive(Debug, Clone)]
+pub struct Lfo {
+    pub shape: LfoShape,
+    pub rate: LfoRate,
+    phase: f32,         // Position in the current cycle (0 to 1)
+    held_value: f32,    // Current sample-and-hold value
+    random: Random,
+}
+
+impl Lfo {
+    pub fn new(shape: LfoShape, rate: LfoRate, seed: u32) -> Self {
+        let mut random = Random::new(seed);
+        let held_value = random.next_bipolar();
+
+        Self {
+            shape,
+            rate,
+            phase: 0.0,
+            held_value,
+            random,
+        }
+    }
+
+    /// Cycles per second at the given tempo
+    pub fn frequency(&self, tempo_bpm: f32) -> f32 {
+        match self.rate {
+            LfoRate::Hertz(rate) => rate,
+            LfoRate::Synced(division) => tempo_bpm / 60.0 / division.beats(),
+        }
+    }
+
+    /// Current output (-1.0 to 1.0)
+    pub fn value(&self) -> f32 {
+        match self.shape {
+            LfoShape::Sine => sine_at(self.phase),
+            LfoShape::Triangle => triangle_at(self.phase),
+            LfoShape::Sawtooth => sawtooth_at(self.phase),
+            LfoShape::Square => square_at(self.phase),
+            LfoShape::SampleAndHold => self.held_value,
+        }
+    }
+
+    /// Move the LFO forward by a number of samples
+    pub fn advance(&mut self, samples: usize, sample_rate: u32, tempo_bpm: f32) {
+        self.phase += self.frequency(tempo_bpm) * samples as f32 / sample_rate as f32;
+        if self.phase >= 1.0 {
+            self.phase = self.phase.fract();
+            self.held_value = self.random.next_bipolar();
+        }
+    }
+
+    /// Speed up (or slow down) by one step, a step being a note division when synced
+    pub fn step_rate(&mut self, faster: bool) {
+        self.rate = match self.rate {
+            LfoRate::Hertz(rate) => {
+                let rate = if faster { rate * RATE_STEP_FACTOR } else { rate / RATE_STEP_FACTOR };
+                LfoRate::Hertz(rate.clamp(MIN_LFO_RATE_HZ, MAX_LFO_RATE_HZ))
+            }
+            LfoRate::Synced(division) => {
+                LfoRate::Synced(if faster { division.faster() } else { division.slower() })
+            }
+        };
+    }
+
+    /// Switch between a free-running rate and a tempo-synced division
+    ///
+    /// The new rate is the one closest to the old at the given tempo.
+    pub fn toggle_sync(&mut self, tempo_bpm: f32) {
+        let frequency = self.frequency(tempo_bpm);
+        self.rate = match self.rate {
+            LfoRate::Hertz(_) => {
+                let beats = tempo_bpm / 60.0 / frequency;
+                let divisions = [
+                    SyncDivision::TwoBars,
+                    SyncDivision::Whole,
+                    SyncDivision::Half,
+                    SyncDivision::Quarter,
+                    SyncDivision::Eighth,
+                    SyncDivision::Sixteenth,
+                ];
+                let closest = divisions.into_iter()
+                    .min_by(|a, b| (a.beats().ln() - beats.ln()).abs().total_cmp(&(b.beats().ln() - beats.ln()).abs()))
+                    .unwrap_or(SyncDivision::Quarter);
+                LfoRate::Synced(closest)
+            }
+            LfoRate::Synced(_) => LfoRate::Hertz(frequency.clamp(MIN_LFO_RATE_HZ, MAX_LFO_RATE_HZ)),
+        };
+    }
+}
@@ -0,0 +1,290 @@
+use std::fmt;
+use crate::waveforms::adsr_envelope::EnvelopeGate;
+use crate::waveforms::breakpoint_envelope::{Dahdsr, EnvelopeGenerator, EnvelopeShape};
+use crate::waveforms::envelope_curve::EnvelopeCurve;
+use super::lfo::{Lfo, LfoRate, LfoShape, SyncDivision};
+
+/// Number of LFOs every track has
+pub const LFO_COUNT: usize = 3;
+
+/// Most routes a track's matrix can hold
+pub const MAX_ROUTES: usize = 8;
+
+/// Pitch change of a pitch route at full amount, in semitones
+pub const PITCH_RANGE_SEMITONES: f32 = 12.0;
+
+/// Tempo tempo-synced LFOs follow until another is set
+pub const DEFAULT_TEMPO_BPM: f32 = 120.0;
+
+/// Where a route takes its value from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ModSource {
+    Lfo(usize),   // One of the track's LFOs (-1.0 to 1.0)
+    Envelope,     // The track's modulation envelope (0.0 to 1.0)
+    AmpEnvelope,  // A copy of the amplitude envelope of the latest note (0.0 to 1.0)
+}
+
+impl ModSource {
+    /// The next source in the cycle, wrapping around
+    pub fn next(&self) -> ModSource {
+        match self {
+            ModSource::Lfo(index) if index + 1 < LFO_COUNT => ModSource::Lfo(index + 1),
+            ModSource::Lfo(_) => ModSource::Envelope,
+            ModSource::Envelope => ModSource::AmpEnvelope,
+            ModSource::AmpEnvelope => ModSource::Lfo(0),
+        }
+    }
+}
+
+impl fmt::Display for ModSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModSource::Lfo(index) => write!(f, "LFO {}", index + 1),
+            ModSource::Envelope => write!(f, "Mod Envelope"),
+            ModSource::AmpEnvelope => write!(f, "Amp Envelope"),
+        }
+    }
+}
+
+/// Effect of a track that a route can reach
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EffectSlot {
+    Gate,
+    Equalizer,
+    Compressor,
+    Distortion,
+    Bitcrusher,
+    Waveshaper,
+    Delay,
+    Reverb,
+    Flanger,
+    Chorus,
+    Phaser,
+    Tremolo,
+    Limiter,
+}
+
+impl EffectSlot {
+    /// Every slot, in the order the effects run
+    pub const ALL: [EffectSlot; 13] = [
+        EffectSlot::Gate,
+        EffectSlot::Equalizer,
+        EffectSlot::Compressor,
+        EffectSlot::Distortion,
+        EffectSlot::Bitcrusher,
+        EffectSlot::Waveshaper,
+        EffectSlot::Delay,
+        EffectSlot::Reverb,
+        EffectSlot::Flanger,
+        EffectSlot::Chorus,
+        EffectSlot::Phaser,
+        EffectSlot::Tremolo,
+        EffectSlot::Limiter,
+    ];
+}
+
+impl fmt::Display for EffectSlot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EffectSlot::Gate => write!(f, "Gate"),
+            EffectSlot::Equalizer => write!(f, "Equalizer"),
+            EffectSlot::Compressor => write!(f, "Compressor"),
+            EffectSlot::Distortion => write!(f, "Distortion"),
+            EffectSlot::Bitcrusher => write!(f, "Bitcrusher"),
+            EffectSlot::Waveshaper => write!(f, "Waveshaper"),
+            EffectSlot::Delay => write!(f, "Delay"),
+            EffectSlot::Reverb => write!(f, "Reverb"),
+            EffectSlot::Flanger => write!(f, "Flanger"),
+            EffectSlot::Chorus => write!(f, "Chorus"),
+            EffectSlot::Phaser => write!(f, "Phaser"),
+            EffectSlot::Tremolo => write!(f, "Tremolo"),
+            EffectSlot::Limiter => write!(f, "Limiter"),
+        }
+    }
+}
+
+/// What a route modulates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ModDestination {
+    Pitch,                     // Voice pitch, PITCH_RANGE_SEMITONES at full amount
+    FilterCutoff,              // Filter cutoff knob position
+    Volume,                    // Track volume
+    Pan,                       // Track pan
+    WavetablePosition,         // Position in the track's wavetable
+    Effect(EffectSlot, usize), // Normalized parameter of a track effect, by parameter index
+}
+
+/// Connection from a source to a destination
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModRoute {
+    pub source: ModSource,
+    pub destination: ModDestination,
+    pub amount: f32,  // Depth of the route (-1.0 to 1.0)
+}
+
+impl ModRoute {
+    pub fn new(source: ModSource, destination: ModDestination, amount: f32) -> Self {
+        Self {
+            source,
+            destination,
+            amount: amount.clamp(-1.0, 1.0),
+        }
+    }
+}
+
+/// Summed offsets of every route for one block, applied on top of the track's own settings
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Modulation {
+    pub pitch: f32,   // Semitones
+    pub cutoff: f32,  // Cutoff knob offset
+    pub volume: f32,
+    pub pan: f32,
+    pub wavetable_position: f32,
+    pub effects: Vec<(EffectSlot, usize, f32)>,  // Normalized parameter offsets
+}
+
+impl Modulation {
+    /// Parameter offsets for one effect
+    pub fn effect_offsets(&self, slot: EffectSlot) -> impl Iterator<Item = (usize, f32)> + '_ {
+        self.effects.iter()
+            .filter(move |(effect_slot, _, _)| *effect_slot == slot)
+            .map(|&(_, parameter, offset)| (parameter, offset))
+    }
+
+    fn add_effect_offset(&mut self, slot: EffectSlot, parameter: usize, offset: f32) {
+        match self.effects.iter_mut().find(|(s, p, _)| *s == slot && *p == parameter) {
+            Some((_, _, total)) => *total += offset,
+            None => self.effects.push((slot, parameter, offset)),
+        }
+    }
+}
+
+/// Envelope sources following the latest note of the track
+#[derive(Debug, Clone)]
+struct EnvelopeSources {
+    envelope: EnvelopeGenerator,
+    amp_envelope: EnvelopeGenerator,
+    gate: EnvelopeGate,
+}
+
+/// A track's LFOs and envelope sources together with the routes connecting them to destinations
+#[derive(Debug, Clone)]
+pub struct ModulationMatrix {
+    pub lfos: Vec<Lfo>,
+    pub routes: Vec<ModRoute>,
+    pub envelope: Dahdsr,  // Shape of the modulation envelope source
+    pub tempo_bpm: f32,    // Tempo for synced LFOs
+    envelope_sources: Option<EnvelopeSources>,
+}
+
+impl ModulationMatrix {
+    /// Create an empty matrix, the seed keeps the random LFOs of different tracks apart
+    pub fn new(seed: u32) -> Self {
+        let lfos = vec![
+            Lfo::new(LfoShape::Sine, LfoRate::Hertz(5.0), seed.wrapping_mul(3) + 1),
+            Lfo::new(LfoShape::Triangle, LfoRate::Hertz(0.5), seed.wrapping_mul(3) + 2),
+            Lfo::new(LfoShape::SampleAndHold, LfoRate::Synced(SyncDivision::Eighth), seed.wrapping_mul(3) + 3),
+        ];
+
+        let envelope = Dahdsr {
+            delay: 0.0,
+            attack: 0.2,
+            hold: 0.0,
+            decay: 0.6,
+            sustain: 0.3,
+            release: 0.5,
+            attack_curve: EnvelopeCurve::Linear,
+            decay_curve: EnvelopeCurve::Exponential,
+            release_curve: EnvelopeCurve::Exponential,
+        };
+
+        Self {
+            lfos,
+            routes: Vec::new(),
+            envelope,
+            tempo_bpm: DEFAULT_TEMPO_BPM,
+            envelope_sources: None,
+        }
+    }
+
+    /// Add a route, returning its index, or None when the matrix is full
+    pub fn add_route(&mut self, route: ModRoute) -> Option<usize> {
+        if self.routes.len() >= MAX_ROUTES {
+            return None;
+        }
+        self.routes.push(route);
+        Some(self.routes.len() - 1)
+    }
+
+    pub fn remove_route(&mut self, index: usize) {
+        if index < self.routes.len() {
+            self.routes.remove(index);
+        }
+    }
+
+    /// Restart the envelope sources for a new note
+    ///
+    /// The envelopes follow the note's gate, releasing when it closes.
+    pub fn trigger(&mut self, amp_envelope: EnvelopeShape, gate: EnvelopeGate, sample_rate: u32) {
+        self.envelope_sources = Some(EnvelopeSources {
+            envelope: EnvelopeGenerator::new(self.envelope.shape(), sample_rate),
+            amp_envelope: EnvelopeGenerator::new(amp_envelope, sample_rate),
+            gate,
+        });
+    }
+
+    /// Keep the envelope sources running for a legato note, releasing when its gate closes
+    pub fn continue_with(&mut self, gate: EnvelopeGate) {
+        if let Some(sources) = &mut self.envelope_sources {
+            sources.gate = gate;
+        }
+    }
+
+    /// Forget the envelope sources, as after a reset of the track
+    pub fn reset(&mut self) {
+        self.envelope_sources = None;
+    }
+
+    /// Value of a source at the start of the current block
+    pub fn source_value(&self, source: ModSource) -> f32 {
+        match source {
+            ModSource::Lfo(index) => self.lfos.get(index).map_or(0.0, |lfo| lfo.value()),
+            ModSource::Envelope => self.envelope_sources.as_ref().map_or(0.0, |sources| sources.envelope.level()),
+            ModSource::AmpEnvelope => self.envelope_sources.as_ref().map_or(0.0, |sources| sources.amp_envelope.level()),
+        }
+    }
+
+    /// Sum the routes for the next block of `frames` samples, then move every source past the block
+    pub fn advance(&mut self, frames: usize, sample_rate: u32) -> Modulation {
+        let mut modulation = Modulation::default();
+
+        for route in &self.routes {
+            let value = self.source_value(route.source) * route.amount;
+            match route.destination {
+                ModDestination::Pitch => modulation.pitch += value * PITCH_RANGE_SEMITONES,
+                ModDestination::FilterCutoff => modulation.cutoff += value,
+                ModDestination::Volume => modulation.volume += value,
+                ModDestination::Pan => modulation.pan += value,
+                ModDestination::WavetablePosition => modulation.wavetable_position += value,
+                ModDestination::Effect(slot, parameter) => modulation.add_effect_offset(slot, parameter, value),
+            }
+        }
+
+        for lfo in &mut self.lfos {
+            lfo.advance(frames, sample_rate, self.tempo_bpm);
+        }
+
+        if let Some(sources) = &mut self.envelope_sources {
+            if !sources.gate.is_open() {
+                sources.envelope.release();
+                sources.amp_envelope.release();
+            }
+            for _ in 0..frames {
+                sources.envelope.next_level();
+                sources.amp_envelope.next_level();
+            }
+        }
+
+        modulation
+    }
+}
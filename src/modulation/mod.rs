@@ -0,0 +1,5 @@
+pub mod lfo;
+pub mod matrix;
+
+pub use lfo::Lfo;
+pub use matrix::{EffectSlot, ModDestination, ModRoute, ModSource, Modulation, ModulationMatrix};
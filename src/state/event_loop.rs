@@ -43,7 +43,7 @@ pub fn start_event_loop(state: &mut State, sink: &mut Sink, sprites: &Sprites) {
 
     // Initialize command pattern handlers
     let input_handler = InputHandler::new();
-    let audio_updater = AudioStateUpdater::new();
+    let mut audio_updater = AudioStateUpdater::new();
     let visual_updater = VisualStateUpdater::new();
     let recording_updater = RecordingStateUpdater::new();
     let mouse_updater = MouseStateUpdater::new();
@@ -1,12 +1,34 @@
+use std::path::Path;
 use std::time::{Duration, Instant};
 
+use minifb::Key;
+use crate::graphics::constants::{WAVEFORM_SINE, WAVEFORM_SQUARE, WAVEFORM_TRIANGLE, WAVEFORM_SAWTOOTH, WAVEFORM_NOISE, WAVEFORM_FM, WAVEFORM_WAVETABLE};
 use crate::music_theory::{OCTAVE_LOWER_BOUND, OCTAVE_UPPER_BOUND};
 use crate::music_theory::note::Note;
-use crate::waveforms::WaveformType;
-use crate::effects::{DelayEffect, ReverbEffect, FlangerEffect};
+use crate::waveforms::Waveform;
+use crate::waveforms::noise::NoiseColor;
+use crate::waveforms::fm::{FmParameter, FmPatch, FmPreset};
+use crate::waveforms::drum_voice::DrumVoice;
+use crate::waveforms::sampler::{SampleMap, SamplePoint};
+use crate::waveforms::wavetable::{Wavetable, WAVETABLES_DIRECTORY};
+use crate::effects::{AudioEffect, EffectChain, DelayEffect, ReverbEffect, FlangerEffect, ChorusEffect, PhaserEffect, TremoloEffect, DistortionEffect, DistortionType, BitcrusherEffect, WaveshaperEffect, ShaperCurve, StateVariableFilter, FilterMode, BrickwallLimiter, LimiterEffect, CompressorEffect, GateEffect, EqualizerEffect};
+use crate::effects::filter::MAX_CUTOFF_HZ;
+use crate::effects::chorus::MAX_CHORUS_VOICES;
+use crate::effects::phaser::MAX_PHASER_STAGES;
+use crate::effects::waveshaper::WAVESHAPER_TABLES_DIRECTORY;
+use crate::audio::AudioConfig;
+use crate::audio::config::SAMPLE_RATES;
+use crate::audio::voice::{Glide, GlideMode, PlayMode, VoicePool, VoiceStealing, DEFAULT_VOICE_COUNT, MAX_VOICE_COUNT};
+use crate::audio::unison::{Unison, MAX_DETUNE_CENTS, MAX_UNISON_VOICES};
+use crate::waveforms::adsr_envelope::EnvelopeGate;
+use crate::waveforms::envelope_curve::EnvelopeCurve;
+use crate::waveforms::breakpoint_envelope::{Breakpoint, Dahdsr, EnvelopeMode, EnvelopeShape, MAX_BREAKPOINTS};
+use crate::modulation::{EffectSlot, Lfo, ModDestination, ModRoute, ModSource, ModulationMatrix};
+use crate::modulation::lfo::LfoShape;
+use crate::modulation::matrix::DEFAULT_TEMPO_BPM;
 
 // DAW Track System
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct Track {
     pub id: usize,
     pub name: String,
@@ -14,24 +36,43 @@ pub struct Track {
     pub volume: f32,        // 0.0 - 1.0
     pub pan: f32,           // -1.0 (left) to 1.0 (right)
     pub playing: bool,      // Whether this track's loop is currently playing
-    pub waveform: WaveformType,
+    pub waveform: Waveform,
+    pub band_limited: bool, // PolyBLEP square/sawtooth, false for the raw lo-fi sound
+    pub drum_mode: bool,    // Notes play synthesized drums instead of the waveform
+    pub sample_map: Option<SampleMap>, // Samples played instead of the waveform or drums when loaded
+    pub fm_patch: FmPatch,  // Patch of the FM waveform
+    // Table of the wavetable waveform
+    pub wavetable: Wavetable,
+    pub wavetable_position: f32,          // 0.0 (first frame) - 1.0 (last frame)
+    pub current_wavetable_position: f32,  // Position including modulation, as of the last rendered block
     pub octave: i32,
-    // Track-specific effects
-    pub delay_enabled: bool,
-    pub reverb_enabled: bool,
-    pub flanger_enabled: bool,
-    pub delay_effect: DelayEffect,
-    pub reverb_effect: ReverbEffect,
-    pub flanger_effect: FlangerEffect,
-    // Track-specific ADSR
+    pub voices: VoicePool,  // Sounding notes owned by this track
+    pub unison: Unison,     // Oscillators stacked per note
+    // Track-specific filter (after the oscillators)
+    pub filter_enabled: bool,
+    pub filter: StateVariableFilter,
+    // Track-specific effects, one in each slot in EffectSlot::ALL order
+    pub effects: EffectChain,
+    pub sidechain_source: Option<usize>,  // Track whose output keys the compressor instead of this track's own signal
+    // Track-specific DAHDSR (0-99 faders, times up to 2 seconds)
+    pub delay: u8,
     pub attack: u8,
+    pub hold: u8,
     pub decay: u8,
     pub sustain: u8,
     pub release: u8,
+    pub attack_curve: EnvelopeCurve,
+    pub decay_curve: EnvelopeCurve,
+    pub release_curve: EnvelopeCurve,
+    // Free breakpoint envelope, used instead of the DAHDSR in breakpoint mode
+    pub envelope_mode: EnvelopeMode,
+    pub breakpoint_envelope: EnvelopeShape,
+    // LFOs, modulation envelope and their routes
+    pub modulation: ModulationMatrix,
 }
 
 impl Track {
-    pub fn new(id: usize, name: String) -> Self {
+    pub fn new(id: usize, name: String, sample_rate: u32) -> Self {
         Self {
             id,
             name,
@@ -39,18 +80,169 @@ impl Track {
             volume: 0.8,
             pan: 0.0,
             playing: false,
-            waveform: WaveformType::Square,
+            waveform: Waveform::SQUARE,
+            band_limited: true,
+            drum_mode: false,
+            sample_map: None,
+            fm_patch: FmPatch::preset(FmPreset::ElectricPiano),
+            wavetable: Wavetable::from_shapes(),
+            wavetable_position: 0.0,
+            current_wavetable_position: 0.0,
             octave: 4,
-            delay_enabled: false,
-            reverb_enabled: false,
-            flanger_enabled: false,
-            delay_effect: DelayEffect::new(300.0, 0.55, 0.5, 44100),
-            reverb_effect: ReverbEffect::new(0.7, 0.4, 0.6, 44100),
-            flanger_effect: FlangerEffect::new(0.5, 0.7, 0.1, 0.5, 44100),
+            voices: VoicePool::new(DEFAULT_VOICE_COUNT, VoiceStealing::Oldest),
+            unison: Unison::default(),
+            filter_enabled: false,
+            filter: StateVariableFilter::new(FilterMode::LowPass, MAX_CUTOFF_HZ, 0.0, sample_rate),
+            effects: track_effect_chain(sample_rate),
+            sidechain_source: None,
+            delay: 0,
             attack: 0,
+            hold: 0,
             decay: 0,
             sustain: 50,
             release: 20,
+            attack_curve: EnvelopeCurve::Linear,
+            decay_curve: EnvelopeCurve::Exponential,
+            release_curve: EnvelopeCurve::Exponential,
+            envelope_mode: EnvelopeMode::Dahdsr,
+            breakpoint_envelope: EnvelopeShape::default_breakpoints(),
+            modulation: ModulationMatrix::new(id as u32),
+        }
+    }
+    
+    /// Copy of the track's settings, effects and recorded notes without any sounding voices
+    ///
+    /// Lets a bounce play the track through the mixer without disturbing the live track.
+    pub fn detached_copy(&self) -> Track {
+        Track {
+            id: self.id,
+            name: self.name.clone(),
+            recorded_notes: self.recorded_notes.clone(),
+            volume: self.volume,
+            pan: self.pan,
+            playing: self.playing,
+            waveform: self.waveform,
+            band_limited: self.band_limited,
+            drum_mode: self.drum_mode,
+            sample_map: self.sample_map.clone(),
+            fm_patch: self.fm_patch,
+            wavetable: self.wavetable.clone(),
+            wavetable_position: self.wavetable_position,
+            current_wavetable_position: self.current_wavetable_position,
+            octave: self.octave,
+            voices: VoicePool::with_settings_of(&self.voices),
+            unison: self.unison,
+            filter_enabled: self.filter_enabled,
+            filter: self.filter.clone(),
+            effects: self.effects.clone(),
+            sidechain_source: self.sidechain_source,
+            delay: self.delay,
+            attack: self.attack,
+            hold: self.hold,
+            decay: self.decay,
+            sustain: self.sustain,
+            release: self.release,
+            attack_curve: self.attack_curve,
+            decay_curve: self.decay_curve,
+            release_curve: self.release_curve,
+            envelope_mode: self.envelope_mode,
+            breakpoint_envelope: self.breakpoint_envelope.clone(),
+            modulation: self.modulation.clone(),
+        }
+    }
+    
+    /// Every destination the track's modulation routes can reach
+    pub fn modulation_destinations(&self) -> Vec<ModDestination> {
+        let mut destinations = vec![
+            ModDestination::Pitch,
+            ModDestination::FilterCutoff,
+            ModDestination::Volume,
+            ModDestination::Pan,
+            ModDestination::WavetablePosition,
+        ];
+        for chained in self.effects.iter() {
+            let slot = chained.slot;
+            let parameter_count = chained.effect.parameter_names().len();
+            destinations.extend((0..parameter_count)
+                .filter(|&parameter| chained.effect.is_modulatable(parameter))
+                .map(|parameter| ModDestination::Effect(slot, parameter)));
+        }
+        destinations
+    }
+    
+    /// Readable name of a modulation destination
+    pub fn destination_name(&self, destination: ModDestination) -> String {
+        match destination {
+            ModDestination::Pitch => "Pitch".to_string(),
+            ModDestination::FilterCutoff => "Filter Cutoff".to_string(),
+            ModDestination::Volume => "Volume".to_string(),
+            ModDestination::Pan => "Pan".to_string(),
+            ModDestination::WavetablePosition => "Wavetable Position".to_string(),
+            ModDestination::Effect(slot, parameter) => {
+                let name = self.effects.slot(slot)
+                    .and_then(|chained| chained.effect.parameter_names().get(parameter).copied())
+                    .unwrap_or("?");
+                format!("{} {}", slot, name)
+            }
+        }
+    }
+    
+    /// Deepest gain reduction of the track's enabled gate, compressor and limiter in dB, for metering
+    pub fn gain_reduction_db(&self) -> f32 {
+        self.effects.gain_reduction_db()
+    }
+    
+    /// Clear the filter and effect state and forget the modulation envelopes, as before a fresh render
+    pub fn reset_processing(&mut self) {
+        self.filter.reset();
+        self.effects.reset();
+        self.modulation.reset();
+    }
+    
+    /// DAHDSR settings in seconds from the track's faders
+    pub fn dahdsr(&self) -> Dahdsr {
+        let seconds = |value: u8| value as f32 / 99.0 * 2.0;
+        
+        Dahdsr {
+            delay: seconds(self.delay),
+            attack: seconds(self.attack),
+            hold: seconds(self.hold),
+            decay: seconds(self.decay),
+            sustain: self.sustain as f32 / 99.0,
+            release: seconds(self.release),
+            attack_curve: self.attack_curve,
+            decay_curve: self.decay_curve,
+            release_curve: self.release_curve,
+        }
+    }
+    
+    /// Envelope shaping the amplitude of the track's voices
+    ///
+    /// Drums shape themselves, so in drum mode this is a flat envelope that lets
+    /// synthesized drums or samples play out as one-shots.
+    pub fn amp_envelope(&self) -> EnvelopeShape {
+        if self.drum_mode {
+            return match &self.sample_map {
+                Some(map) => map.one_shot_envelope(),
+                None => DrumVoice::envelope(),
+            };
+        }
+        
+        match self.envelope_mode {
+            EnvelopeMode::Dahdsr => self.dahdsr().shape(),
+            EnvelopeMode::Breakpoint => self.breakpoint_envelope.clone(),
+        }
+    }
+    
+    /// Rebuild the track's filter and effects for a new sample rate
+    ///
+    /// Sounding voices are stopped since their oscillators run at the old rate.
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.voices.stop_all();
+        self.filter.set_sample_rate(sample_rate);
+        self.effects.set_sample_rate(sample_rate);
+        if let Some(map) = &mut self.sample_map {
+            map.set_sample_rate(sample_rate);
         }
     }
 }
@@ -58,26 +250,67 @@ impl Track {
 #[derive(Debug, Clone)]
 pub struct MasterTrack {
     pub volume: f32,        // Master volume 0.0 - 1.0
-    pub delay_enabled: bool,
-    pub reverb_enabled: bool,
-    pub flanger_enabled: bool,
-    pub delay_effect: DelayEffect,
-    pub reverb_effect: ReverbEffect,
-    pub flanger_effect: FlangerEffect,
+    pub effects: EffectChain,  // One in each slot in EffectSlot::ALL order
+    pub limiter: BrickwallLimiter,  // Always last on the master bus
 }
 
 impl MasterTrack {
-    pub fn new() -> Self {
+    pub fn new(sample_rate: u32) -> Self {
         Self {
             volume: 0.9,
-            delay_enabled: false,
-            reverb_enabled: false,
-            flanger_enabled: false,
-            delay_effect: DelayEffect::new(400.0, 0.4, 0.3, 44100),
-            reverb_effect: ReverbEffect::new(0.8, 0.3, 0.4, 44100),
-            flanger_effect: FlangerEffect::new(0.3, 0.5, 0.05, 0.3, 44100),
+            effects: master_effect_chain(sample_rate),
+            limiter: BrickwallLimiter::new(0.98, 50.0, sample_rate),
         }
     }
+    
+    /// Deepest gain reduction of the master's enabled dynamics processors and output limiter in dB, for metering
+    pub fn gain_reduction_db(&self) -> f32 {
+        self.limiter.gain_reduction_db().max(self.effects.gain_reduction_db())
+    }
+    
+    /// Rebuild the master effects for a new sample rate
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.effects.set_sample_rate(sample_rate);
+        self.limiter.set_sample_rate(sample_rate);
+    }
+}
+
+/// Effects of a new track, in EffectSlot::ALL order
+fn track_effect_chain(sample_rate: u32) -> EffectChain {
+    let mut effects = EffectChain::new();
+    effects.add_effect(EffectSlot::Gate, Box::new(GateEffect::new(-50.0, 40.0, 1.0, 50.0, 100.0, sample_rate)));
+    effects.add_effect(EffectSlot::Equalizer, Box::new(EqualizerEffect::new(sample_rate)));
+    effects.add_effect(EffectSlot::Compressor, Box::new(CompressorEffect::new(-18.0, 4.0, 10.0, 100.0, 6.0, 3.0, sample_rate)));
+    effects.add_effect(EffectSlot::Distortion, Box::new(DistortionEffect::new(DistortionType::SoftClip, 12.0, 1.0, sample_rate)));
+    effects.add_effect(EffectSlot::Bitcrusher, Box::new(BitcrusherEffect::new(6.0, 11025.0, 1.0, sample_rate)));  // Chiptune crunch
+    effects.add_effect(EffectSlot::Waveshaper, Box::new(WaveshaperEffect::new(ShaperCurve::Sine, 2.0, 1.0, sample_rate)));
+    effects.add_effect(EffectSlot::Delay, Box::new(DelayEffect::new(300.0, 0.55, 0.5, sample_rate)));
+    effects.add_effect(EffectSlot::Reverb, Box::new(ReverbEffect::new(0.7, 0.4, 0.6, sample_rate)));
+    effects.add_effect(EffectSlot::Flanger, Box::new(FlangerEffect::new(0.5, 0.7, 0.1, 0.5, sample_rate)));
+    effects.add_effect(EffectSlot::Chorus, Box::new(ChorusEffect::new(0.8, 0.5, 3, 0.5, sample_rate)));
+    effects.add_effect(EffectSlot::Phaser, Box::new(PhaserEffect::new(0.4, 0.8, 0.5, 0.5, sample_rate)));
+    effects.add_effect(EffectSlot::Tremolo, Box::new(TremoloEffect::new(5.0, 0.5, LfoShape::Sine, sample_rate)));
+    effects.add_effect(EffectSlot::Limiter, Box::new(LimiterEffect::new(-1.0, 5.0, 50.0, sample_rate)));
+    effects
+}
+
+/// Effects of the master bus, in EffectSlot::ALL order
+fn master_effect_chain(sample_rate: u32) -> EffectChain {
+    let mut effects = EffectChain::new();
+    effects.add_effect(EffectSlot::Gate, Box::new(GateEffect::new(-60.0, 30.0, 1.0, 50.0, 200.0, sample_rate)));
+    effects.add_effect(EffectSlot::Equalizer, Box::new(EqualizerEffect::new(sample_rate)));
+    effects.add_effect(EffectSlot::Compressor, Box::new(CompressorEffect::new(-12.0, 2.0, 20.0, 200.0, 6.0, 2.0, sample_rate)));  // Gentle glue for the summed tracks
+    effects.add_effect(EffectSlot::Distortion, Box::new(DistortionEffect::new(DistortionType::Tube, 6.0, 0.5, sample_rate)));
+    effects.add_effect(EffectSlot::Bitcrusher, Box::new(BitcrusherEffect::new(8.0, 22050.0, 0.5, sample_rate)));
+    effects.add_effect(EffectSlot::Waveshaper, Box::new(WaveshaperEffect::new(ShaperCurve::Sine, 1.0, 0.5, sample_rate)));
+    effects.add_effect(EffectSlot::Delay, Box::new(DelayEffect::new(400.0, 0.4, 0.3, sample_rate)));
+    effects.add_effect(EffectSlot::Reverb, Box::new(ReverbEffect::new(0.8, 0.3, 0.4, sample_rate)));
+    effects.add_effect(EffectSlot::Flanger, Box::new(FlangerEffect::new(0.3, 0.5, 0.05, 0.3, sample_rate)));
+    effects.add_effect(EffectSlot::Chorus, Box::new(ChorusEffect::new(0.5, 0.4, 2, 0.3, sample_rate)));
+    effects.add_effect(EffectSlot::Phaser, Box::new(PhaserEffect::new(0.2, 0.6, 0.3, 0.4, sample_rate)));
+    effects.add_effect(EffectSlot::Tremolo, Box::new(TremoloEffect::new(4.0, 0.3, LfoShape::Sine, sample_rate)));
+    effects.add_effect(EffectSlot::Limiter, Box::new(LimiterEffect::new(-0.3, 5.0, 100.0, sample_rate)));
+    effects
 }
 
 // Recording structures
@@ -89,6 +322,14 @@ pub struct RecordedNote {
     pub duration: f32,  // How long the note was held
 }
 
+/// A note played live, sustaining until its key or the mouse button is let go
+#[derive(Debug, Clone)]
+pub struct HeldNote {
+    pub note: Note,
+    pub gate: EnvelopeGate,
+    pub recording: Option<(Instant, i32)>, // (start_time, octave) when pressed while recording
+}
+
 #[derive(Debug, Clone)]
 pub struct VisualNote {
     pub note: Note,
@@ -133,25 +374,33 @@ pub mod event_loop;
 pub mod utils;
 pub mod updaters;
 
+const FILTER_CUTOFF_STEP: f32 = 1.0 / 7.0; // One of the 8 cutoff knob positions
+const EQ_FREQUENCY_STEP: f32 = 1.0 / 30.0; // A third of an octave on the 20 Hz - 20 kHz knob
+const EQ_Q_STEP: f32 = 1.0 / 20.0;
+const DEFAULT_ROUTE_AMOUNT: f32 = 0.05; // A new route starts as a light vibrato
+const MIN_TEMPO_BPM: f32 = 20.0;
+const MAX_TEMPO_BPM: f32 = 300.0;
 const FRAME_DURATION: Duration = Duration::from_millis(16); // Approximately 60Hz refresh rate
 
 // DAW State Struct - Multi-track Digital Audio Workstation
 pub struct State {
     // DAW Core
+    pub audio_config: AudioConfig,   // Sample rate, block size and channels used by the whole engine
     pub tracks: Vec<Track>,          // 4 individual tracks
     pub master_track: MasterTrack,   // Master mix bus
     pub current_track_id: usize,     // Currently selected track (0-3)
+    pub tempo_bpm: f32,              // Tempo followed by tempo-synced LFOs
     
     // Legacy single-track compatibility (will be removed later)
     pub(crate) octave: i32,
     pub(crate) waveform: Waveform,
     pub(crate) pressed_key: Option<(Key, Note)>,
     waveform_sprite_index: usize,
-    pub(crate) filter_factor: f32,
-    pub(crate) lpf_active: usize,
     pub(crate) current_frequency: Option<f32>, // Track current playing frequency
     pub(crate) animation_start_time: Instant, // When the animation started
     pub(crate) key_release_time: Option<Instant>, // When the key was released for fade-out
+    pub(crate) held_keys: Vec<(Key, HeldNote)>, // Notes held down on the computer keyboard
+    pub(crate) mouse_held_note: Option<HeldNote>, // Note held down with the mouse
     
     // Legacy ADSR (will use track-specific ADSR later)
     pub attack: u8,
@@ -161,15 +410,30 @@ pub struct State {
     
     // Recording state
     pub recording_state: RecordingState,
-    pub recorded_notes: Vec<RecordedNote>, // Legacy - will use track-specific
     pub visual_notes: Vec<VisualNote>,
     pub recording_start_time: Option<Instant>,
     pub playback_start_time: Option<Instant>,
-    pub current_note_start: Option<(Instant, Note, i32)>, // (start_time, note, octave)
     
     // Mouse state
     pub mouse: MouseState,
     
+    // Breakpoint envelope editing
+    pub selected_breakpoint: usize,  // Point of the current track's breakpoint envelope edited by the faders and keys
+    
+    // Modulation matrix editing
+    pub selected_route: usize,  // Route of the current track's modulation matrix edited by the keys
+    
+    // Sample editing
+    pub selected_sample_point: SamplePoint,  // Point of the current track's samples moved by the keys
+    
+    // FM patch editing
+    pub selected_fm_operator: usize,           // Operator of the current track's FM patch edited by the keys
+    pub selected_fm_parameter: FmParameter,
+    
+    // Equalizer editing
+    pub selected_eq_band: usize,  // Band of the edited equalizer moved by the keys
+    pub eq_on_master: bool,       // Edit the master bus equalizer instead of the current track's
+    
     // Stop button feedback
     pub stop_button_glow_time: Option<Instant>,
     
@@ -185,28 +449,37 @@ pub struct State {
 // Initialize DAW State
 impl State {
     pub(crate) fn new() -> Self {
+        let audio_config = AudioConfig::default();
+        let sample_rate = audio_config.sample_rate;
+        
         // Create 4 tracks with different default settings
-        let tracks = vec![
-            Track::new(0, "Lead".to_string()),
-            Track::new(1, "Bass".to_string()),
-            Track::new(2, "Drums".to_string()),
-            Track::new(3, "Pads".to_string()),
+        let mut tracks = vec![
+            Track::new(0, "Lead".to_string(), sample_rate),
+            Track::new(1, "Bass".to_string(), sample_rate),
+            Track::new(2, "Drums".to_string(), sample_rate),
+            Track::new(3, "Pads".to_string(), sample_rate),
         ];
+        tracks[2].drum_mode = true; // The Drums track plays the drum kit
+        tracks[1].voices.set_play_mode(PlayMode::Legato); // The Bass track slides between overlapping notes
+        tracks[3].sidechain_source = Some(2); // The Pads compressor ducks under the Drums once switched on
+        tracks[1].voices.set_glide(Glide { time: 0.08, mode: GlideMode::ConstantTime });
         
         State {
             // DAW Core initialization
+            audio_config,
             tracks,
-            master_track: MasterTrack::new(),
+            master_track: MasterTrack::new(sample_rate),
             current_track_id: 0, // Start with track 0 (Lead)
+            tempo_bpm: DEFAULT_TEMPO_BPM,
             octave: 4, // Set default octave to 4
-            waveform: WaveformType::Square, // Set default waveform to Square
+            waveform: Waveform::SQUARE, // Set default waveform to Square
             pressed_key: None, // Default is no key
             waveform_sprite_index: WAVEFORM_SQUARE, // Set default waveform sprite index to Square
-            filter_factor: 1.0, // Set default cutoff to 1.0
-            lpf_active: 0, // Default for LPF is deactivated
             current_frequency: None, // No frequency being played initially
             animation_start_time: Instant::now(), // Initialize animation time
             key_release_time: None, // No key released initially
+            held_keys: Vec::new(), // No keys held initially
+            mouse_held_note: None, // No note held with the mouse initially
             // ADSR defaults for pluck-like instant sound
             attack: 0,   // Instant attack (no delayed PAD effect)
             decay: 0,    // No decay (sounds does not fade to sustain level)
@@ -215,15 +488,28 @@ impl State {
             
             // Recording state defaults
             recording_state: RecordingState::Stopped,
-            recorded_notes: Vec::new(),
             visual_notes: Vec::new(),
             recording_start_time: None,
             playback_start_time: None,
-            current_note_start: None,
             
             // Mouse state defaults
             mouse: MouseState::new(),
             
+            // First breakpoint selected for editing
+            selected_breakpoint: 0,
+            
+            // No route selected until one is added
+            selected_route: 0,
+            selected_sample_point: SamplePoint::Start,
+            
+            // First operator's ratio selected for editing
+            selected_fm_operator: 0,
+            selected_fm_parameter: FmParameter::Ratio,
+            
+            // Lowest band that starts in the signal path selected for editing
+            selected_eq_band: 1,
+            eq_on_master: false,
+            
             // Stop button feedback defaults
             stop_button_glow_time: None,
             
@@ -231,17 +517,12 @@ impl State {
             delay_enabled: false,
             reverb_enabled: false,
             flanger_enabled: false,
-            delay_effect: DelayEffect::new(300.0, 0.55, 0.5, 44100), // 300ms delay, 55% feedback, 50% mix
-            reverb_effect: ReverbEffect::new(0.7, 0.4, 0.6, 44100), // Large room, light damping, 60% mix  
-            flanger_effect: FlangerEffect::new(0.5, 0.7, 0.1, 0.5, 44100), // 0.5Hz LFO, 70% depth, 10% feedback, 50% mix
+            delay_effect: DelayEffect::new(300.0, 0.55, 0.5, sample_rate), // 300ms delay, 55% feedback, 50% mix
+            reverb_effect: ReverbEffect::new(0.7, 0.4, 0.6, sample_rate), // Large room, light damping, 60% mix  
+            flanger_effect: FlangerEffect::new(0.5, 0.7, 0.1, 0.5, sample_rate), // 0.5Hz LFO, 70% depth, 10% feedback, 50% mix
         }
     }
 
-    /// Multiplies the sample frequency with that of the filter cutoff coefficient
-    pub fn apply_lpf(&mut self, sample: f32) -> f32 {
-        sample * self.filter_factor
-    }
-
     /// Increases the octave by one step, ensuring it does not exceed the upper bound.
     pub fn increase_octave(&mut self) {
         if self.octave < OCTAVE_UPPER_BOUND {
@@ -256,32 +537,12 @@ impl State {
         }
     }
 
-    /// Toggle LPF on/off
-    pub fn toggle_lpf(&mut self) {
-        self.lpf_active ^= 1;
-        self.filter_factor = 1.0;
-    }
-
-    /// Increases the filter cutoff
-    pub fn increase_filter_cutoff(&mut self) {
-        if self.lpf_active == 1 && self.filter_factor <= 0.9 {
-            self.filter_factor += 0.142857;
-        }
-    }
-
-    /// Decreases the filter cutoff
-    pub fn decrease_filter_cutoff(&mut self) {
-        if self.lpf_active == 1 && self.filter_factor >= 0.15 {
-            self.filter_factor -= 0.142857;
-        }
-    }
-
     /// Returns the current octave value.
     pub fn get_current_octave(&self) -> i32 {
         self.octave
     }
 
-    /// Toggles the waveform between SINE and SQUARE and sets the associated sprite index accordingly.
+    /// Cycles the waveform through the oscillators and noise colors and sets the associated sprite index accordingly.
     pub fn toggle_waveform(&mut self) {
         self.waveform = match self.waveform {
             Waveform::SINE => {
@@ -297,6 +558,20 @@ impl State {
                 Waveform::SAWTOOTH
             },
             Waveform::SAWTOOTH => {
+                self.waveform_sprite_index = WAVEFORM_NOISE;
+                Waveform::NOISE(NoiseColor::White)
+            },
+            Waveform::NOISE(NoiseColor::White) => Waveform::NOISE(NoiseColor::Pink),
+            Waveform::NOISE(NoiseColor::Pink) => Waveform::NOISE(NoiseColor::Brown),
+            Waveform::NOISE(NoiseColor::Brown) => {
+                self.waveform_sprite_index = WAVEFORM_FM;
+                Waveform::FM
+            },
+            Waveform::FM => {
+                self.waveform_sprite_index = WAVEFORM_WAVETABLE;
+                Waveform::WAVETABLE
+            },
+            Waveform::WAVETABLE => {
                 self.waveform_sprite_index = WAVEFORM_SINE;
                 Waveform::SINE
             }
@@ -357,24 +632,17 @@ impl State {
     pub fn start_recording(&mut self) {
         self.recording_state = RecordingState::Recording;
         self.recording_start_time = Some(Instant::now());
-        self.recorded_notes.clear();
-        self.current_note_start = None;
     }
 
     pub fn stop_recording(&mut self) {
-        // Finish any currently held note
-        if let Some((start_time, note, octave)) = self.current_note_start.take() {
-            let duration = start_time.elapsed().as_secs_f32();
-            let timestamp = self.recording_start_time
-                .map(|start| start.elapsed().as_secs_f32() - duration)
-                .unwrap_or(0.0);
-            
-            self.recorded_notes.push(RecordedNote {
-                note,
-                octave,
-                timestamp,
-                duration,
-            });
+        // Finish the notes still held, each from its own press
+        let still_recording: Vec<_> = self.held_keys.iter_mut()
+            .map(|(_, held)| held)
+            .chain(self.mouse_held_note.as_mut())
+            .filter_map(|held| held.recording.take().map(|(start_time, octave)| (held.note, start_time, octave)))
+            .collect();
+        for (note, start_time, octave) in still_recording {
+            self.finish_recorded_note(note, start_time, octave);
         }
         
         self.recording_state = RecordingState::Stopped;
@@ -382,7 +650,7 @@ impl State {
     }
 
     pub fn start_playback(&mut self) {
-        if !self.recorded_notes.is_empty() {
+        if self.tracks.iter().any(|track| !track.recorded_notes.is_empty()) {
             self.recording_state = RecordingState::Playing;
             self.playback_start_time = Some(Instant::now());
         }
@@ -428,45 +696,58 @@ impl State {
         });
     }
 
-    /// Calculate ADSR envelope amplitude at a given time since note start
+    /// Calculate the current track's envelope amplitude at a given time since note start
+    ///
+    /// Runs the track's DAHDSR or breakpoint envelope the same way its voices are shaped,
+    /// treating `time_since_start` as how long the key was held.
     pub fn calculate_adsr_amplitude(&self, time_since_start: f32, is_key_pressed: bool, time_since_release: Option<f32>) -> f32 {
-        if let Some(release_time) = time_since_release {
-            // Release phase
-            let release_duration = self.release_normalized() * 2.0; // Scale to 2 seconds max
-            if release_duration == 0.0 {
-                return 0.0;
-            }
-            let release_progress = (release_time / release_duration).min(1.0);
-            return self.sustain_normalized() * (1.0 - release_progress);
-        }
-
-        if !is_key_pressed {
+        if !is_key_pressed && time_since_release.is_none() {
             return 0.0;
         }
 
-        let attack_duration = self.attack_normalized() * 2.0; // Scale to 2 seconds max
-        let decay_duration = self.decay_normalized() * 2.0;
-
-        if time_since_start <= attack_duration {
-            // Attack phase
-            if attack_duration == 0.0 {
-                return 1.0;
-            }
-            return time_since_start / attack_duration;
-        } else if time_since_start <= attack_duration + decay_duration {
-            // Decay phase
-            if decay_duration == 0.0 {
-                return self.sustain_normalized();
+        self.current_track().amp_envelope().level_at(time_since_start, time_since_release)
+    }
+    
+    /// Envelope amplitude of the last note played, used to fade the waveform display
+    ///
+    /// The note counts as held from its press until every note is let go.
+    pub fn display_amplitude(&self) -> f32 {
+        let time_since_release = self.key_release_time.map(|release_time| release_time.elapsed().as_secs_f32());
+        let time_held = self.animation_start_time.elapsed().as_secs_f32() - time_since_release.unwrap_or(0.0);
+        let is_key_pressed = self.current_frequency.is_some() && time_since_release.is_none();
+        
+        self.calculate_adsr_amplitude(time_held, is_key_pressed, time_since_release)
+    }
+    
+    // === AUDIO CONFIGURATION ===
+    
+    /// Switch the engine to a new audio configuration, rebuilding every effect for its sample rate
+    pub fn set_audio_config(&mut self, audio_config: AudioConfig) {
+        let sample_rate_changed = audio_config.sample_rate != self.audio_config.sample_rate;
+        self.audio_config = audio_config;
+        
+        if sample_rate_changed {
+            let sample_rate = audio_config.sample_rate;
+            for track in &mut self.tracks {
+                track.set_sample_rate(sample_rate);
             }
-            let decay_time = time_since_start - attack_duration;
-            let decay_progress = decay_time / decay_duration;
-            return 1.0 - (1.0 - self.sustain_normalized()) * decay_progress;
-        } else {
-            // Sustain phase
-            return self.sustain_normalized();
+            self.master_track.set_sample_rate(sample_rate);
+            
+            // Legacy effects
+            self.delay_effect.set_sample_rate(sample_rate);
+            self.reverb_effect.set_sample_rate(sample_rate);
+            self.flanger_effect.set_sample_rate(sample_rate);
         }
     }
     
+    /// Switch the engine to the next of the supported sample rates
+    pub fn cycle_sample_rate(&mut self) {
+        let current = SAMPLE_RATES.iter().position(|&rate| rate == self.audio_config.sample_rate);
+        let next = SAMPLE_RATES[current.map_or(0, |index| (index + 1) % SAMPLE_RATES.len())];
+        let config = self.audio_config;
+        self.set_audio_config(AudioConfig::new(next, config.block_size, config.channels));
+    }
+    
     // === DAW TRACK MANAGEMENT METHODS ===
     
     /// Switch to a specific track (0-3)
@@ -498,6 +779,13 @@ impl State {
         }
     }
     
+    /// Silence every sounding voice on all tracks
+    pub fn stop_all_voices(&mut self) {
+        for track in &mut self.tracks {
+            track.voices.stop_all();
+        }
+    }
+    
     /// Adjust volume of current track
     pub fn adjust_current_track_volume(&mut self, delta: f32) {
         let track = &mut self.tracks[self.current_track_id];
@@ -530,7 +818,6 @@ impl State {
         self.recording_start_time = Some(Instant::now());
         // Clear current track's recorded notes
         self.tracks[self.current_track_id].recorded_notes.clear();
-        self.current_note_start = None;
     }
     
     /// Add recorded note to current track
@@ -538,6 +825,21 @@ impl State {
         self.tracks[self.current_track_id].recorded_notes.push(note);
     }
     
+    /// Record a note pressed at `start_time` and let go now on the current track
+    pub fn finish_recorded_note(&mut self, note: Note, start_time: Instant, octave: i32) {
+        let duration = start_time.elapsed().as_secs_f32();
+        let timestamp = self.recording_start_time
+            .map(|start| start.elapsed().as_secs_f32() - duration)
+            .unwrap_or(0.0);
+        
+        self.add_note_to_current_track(RecordedNote {
+            note,
+            octave,
+            timestamp,
+            duration,
+        });
+    }
+    
     // === TRACK-SPECIFIC ADSR CONTROLS ===
     
     /// Increase attack on current track
@@ -604,6 +906,367 @@ impl State {
         self.release = track.release;
     }
     
+    /// Bend the attack curve of the current track (positive is more exponential)
+    pub fn bend_current_track_attack_curve(&mut self, delta: f32) {
+        let track = &mut self.tracks[self.current_track_id];
+        track.attack_curve = track.attack_curve.bend(delta);
+    }
+    
+    /// Bend the decay curve of the current track (positive is more exponential)
+    pub fn bend_current_track_decay_curve(&mut self, delta: f32) {
+        let track = &mut self.tracks[self.current_track_id];
+        track.decay_curve = track.decay_curve.bend(delta);
+    }
+    
+    /// Bend the release curve of the current track (positive is more exponential)
+    pub fn bend_current_track_release_curve(&mut self, delta: f32) {
+        let track = &mut self.tracks[self.current_track_id];
+        track.release_curve = track.release_curve.bend(delta);
+    }
+    
+    // === TRACK-SPECIFIC BREAKPOINT ENVELOPE CONTROLS ===
+    
+    /// Switch the current track between its DAHDSR and breakpoint envelopes
+    pub fn toggle_current_track_envelope_mode(&mut self) {
+        let track = &mut self.tracks[self.current_track_id];
+        track.envelope_mode = match track.envelope_mode {
+            EnvelopeMode::Dahdsr => EnvelopeMode::Breakpoint,
+            EnvelopeMode::Breakpoint => EnvelopeMode::Dahdsr,
+        };
+    }
+    
+    /// Select a point of the current track's breakpoint envelope for editing
+    pub fn select_breakpoint(&mut self, index: usize) {
+        let point_count = self.tracks[self.current_track_id].breakpoint_envelope.points.len();
+        self.selected_breakpoint = index.min(point_count.min(MAX_BREAKPOINTS).saturating_sub(1));
+    }
+    
+    /// Select the next (or previous) breakpoint
+    pub fn step_selected_breakpoint(&mut self, forward: bool) {
+        let index = if forward {
+            self.selected_breakpoint + 1
+        } else {
+            self.selected_breakpoint.saturating_sub(1)
+        };
+        self.select_breakpoint(index);
+    }
+    
+    /// The selected point of the current track's breakpoint envelope
+    fn selected_breakpoint_mut(&mut self) -> Option<&mut Breakpoint> {
+        let index = self.selected_breakpoint;
+        self.tracks[self.current_track_id].breakpoint_envelope.points.get_mut(index)
+    }
+    
+    /// Set the level of the selected breakpoint (0.0 - 1.0)
+    pub fn set_selected_breakpoint_level(&mut self, level: f32) {
+        if let Some(point) = self.selected_breakpoint_mut() {
+            point.level = level.clamp(0.0, 1.0);
+        }
+    }
+    
+    /// Adjust the level of the selected breakpoint
+    pub fn adjust_selected_breakpoint_level(&mut self, delta: f32) {
+        if let Some(point) = self.selected_breakpoint_mut() {
+            point.level = (point.level + delta).clamp(0.0, 1.0);
+        }
+    }
+    
+    /// Adjust the time it takes to reach the selected breakpoint, in seconds
+    pub fn adjust_selected_breakpoint_time(&mut self, delta: f32) {
+        if let Some(point) = self.selected_breakpoint_mut() {
+            point.time = (point.time + delta).clamp(0.0, 2.0);
+        }
+    }
+    
+    /// Bend the curve leading to the selected breakpoint (positive is more exponential)
+    pub fn bend_selected_breakpoint_curve(&mut self, delta: f32) {
+        if let Some(point) = self.selected_breakpoint_mut() {
+            point.curve = point.curve.bend(delta);
+        }
+    }
+    
+    /// Make the selected breakpoint the sustain point, or clear it if it already is
+    pub fn toggle_selected_breakpoint_sustain(&mut self) {
+        let index = self.selected_breakpoint;
+        let envelope = &mut self.tracks[self.current_track_id].breakpoint_envelope;
+        envelope.sustain_point = if envelope.sustain_point == Some(index) { None } else { Some(index) };
+    }
+    
+    /// Make the selected breakpoint the start of the sustain loop, or clear it if it already is
+    pub fn toggle_selected_breakpoint_loop_start(&mut self) {
+        let index = self.selected_breakpoint;
+        let envelope = &mut self.tracks[self.current_track_id].breakpoint_envelope;
+        envelope.loop_start = if envelope.loop_start == Some(index) { None } else { Some(index) };
+    }
+    
+    // === TRACK-SPECIFIC MODULATION CONTROLS ===
+    
+    /// Add a route to the current track's matrix and select it, a gentle vibrato until edited
+    pub fn add_current_track_route(&mut self) {
+        let route = ModRoute::new(ModSource::Lfo(0), ModDestination::Pitch, DEFAULT_ROUTE_AMOUNT);
+        if let Some(index) = self.tracks[self.current_track_id].modulation.add_route(route) {
+            self.selected_route = index;
+        }
+    }
+    
+    /// Remove the selected route from the current track's matrix
+    pub fn remove_selected_route(&mut self) {
+        let modulation = &mut self.tracks[self.current_track_id].modulation;
+        modulation.remove_route(self.selected_route);
+        self.selected_route = self.selected_route.min(modulation.routes.len().saturating_sub(1));
+    }
+    
+    /// Select the next route of the current track, wrapping around
+    pub fn select_next_route(&mut self) {
+        let route_count = self.tracks[self.current_track_id].modulation.routes.len();
+        self.selected_route = if route_count == 0 { 0 } else { (self.selected_route + 1) % route_count };
+    }
+    
+    fn selected_route_mut(&mut self) -> Option<&mut ModRoute> {
+        let index = self.selected_route;
+        self.tracks[self.current_track_id].modulation.routes.get_mut(index)
+    }
+    
+    /// Switch the selected route to the next source
+    pub fn cycle_selected_route_source(&mut self) {
+        if let Some(route) = self.selected_route_mut() {
+            route.source = route.source.next();
+        }
+    }
+    
+    /// Switch the selected route to the next (or previous) destination
+    pub fn cycle_selected_route_destination(&mut self, forward: bool) {
+        let destinations = self.tracks[self.current_track_id].modulation_destinations();
+        if let Some(route) = self.selected_route_mut() {
+            let index = destinations.iter().position(|&destination| destination == route.destination).unwrap_or(0);
+            let next = if forward {
+                (index + 1) % destinations.len()
+            } else {
+                (index + destinations.len() - 1) % destinations.len()
+            };
+            route.destination = destinations[next];
+        }
+    }
+    
+    /// Adjust the depth of the selected route (-1.0 to 1.0)
+    pub fn adjust_selected_route_amount(&mut self, delta: f32) {
+        if let Some(route) = self.selected_route_mut() {
+            route.amount = (route.amount + delta).clamp(-1.0, 1.0);
+        }
+    }
+    
+    /// The LFO feeding the selected route, if its source is an LFO
+    fn selected_route_lfo_mut(&mut self) -> Option<&mut Lfo> {
+        let index = self.selected_route;
+        let modulation = &mut self.tracks[self.current_track_id].modulation;
+        match modulation.routes.get(index)?.source {
+            ModSource::Lfo(lfo) => modulation.lfos.get_mut(lfo),
+            _ => None,
+        }
+    }
+    
+    /// Change the shape of the LFO feeding the selected route
+    pub fn cycle_selected_route_lfo_shape(&mut self) {
+        if let Some(lfo) = self.selected_route_lfo_mut() {
+            lfo.shape = lfo.shape.next();
+        }
+    }
+    
+    /// Speed up (or slow down) the LFO feeding the selected route
+    pub fn step_selected_route_lfo_rate(&mut self, faster: bool) {
+        if let Some(lfo) = self.selected_route_lfo_mut() {
+            lfo.step_rate(faster);
+        }
+    }
+    
+    /// Switch the LFO feeding the selected route between free-running and tempo-synced
+    pub fn toggle_selected_route_lfo_sync(&mut self) {
+        let tempo_bpm = self.tempo_bpm;
+        if let Some(lfo) = self.selected_route_lfo_mut() {
+            lfo.toggle_sync(tempo_bpm);
+        }
+    }
+    
+    /// Set the tempo that synced LFOs follow on every track
+    pub fn set_tempo(&mut self, tempo_bpm: f32) {
+        self.tempo_bpm = tempo_bpm.clamp(MIN_TEMPO_BPM, MAX_TEMPO_BPM);
+        for track in &mut self.tracks {
+            track.modulation.tempo_bpm = self.tempo_bpm;
+        }
+    }
+    
+    /// One-line summary of the selected route for status messages
+    pub fn describe_selected_route(&self) -> String {
+        let track = self.current_track();
+        let modulation = &track.modulation;
+        let Some(route) = modulation.routes.get(self.selected_route) else {
+            return format!("{}: no modulation routes", track.name);
+        };
+        
+        let source = match route.source {
+            ModSource::Lfo(index) => match modulation.lfos.get(index) {
+                Some(lfo) => format!("{} ({}, {})", route.source, lfo.shape, lfo.rate),
+                None => route.source.to_string(),
+            },
+            _ => route.source.to_string(),
+        };
+        
+        format!("{} route {}/{}: {} -> {}, amount {:+.2}",
+                track.name,
+                self.selected_route + 1,
+                modulation.routes.len(),
+                source,
+                track.destination_name(route.destination),
+                route.amount)
+    }
+    
+    // === TRACK-SPECIFIC FM CONTROLS ===
+    
+    /// FM patch of the current track, if it plays one
+    pub fn current_track_fm_patch_mut(&mut self) -> Option<&mut FmPatch> {
+        let track = &mut self.tracks[self.current_track_id];
+        match track.waveform {
+            Waveform::FM => Some(&mut track.fm_patch),
+            _ => None,
+        }
+    }
+    
+    /// Select the next (or previous) operator of the current track's FM patch
+    pub fn select_fm_operator(&mut self, forward: bool) {
+        let Some(count) = self.current_track_fm_patch_mut().map(|patch| patch.operator_count) else {
+            return;
+        };
+        let operator = self.selected_fm_operator.min(count - 1);
+        self.selected_fm_operator = if forward { (operator + 1) % count } else { (operator + count - 1) % count };
+    }
+    
+    /// Select the next (or previous) parameter of the selected operator
+    pub fn select_fm_parameter(&mut self, forward: bool) {
+        self.selected_fm_parameter = self.selected_fm_parameter.step(forward);
+    }
+    
+    /// Move the selected parameter of the current track's FM patch one step up or down
+    pub fn step_selected_fm_parameter(&mut self, up: bool) {
+        let (operator, parameter) = (self.selected_fm_operator, self.selected_fm_parameter);
+        if let Some(patch) = self.current_track_fm_patch_mut() {
+            patch.step_parameter(operator.min(patch.operator_count - 1), parameter, up);
+        }
+    }
+    
+    pub fn cycle_current_track_fm_algorithm(&mut self) {
+        if let Some(patch) = self.current_track_fm_patch_mut() {
+            patch.algorithm = patch.algorithm.next();
+        }
+    }
+    
+    /// Change the number of operators of the current track's FM patch, keeping the selection on an active one
+    pub fn cycle_current_track_fm_operator_count(&mut self) {
+        if let Some(patch) = self.current_track_fm_patch_mut() {
+            patch.cycle_operator_count();
+            let count = patch.operator_count;
+            self.selected_fm_operator = self.selected_fm_operator.min(count - 1);
+        }
+    }
+    
+    /// One line summary of the current track's FM patch and the selected parameter, for the console
+    pub fn describe_fm_selection(&self) -> String {
+        let track = self.current_track();
+        if track.waveform != Waveform::FM {
+            return format!("{}: not an FM waveform (Tab to the FM presets)", track.name);
+        }
+        let patch = &track.fm_patch;
+        
+        let parameter = match self.selected_fm_parameter {
+            FmParameter::Feedback => format!("Feedback {:.2}", patch.feedback),
+            parameter => format!("Operator {} {} {:.2}",
+                                 self.selected_fm_operator + 1,
+                                 parameter,
+                                 patch.parameter(self.selected_fm_operator, parameter)),
+        };
+        
+        format!("{}: FM {}, {} operators, {} algorithm, {}",
+                track.name,
+                patch.preset,
+                patch.operator_count,
+                patch.algorithm,
+                parameter)
+    }
+    
+    // === TRACK-SPECIFIC WAVETABLE CONTROLS ===
+    
+    /// Switch the current track to the next table of the wavetables directory, after the last back to the basic shapes
+    pub fn cycle_current_track_wavetable(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let track = &mut self.tracks[self.current_track_id];
+        track.wavetable = track.wavetable.next_in(Path::new(WAVETABLES_DIRECTORY))?;
+        track.voices.stop_all();
+        Ok(())
+    }
+    
+    /// Move the current track's wavetable position, staying within the table
+    pub fn adjust_current_track_wavetable_position(&mut self, delta: f32) {
+        let track = &mut self.tracks[self.current_track_id];
+        track.wavetable_position = (track.wavetable_position + delta).clamp(0.0, 1.0);
+    }
+    
+    // === TRACK-SPECIFIC UNISON CONTROLS ===
+    
+    /// Stack one oscillator more or less per note on the current track
+    pub fn adjust_current_track_unison_voices(&mut self, delta: i32) {
+        let unison = &mut self.tracks[self.current_track_id].unison;
+        unison.voices = (unison.voices as i32 + delta).clamp(1, MAX_UNISON_VOICES as i32) as usize;
+    }
+    
+    /// Widen or narrow the current track's unison detune, in cents
+    pub fn adjust_current_track_unison_detune(&mut self, delta: f32) {
+        let unison = &mut self.tracks[self.current_track_id].unison;
+        unison.detune = (unison.detune + delta).clamp(0.0, MAX_DETUNE_CENTS);
+    }
+    
+    pub fn adjust_current_track_unison_spread(&mut self, delta: f32) {
+        let unison = &mut self.tracks[self.current_track_id].unison;
+        unison.stereo_spread = (unison.stereo_spread + delta).clamp(0.0, 1.0);
+    }
+    
+    pub fn toggle_current_track_unison_random_phase(&mut self) {
+        let unison = &mut self.tracks[self.current_track_id].unison;
+        unison.random_phase = !unison.random_phase;
+    }
+    
+    // === TRACK-SPECIFIC PLAY MODE CONTROLS ===
+    
+    /// Switch the current track between poly, mono and legato
+    pub fn cycle_current_track_play_mode(&mut self) {
+        let voices = &mut self.tracks[self.current_track_id].voices;
+        voices.set_play_mode(voices.play_mode().next());
+    }
+    
+    /// Switch the current track's glide between constant time and constant rate
+    pub fn cycle_current_track_glide_mode(&mut self) {
+        let voices = &mut self.tracks[self.current_track_id].voices;
+        let glide = voices.glide();
+        voices.set_glide(Glide { mode: glide.mode.next(), ..glide });
+    }
+    
+    /// Lengthen or shorten the current track's glide, in seconds
+    pub fn adjust_current_track_glide_time(&mut self, delta: f32) {
+        let voices = &mut self.tracks[self.current_track_id].voices;
+        let glide = voices.glide();
+        voices.set_glide(Glide { time: glide.time + delta, ..glide });
+    }
+    
+    /// Add or remove voices of the current track, silencing the oldest when there are too many
+    pub fn adjust_current_track_voice_count(&mut self, delta: i32) {
+        let voices = &mut self.tracks[self.current_track_id].voices;
+        let count = (voices.max_voices() as i32 + delta).clamp(1, MAX_VOICE_COUNT as i32);
+        voices.set_max_voices(count as usize);
+    }
+    
+    /// Switch which voice the current track takes over when all of its voices are busy
+    pub fn cycle_current_track_voice_stealing(&mut self) {
+        let voices = &mut self.tracks[self.current_track_id].voices;
+        voices.set_stealing(voices.stealing().next());
+    }
+    
     // === TRACK-SPECIFIC OCTAVE CONTROLS ===
     
     /// Increase octave on current track
@@ -628,28 +1291,80 @@ impl State {
     
     // === TRACK-SPECIFIC EFFECTS CONTROLS ===
     
-    /// Toggle delay on current track
-    pub fn toggle_current_track_delay(&mut self) {
-        let track = &mut self.tracks[self.current_track_id];
-        track.delay_enabled = !track.delay_enabled;
+    /// Switch an effect of the current track on or off, returning whether it is now on
+    pub fn toggle_current_track_effect(&mut self, slot: EffectSlot) -> bool {
+        let enabled = self.tracks[self.current_track_id].effects.toggle(slot);
         // Sync with legacy state
-        self.delay_enabled = track.delay_enabled;
+        match slot {
+            EffectSlot::Delay => self.delay_enabled = enabled,
+            EffectSlot::Reverb => self.reverb_enabled = enabled,
+            EffectSlot::Flanger => self.flanger_enabled = enabled,
+            _ => {}
+        }
+        enabled
     }
     
-    /// Toggle reverb on current track
-    pub fn toggle_current_track_reverb(&mut self) {
-        let track = &mut self.tracks[self.current_track_id];
-        track.reverb_enabled = !track.reverb_enabled;
-        // Sync with legacy state
-        self.reverb_enabled = track.reverb_enabled;
+    /// Switch the current track's delay between true stereo and ping-pong echoes
+    pub fn toggle_current_track_delay_ping_pong(&mut self) {
+        let delay = self.tracks[self.current_track_id].effects.get_mut::<DelayEffect>();
+        delay.set_ping_pong(!delay.ping_pong());
+        delay.reset();
     }
     
-    /// Toggle flanger on current track
-    pub fn toggle_current_track_flanger(&mut self) {
+    /// Give the current track's chorus one more voice, wrapping back to one
+    pub fn cycle_current_track_chorus_voices(&mut self) {
+        let chorus = self.tracks[self.current_track_id].effects.get_mut::<ChorusEffect>();
+        chorus.set_voices(chorus.voices() % MAX_CHORUS_VOICES + 1);
+    }
+    
+    /// Give the current track's phaser two more stages, wrapping back to two
+    pub fn cycle_current_track_phaser_stages(&mut self) {
+        let phaser = self.tracks[self.current_track_id].effects.get_mut::<PhaserEffect>();
+        let stages = if phaser.stages() >= MAX_PHASER_STAGES { 2 } else { phaser.stages() + 2 };
+        phaser.set_stages(stages);
+        phaser.reset();
+    }
+    
+    /// Switch the current track's tremolo to the next LFO shape
+    pub fn cycle_current_track_tremolo_shape(&mut self) {
+        let tremolo = self.tracks[self.current_track_id].effects.get_mut::<TremoloEffect>();
+        tremolo.set_shape(tremolo.shape().next());
+    }
+    
+    /// Switch the current track's distortion to the next curve
+    pub fn cycle_current_track_distortion_type(&mut self) {
+        let distortion = self.tracks[self.current_track_id].effects.get_mut::<DistortionEffect>();
+        distortion.set_distortion_type(distortion.distortion_type().next());
+    }
+    
+    /// Switch the current track's waveshaper to the next built-in curve, then to the tables in
+    /// the waveshaper tables directory
+    pub fn cycle_current_track_waveshaper_curve(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let waveshaper = self.tracks[self.current_track_id].effects.get_mut::<WaveshaperEffect>();
+        waveshaper.next_table_in(Path::new(WAVESHAPER_TABLES_DIRECTORY))
+    }
+    
+    /// Step the oversampling of the current track's distortion and waveshaper together: off, 2x, 4x
+    pub fn cycle_current_track_oversampling(&mut self) {
         let track = &mut self.tracks[self.current_track_id];
-        track.flanger_enabled = !track.flanger_enabled;
-        // Sync with legacy state
-        self.flanger_enabled = track.flanger_enabled;
+        let oversampling = track.effects.get::<DistortionEffect>().oversampling().next();
+        track.effects.get_mut::<DistortionEffect>().set_oversampling(oversampling);
+        track.effects.get_mut::<WaveshaperEffect>().set_oversampling(oversampling);
+    }
+    
+    /// Key the current track's compressor by the next other track, wrapping around to its own signal
+    pub fn cycle_current_track_sidechain_source(&mut self) {
+        let current_track_id = self.current_track_id;
+        let track_count = self.tracks.len();
+        let track = &mut self.tracks[current_track_id];
+        
+        let next = match track.sidechain_source {
+            None => 0,
+            Some(source) => source + 1,
+        };
+        // Skip the track itself; past the last track the compressor goes back to following its own signal
+        let next = if next == current_track_id { next + 1 } else { next };
+        track.sidechain_source = if next < track_count { Some(next) } else { None };
     }
     
     /// Toggle waveform on current track
@@ -669,6 +1384,32 @@ impl State {
                 Waveform::SAWTOOTH
             },
             Waveform::SAWTOOTH => {
+                self.waveform_sprite_index = WAVEFORM_NOISE;
+                Waveform::NOISE(NoiseColor::White)
+            },
+            Waveform::NOISE(NoiseColor::White) => Waveform::NOISE(NoiseColor::Pink),
+            Waveform::NOISE(NoiseColor::Pink) => Waveform::NOISE(NoiseColor::Brown),
+            Waveform::NOISE(NoiseColor::Brown) => {
+                self.waveform_sprite_index = WAVEFORM_FM;
+                track.fm_patch = FmPatch::preset(FmPreset::ElectricPiano);
+                Waveform::FM
+            },
+            // Each preset replaces the track's patch, after the last one the wavetable follows
+            Waveform::FM => match track.fm_patch.preset {
+                FmPreset::ElectricPiano => {
+                    track.fm_patch = FmPatch::preset(FmPreset::Bell);
+                    Waveform::FM
+                },
+                FmPreset::Bell => {
+                    track.fm_patch = FmPatch::preset(FmPreset::Bass);
+                    Waveform::FM
+                },
+                FmPreset::Bass => {
+                    self.waveform_sprite_index = WAVEFORM_WAVETABLE;
+                    Waveform::WAVETABLE
+                },
+            },
+            Waveform::WAVETABLE => {
                 self.waveform_sprite_index = WAVEFORM_SINE;
                 Waveform::SINE
             }
@@ -676,4 +1417,217 @@ impl State {
         // Sync with legacy state
         self.waveform = track.waveform.clone();
     }
+    
+    // === MASTER EFFECTS CONTROLS ===
+    
+    /// Switch an effect of the master bus on or off, returning whether it is now on
+    pub fn toggle_master_effect(&mut self, slot: EffectSlot) -> bool {
+        self.master_track.effects.toggle(slot)
+    }
+    
+    // === TRACK-SPECIFIC FILTER CONTROLS ===
+    
+    /// Toggle the filter on current track, opening the cutoff fully
+    pub fn toggle_current_track_filter(&mut self) {
+        let track = &mut self.tracks[self.current_track_id];
+        track.filter_enabled = !track.filter_enabled;
+        track.filter.set_cutoff(MAX_CUTOFF_HZ);
+        track.filter.reset();
+    }
+    
+    /// Increases the filter cutoff on current track by one knob step
+    pub fn increase_current_track_filter_cutoff(&mut self) {
+        let track = &mut self.tracks[self.current_track_id];
+        if track.filter_enabled {
+            let value = track.filter.cutoff_normalized() + FILTER_CUTOFF_STEP;
+            track.filter.set_cutoff_normalized(value);
+        }
+    }
+    
+    /// Decreases the filter cutoff on current track by one knob step
+    pub fn decrease_current_track_filter_cutoff(&mut self) {
+        let track = &mut self.tracks[self.current_track_id];
+        if track.filter_enabled {
+            let value = track.filter.cutoff_normalized() - FILTER_CUTOFF_STEP;
+            track.filter.set_cutoff_normalized(value);
+        }
+    }
+    
+    /// Adjust the filter resonance on current track
+    pub fn adjust_current_track_filter_resonance(&mut self, delta: f32) {
+        let track = &mut self.tracks[self.current_track_id];
+        let resonance = track.filter.resonance() + delta;
+        track.filter.set_resonance(resonance);
+    }
+    
+    /// Cycle the filter mode (LP, HP, BP, Notch) on current track
+    pub fn cycle_current_track_filter_mode(&mut self) {
+        let track = &mut self.tracks[self.current_track_id];
+        let mode = track.filter.mode().next();
+        track.filter.set_mode(mode);
+    }
+    
+    /// Switch the current track between playing its waveform and playing drums, silencing its voices
+    pub fn toggle_current_track_drum_mode(&mut self) {
+        let track = &mut self.tracks[self.current_track_id];
+        track.drum_mode = !track.drum_mode;
+        track.voices.stop_all();
+    }
+    
+    /// Load the current track's samples from its directory, or unload them if loaded
+    ///
+    /// Returns whether the track plays samples afterwards. Voices are silenced either way.
+    pub fn toggle_current_track_samples(&mut self) -> Result<bool, Box<dyn std::error::Error>> {
+        let sample_rate = self.audio_config.sample_rate;
+        let track = &mut self.tracks[self.current_track_id];
+        track.voices.stop_all();
+        
+        if track.sample_map.take().is_some() {
+            return Ok(false);
+        }
+        
+        let directory = SampleMap::directory_for_track(&track.name);
+        track.sample_map = Some(SampleMap::load_directory(Path::new(&directory), sample_rate)?);
+        Ok(true)
+    }
+    
+    /// Switch the current track's samples between one-shot and looped playback
+    ///
+    /// Returns the new setting, or None when the track has no samples.
+    pub fn toggle_current_track_sample_looping(&mut self) -> Option<bool> {
+        let map = self.tracks[self.current_track_id].sample_map.as_mut()?;
+        map.looping = !map.looping;
+        Some(map.looping)
+    }
+    
+    /// Select the next (or previous) point of the samples moved by the keys
+    pub fn select_sample_point(&mut self, forward: bool) {
+        self.selected_sample_point = if forward {
+            self.selected_sample_point.next()
+        } else {
+            self.selected_sample_point.previous()
+        };
+    }
+    
+    /// Move the selected point of each of the current track's samples by a share of its length
+    ///
+    /// A negative share moves the point towards the beginning. Every press moves it at least one frame.
+    pub fn move_current_track_sample_point(&mut self, share: f32) {
+        let point = self.selected_sample_point;
+        if let Some(map) = &mut self.tracks[self.current_track_id].sample_map {
+            for zone in &mut map.zones {
+                let frames = ((zone.frames() as f32 * share.abs()).round() as isize).max(1);
+                zone.move_point(point, if share < 0.0 { -frames } else { frames });
+            }
+        }
+    }
+    
+    /// Describe where the selected point is in each of the current track's samples
+    pub fn describe_sample_point(&self) -> String {
+        let track = self.current_track();
+        let Some(map) = &track.sample_map else {
+            return format!("{}: no samples loaded", track.name);
+        };
+        
+        let mut description = format!("{}: sample {}", track.name, self.selected_sample_point);
+        for zone in &map.zones {
+            description.push_str(&format!("\n  {} at frame {} of {}", zone.name, zone.point(self.selected_sample_point), zone.frames()));
+        }
+        description
+    }
+    
+    /// Toggle between band-limited and raw (lo-fi) oscillators on current track
+    pub fn toggle_current_track_band_limiting(&mut self) {
+        let track = &mut self.tracks[self.current_track_id];
+        track.band_limited = !track.band_limited;
+    }
+    
+    // === EQUALIZER CONTROLS ===
+    
+    /// Switch the equalizer keys between the current track's equalizer and the master bus one
+    pub fn toggle_eq_target(&mut self) {
+        self.eq_on_master = !self.eq_on_master;
+    }
+    
+    /// Equalizer edited by the keys and whether it is switched on
+    pub fn edited_equalizer(&self) -> (&EqualizerEffect, bool) {
+        if self.eq_on_master {
+            let effects = &self.master_track.effects;
+            (effects.get::<EqualizerEffect>(), effects.is_enabled(EffectSlot::Equalizer))
+        } else {
+            let effects = &self.current_track().effects;
+            (effects.get::<EqualizerEffect>(), effects.is_enabled(EffectSlot::Equalizer))
+        }
+    }
+    
+    fn edited_equalizer_mut(&mut self) -> &mut EqualizerEffect {
+        if self.eq_on_master {
+            self.master_track.effects.get_mut::<EqualizerEffect>()
+        } else {
+            self.tracks[self.current_track_id].effects.get_mut::<EqualizerEffect>()
+        }
+    }
+    
+    /// Select the next (or previous) band of the edited equalizer
+    pub fn select_eq_band(&mut self, forward: bool) {
+        let count = self.edited_equalizer().0.bands().len();
+        let band = self.selected_eq_band.min(count - 1);
+        self.selected_eq_band = if forward { (band + 1) % count } else { (band + count - 1) % count };
+    }
+    
+    /// Move one normalized parameter of the selected band, 0 frequency, 1 gain or 2 Q
+    fn step_selected_eq_band_parameter(&mut self, parameter: usize, delta: f32) {
+        let index = self.selected_eq_band * 3 + parameter;
+        let equalizer = self.edited_equalizer_mut();
+        if let Some(value) = equalizer.parameter(index) {
+            equalizer.set_parameter(index, value + delta);
+        }
+    }
+    
+    /// Move the selected band's frequency up or down a third of an octave
+    pub fn step_selected_eq_band_frequency(&mut self, up: bool) {
+        self.step_selected_eq_band_parameter(0, if up { EQ_FREQUENCY_STEP } else { -EQ_FREQUENCY_STEP });
+    }
+    
+    /// Boost or cut the selected band by a number of dB
+    pub fn adjust_selected_eq_band_gain(&mut self, delta_db: f32) {
+        let band = self.selected_eq_band;
+        let equalizer = self.edited_equalizer_mut();
+        if let Some(gain_db) = equalizer.bands().get(band).map(|band| band.gain_db) {
+            equalizer.set_band_gain_db(band, gain_db + delta_db);
+        }
+    }
+    
+    /// Narrow (or widen) the selected band one step
+    pub fn step_selected_eq_band_q(&mut self, up: bool) {
+        self.step_selected_eq_band_parameter(2, if up { EQ_Q_STEP } else { -EQ_Q_STEP });
+    }
+    
+    /// Switch the selected band in or out of the edited equalizer
+    pub fn toggle_selected_eq_band(&mut self) {
+        let band = self.selected_eq_band;
+        let equalizer = self.edited_equalizer_mut();
+        if let Some(enabled) = equalizer.bands().get(band).map(|band| band.enabled) {
+            equalizer.set_band_enabled(band, !enabled);
+        }
+    }
+    
+    /// One line summary of the edited equalizer's selected band, for the console
+    pub fn describe_eq_selection(&self) -> String {
+        let name = if self.eq_on_master { "Master" } else { &self.current_track().name };
+        let (equalizer, enabled) = self.edited_equalizer();
+        let Some(band) = equalizer.bands().get(self.selected_eq_band) else {
+            return format!("{}: no equalizer band selected", name);
+        };
+        
+        let gain = if band.band_type.has_gain() { format!(" {:+.1} dB", band.gain_db) } else { String::new() };
+        format!("{}: EQ {} {:.0} Hz{} Q {:.2}{}{}",
+                name,
+                equalizer.band_name(self.selected_eq_band),
+                band.frequency,
+                gain,
+                band.q,
+                if band.enabled { "" } else { " (bypassed)" },
+                if enabled { "" } else { ", equalizer off" })
+    }
 }
\ No newline at end of file
@@ -1,16 +1,29 @@
+use crate::audio::{AudioConfig, MultiTrackMixer};
 use crate::state::State;
+use crate::waveforms::STEREO;
+use rodio::buffer::SamplesBuffer;
 use rodio::Sink;
 
+/// Number of blocks kept queued in the sink (about 43ms at 48kHz)
+const STREAM_QUEUED_BLOCKS: usize = 4;
+
 /// Handles audio-related state updates
-pub struct AudioStateUpdater;
+pub struct AudioStateUpdater {
+    mixer: MultiTrackMixer,  // Kept between frames so its block buffers are reused
+}
 
 impl AudioStateUpdater {
     pub fn new() -> Self {
-        Self
+        Self {
+            mixer: MultiTrackMixer::new(AudioConfig::default().sample_rate),
+        }
     }
     
     /// Update audio-related state logic
-    pub fn update(&self, state: &mut State, sink: &mut Sink) {
+    pub fn update(&mut self, state: &mut State, sink: &mut Sink) {
+        // Render the tracks' voices and keep the sink fed
+        self.stream_audio(state, sink);
+        
         // Handle key release timing and audio fade effects
         self.handle_key_release_timing(state);
         
@@ -18,14 +31,27 @@ impl AudioStateUpdater {
         self.update_frequency_display(state);
     }
     
+    /// Render blocks of the full mix until the sink has enough audio queued
+    ///
+    /// Blocks follow the state's audio configuration, so sample rate, block size and
+    /// channel count changes take effect on the next block.
+    fn stream_audio(&mut self, state: &mut State, sink: &mut Sink) {
+        let config = state.audio_config;
+        self.mixer.set_sample_rate(config.sample_rate);
+        
+        while sink.len() < STREAM_QUEUED_BLOCKS {
+            let mut block = vec![0.0; config.block_size * STEREO as usize];
+            self.mixer.render(&mut state.tracks, &mut state.master_track, &mut block);
+            sink.append(SamplesBuffer::new(config.channels, config.sample_rate, config.output_from_stereo(block)));
+        }
+    }
+    
     /// Handle key release timing and fade-out effects
     fn handle_key_release_timing(&self, state: &mut State) {
-        // Clear frequency after fade-out is complete
-        if let Some(release_time) = state.key_release_time {
-            if release_time.elapsed().as_secs_f32() > 2.0 {
-                state.current_frequency = None;
-                state.key_release_time = None;
-            }
+        // Clear frequency once the envelope's release has faded out
+        if state.key_release_time.is_some() && state.display_amplitude() <= 0.0 {
+            state.current_frequency = None;
+            state.key_release_time = None;
         }
     }
     
@@ -1,185 +1,71 @@
 use std::collections::HashMap;
 
 use minifb::Key;
-use rodio::{Sink, Source};
 use crate::audio::MultiTrackMixer;
-use crate::effects::{EffectWrapper, AudioEffect, DelayEffect, ReverbEffect, FlangerEffect};
-use std::time::Duration;
 
-use crate::graphics::draw::{draw_adsr_faders, draw_control_buttons, draw_display_sprite_single, draw_idle_key_sprites, draw_idle_tangent_sprites, draw_note_sprite, draw_octave_fader_sprite, draw_pressed_key_sprite, draw_rack_sprite, draw_tangent_sprites};
+use crate::graphics::draw::{draw_adsr_faders, draw_bulb_sprite, draw_control_buttons, draw_display_sprite_single, draw_eq_response_curve, draw_filter_cutoff_knob_sprite, draw_filter_resonance_knob_sprite, draw_idle_key_sprites, draw_idle_tangent_sprites, draw_note_sprite, draw_octave_fader_sprite, draw_pressed_key_sprite, draw_rack_sprite, draw_tangent_sprites};
 use crate::graphics::sprites::Sprites;
+use crate::modulation::EffectSlot;
 use crate::music_theory::note::Note;
-use crate::state::State;
-use crate::waveforms::adsr_envelope::ADSREnvelope;
-use crate::waveforms::sawtooth_wave::SawtoothWave;
-use crate::waveforms::sine_wave::SineWave;
-use crate::waveforms::square_wave::SquareWave;
-use crate::waveforms::triangle_wave::TriangleWave;
-use crate::waveforms::{Waveform, AMPLITUDE};
-
-/// Effects processor that applies enabled effects to an audio source
-struct EffectsProcessor<S: Source<Item = f32>> {
-    source: S,
-    delay_effect: DelayEffect,
-    reverb_effect: ReverbEffect,
-    flanger_effect: FlangerEffect,
-    delay_enabled: bool,
-    reverb_enabled: bool,
-    flanger_enabled: bool,
-}
+use crate::state::{HeldNote, RecordingState, State};
 
-impl<S: Source<Item = f32>> EffectsProcessor<S> {
-    fn new(source: S, state: &State) -> Self {
-        Self {
-            source,
-            delay_effect: DelayEffect::new(300.0, 0.55, 0.5, 44100), // Enhanced parameters
-            reverb_effect: ReverbEffect::new(0.7, 0.4, 0.6, 44100),  // Larger room, more wet
-            flanger_effect: FlangerEffect::new(0.5, 0.7, 0.1, 0.5, 44100),
-            delay_enabled: state.delay_enabled,
-            reverb_enabled: state.reverb_enabled,
-            flanger_enabled: state.flanger_enabled,
-        }
-    }
-}
-
-impl<S: Source<Item = f32>> Iterator for EffectsProcessor<S> {
-    type Item = f32;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        self.source.next().map(|mut sample| {
-            // Apply effects in series: Delay -> Reverb -> Flanger
-            if self.delay_enabled {
-                sample = self.delay_effect.process_sample(sample);
-            }
-            if self.reverb_enabled {
-                sample = self.reverb_effect.process_sample(sample);
-            }
-            if self.flanger_enabled {
-                sample = self.flanger_effect.process_sample(sample);
-            }
-            sample
-        })
-    }
-}
-
-impl<S: Source<Item = f32>> Source for EffectsProcessor<S> {
-    fn current_frame_len(&self) -> Option<usize> {
-        self.source.current_frame_len()
-    }
-
-    fn channels(&self) -> u16 {
-        self.source.channels()
-    }
-
-    fn sample_rate(&self) -> u32 {
-        self.source.sample_rate()
-    }
-
-    fn total_duration(&self) -> Option<Duration> {
-        self.source.total_duration()
-    }
-}
 use crate::{
     graphics::constants::*,
     graphics::waveform_display::generate_waveform_display
 };
 
-// Handles playing a musical note with a specified octave, waveform, and duration.
+/// Gain reduction shown by a full meter, in dB
+const GAIN_REDUCTION_RANGE_DB: f32 = 24.0;
+const GAIN_REDUCTION_COLOR: u32 = 0xFFFF8800; // Orange
+
+// Handles playing a musical note on the current track with its octave, waveform and envelope.
 ///
 /// # Parameters
-/// - `octave`: A mutable reference to the current octave of the synthesizer.
-/// - `sink`: A mutable reference to the audio sink where the sound will be played.
-/// - `current_waveform`: The waveform enum representing the type of waveform to use for synthesizing the sound.
+/// - `state`: A mutable reference to the state, whose current track allocates a voice for the note.
 /// - `note`: The musical note (pitch) to be played.
-pub fn handle_musical_note(state: &mut State, sink: &mut Sink, note: Note) {
+///
+/// # Returns
+/// The held note with the gate of the voice playing it; the note sustains until it is passed to `release_musical_note`.
+/// While recording, it also keeps when the note started, so overlapping notes are each recorded in full.
+pub fn handle_musical_note(state: &mut State, note: Note) -> HeldNote {
     // Get current track info without borrowing
     let current_track_id = state.current_track_id;
-    let base_frequency = note.frequency(state.tracks[current_track_id].octave);
+    let octave = state.tracks[current_track_id].octave;
+    let base_frequency = note.frequency(octave);
 
     // Store the current frequency for display purposes and reset animation timing
     state.current_frequency = Some(base_frequency);
     state.animation_start_time = std::time::Instant::now();
     state.key_release_time = None; // Clear any previous release time
 
-    // Stop any currently playing audio to prevent queueing
-    sink.stop();
+    // Create mixer and start the note on one of the current track's voices
+    let mixer = MultiTrackMixer::new(state.audio_config.sample_rate);
+    let current_track = &mut state.tracks[current_track_id];
+    let gate = mixer.note_on(current_track, note);
 
-    // Create mixer and play note on current track
-    let mixer = MultiTrackMixer::new(44100);
-    let current_track = &state.tracks[current_track_id];
-    mixer.play_note_on_track(current_track, note, sink);
-    
-    // Return early - mixer handles everything now
-    return;
-
-    /* LEGACY CODE - COMMENTED OUT
-    // For backwards compatibility, also create the original synth
-    // TODO: Remove this once full multi-track system is implemented
-    let synth = match current_track.waveform {
-        Waveform::SINE => {
-            let filtered_frequency = state.apply_lpf(base_frequency);
-            let sine_wave = SineWave::new(filtered_frequency);
-            let adsr_envelope = ADSREnvelope::new(
-                sine_wave,
-                state.attack_normalized() * 2.0,    // Convert 0-99 to 0-2 seconds
-                state.decay_normalized() * 2.0,
-                state.sustain_normalized(),
-                state.release_normalized() * 2.0
-            );
-            Box::new(adsr_envelope) as Box<dyn Source<Item=f32> + 'static + Send>
-        }
-        Waveform::SQUARE => {
-            let filtered_frequency = state.apply_lpf(base_frequency);
-            let square_wave = SquareWave::new(filtered_frequency);
-            let adsr_envelope = ADSREnvelope::new(
-                square_wave,
-                state.attack_normalized() * 2.0,
-                state.decay_normalized() * 2.0,
-                state.sustain_normalized(),
-                state.release_normalized() * 2.0
-            );
-            Box::new(adsr_envelope) as Box<dyn Source<Item=f32> + 'static + Send>
-        }
-        Waveform::TRIANGLE => {
-            let filtered_frequency = state.apply_lpf(base_frequency);
-            let triangle_wave = TriangleWave::new(filtered_frequency);
-            let adsr_envelope = ADSREnvelope::new(
-                triangle_wave,
-                state.attack_normalized() * 2.0,
-                state.decay_normalized() * 2.0,
-                state.sustain_normalized(),
-                state.release_normalized() * 2.0
-            );
-            Box::new(adsr_envelope) as Box<dyn Source<Item=f32> + 'static + Send>
-        }
-        Waveform::SAWTOOTH => {
-            let filtered_frequency = state.apply_lpf(base_frequency);
-            let sawtooth_wave = SawtoothWave::new(filtered_frequency);
-            let adsr_envelope = ADSREnvelope::new(
-                sawtooth_wave,
-                state.attack_normalized() * 2.0,
-                state.decay_normalized() * 2.0,
-                state.sustain_normalized(),
-                state.release_normalized() * 2.0
-            );
-            Box::new(adsr_envelope) as Box<dyn Source<Item=f32> + 'static + Send>
-        }
-    };
+    let recording = (state.recording_state == RecordingState::Recording)
+        .then(|| (std::time::Instant::now(), octave));
 
-    // Create Source from our Synth with ADSR envelope - envelope handles its own termination
-    let mut source = synth.amplify(AMPLITUDE);
+    HeldNote { note, gate, recording }
+}
 
-    // Apply effects chain if any are enabled
-    let source_with_effects: Box<dyn Source<Item=f32> + Send> = if state.delay_enabled || state.reverb_enabled || state.flanger_enabled {
-        // Create an effects-processing source
-        Box::new(EffectsProcessor::new(source, state))
-    } else {
-        Box::new(source)
-    };
+/// Handles the note-off of a note played live, starting its release stage.
+///
+/// A note pressed while recording is recorded here, lasting from its own press to its own release.
+///
+/// # Parameters
+/// - `state`: A mutable reference to the state.
+/// - `held`: The held note returned by `handle_musical_note` when the note started.
+pub fn release_musical_note(state: &mut State, held: HeldNote) {
+    held.gate.close();
+
+    if state.recording_state != RecordingState::Recording {
+        return;
+    }
 
-    // Play the sound source immediately, replacing any queued audio
-    let _result = sink.append(source_with_effects);
-    */
+    if let Some((start_time, octave)) = held.recording {
+        state.finish_recorded_note(held.note, start_time, octave);
+    }
 }
 
 
@@ -205,18 +91,24 @@ pub fn update_buffer_with_state(state: &State, sprites: &Sprites, window_buffer:
     // Draw all tangents as overlay on key sprites in their idle state first
     draw_idle_tangent_sprites(sprites, window_buffer, &tangent_map);
 
-    // Draw the bulb
-    // draw_bulb_sprite(state, sprites, window_buffer);
+    // Draw the bulb, lit while the current track's filter is on
+    draw_bulb_sprite(state, sprites, window_buffer);
 
-    // Draw the cutoff knob for LPF
-    // draw_filter_cutoff_knob_sprite(state, sprites, window_buffer);
+    // Draw the cutoff knob for the filter
+    draw_filter_cutoff_knob_sprite(state, sprites, window_buffer);
 
-    // Draw the idle knob to the left of the cutoff knob for LPF
-    // draw_idle_knob_sprite(sprites, window_buffer);
+    // Draw the resonance knob to the right of the cutoff knob
+    draw_filter_resonance_knob_sprite(state, sprites, window_buffer);
 
     // Draw ADSR faders
     draw_adsr_faders(state, sprites, window_buffer);
     
+    // Draw the master bus gain reduction next to the track list
+    draw_master_gain_reduction_meter(state, window_buffer);
+    
+    // Draw the frequency response of the equalizer the keys edit while it is on
+    draw_eq_response_curve(state, window_buffer);
+    
     // Draw control buttons - DISABLED: now using per-track transport
     // draw_control_buttons(state, window_buffer);
     
@@ -237,15 +129,8 @@ pub fn update_buffer_with_state(state: &State, sprites: &Sprites, window_buffer:
     
     // Always show the display frame, but only show waveform when playing or fading
     let (frequency, amplitude) = if state.current_frequency.is_some() || state.key_release_time.is_some() {
-        // Calculate amplitude based on whether key is pressed or released
-        let amplitude = if let Some(release_time) = state.key_release_time {
-            // Fade out over 2 seconds after key release
-            let fade_duration = release_time.elapsed().as_secs_f32();
-            let fade_factor = (1.0 - fade_duration / 2.0).max(0.0);
-            fade_factor
-        } else {
-            1.0 // Full brightness when key is pressed
-        };
+        // Follow the current track's amplitude envelope through attack, sustain and release
+        let amplitude = state.display_amplitude();
         
         // Use last played frequency during fade
         let frequency = state.current_frequency.unwrap_or(440.0);
@@ -257,8 +142,11 @@ pub fn update_buffer_with_state(state: &State, sprites: &Sprites, window_buffer:
     
     // Always generate display (frame always visible, waveform only when amplitude > 0)
     // Use current track's waveform
-    let current_track_waveform = state.tracks[state.current_track_id].waveform.clone();
-    let waveform_sprite = generate_waveform_display(frequency, current_track_waveform, animation_time, amplitude);
+    let current_track = &state.tracks[state.current_track_id];
+    let waveform_sprite = generate_waveform_display(frequency,
+                                                    current_track,
+                                                    animation_time,
+                                                    amplitude);
     draw_display_sprite_single(&waveform_sprite, window_buffer);
     
 
@@ -379,9 +267,9 @@ pub fn draw_effects_buttons(state: &State, buffer: &mut Vec<u32>) {
     // Show current track's effects
     let current_track = &state.tracks[state.current_track_id];
     let effects = [
-        ("DLY", current_track.delay_enabled, 0xFF4444FF), // Blue for delay
-        ("REV", current_track.reverb_enabled, 0xFF44FF44), // Green for reverb  
-        ("FLG", current_track.flanger_enabled, 0xFFFF4444), // Red for flanger
+        ("DLY", current_track.effects.is_enabled(EffectSlot::Delay), 0xFF4444FF), // Blue for delay
+        ("REV", current_track.effects.is_enabled(EffectSlot::Reverb), 0xFF44FF44), // Green for reverb  
+        ("FLG", current_track.effects.is_enabled(EffectSlot::Flanger), 0xFFFF4444), // Red for flanger
     ];
     
     for (i, (label, enabled, base_color)) in effects.iter().enumerate() {
@@ -559,9 +447,31 @@ pub fn draw_track_info(state: &State, buffer: &mut Vec<u32>) {
         let vol_x = loop_x + 25;
         let vol_width = (track.volume * 25.0) as usize;
         draw_volume_bar(vol_x, y + 8, vol_width, 4, 0xFF0088FF, buffer);
+        
+        // Gain reduction of the track's gate, compressor and limiter
+        let reduction_x = vol_x + 30;
+        let reduction_width = gain_reduction_length(track.gain_reduction_db(), 40);
+        draw_volume_bar(reduction_x, y + 8, reduction_width, 4, GAIN_REDUCTION_COLOR, buffer);
     }
 }
 
+/// Draw the master bus gain reduction as a bar growing down from the top, right of the track list
+pub fn draw_master_gain_reduction_meter(state: &State, buffer: &mut Vec<u32>) {
+    let x = 10 + 250 + 6; // Just past the track bars
+    let y = 10; // Level with the first track
+    let width = 6;
+    let height = 4 * 25 - 5; // Down to the bottom of the last track bar
+    
+    draw_track_bar(x, y, width, height, 0xFF202020, buffer);
+    let reduction_height = gain_reduction_length(state.master_track.gain_reduction_db(), height);
+    draw_volume_bar(x + 1, y, width - 2, reduction_height, GAIN_REDUCTION_COLOR, buffer);
+}
+
+/// Length in pixels of a gain reduction meter, full at GAIN_REDUCTION_RANGE_DB
+fn gain_reduction_length(reduction_db: f32, full_length: usize) -> usize {
+    ((reduction_db / GAIN_REDUCTION_RANGE_DB).clamp(0.0, 1.0) * full_length as f32) as usize
+}
+
 /// Draw a simple track background bar
 fn draw_track_bar(x: usize, y: usize, width: usize, height: usize, color: u32, buffer: &mut Vec<u32>) {
     for dy in 0..height {
@@ -1,20 +1,63 @@
 use rodio::Source;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
+use crate::waveforms::breakpoint_envelope::{Dahdsr, EnvelopeGenerator, EnvelopeShape};
 
-/// ADSR envelope wrapper that applies envelope shaping to any source
+/// Handle to the gate of an envelope
+///
+/// The gate opens when the note starts and closing it starts the release stage,
+/// so whoever holds the note (keyboard, mouse or sequencer) decides when it ends.
+#[derive(Debug, Clone)]
+pub struct EnvelopeGate {
+    open: Arc<AtomicBool>,
+}
+
+impl EnvelopeGate {
+    /// Open gate, for sources that follow a note before the envelope around them exists
+    pub fn new() -> Self {
+        Self {
+            open: Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    /// Close the gate, the envelope enters its release stage on its next sample
+    pub fn close(&self) {
+        self.open.store(false, Ordering::Relaxed);
+    }
+
+    /// Whether the note is still held
+    pub fn is_open(&self) -> bool {
+        self.open.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for EnvelopeGate {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Source that can also produce stereo frames
+pub trait StereoSource: Source<Item = f32> {
+    /// Next left/right frame, or None when the source has ended
+    fn next_frame(&mut self) -> Option<(f32, f32)>;
+}
+
+/// Envelope wrapper that applies envelope shaping to any source
+///
+/// Follows an ADSR, DAHDSR or breakpoint shape. Sustain holds for as long as the gate is
+/// open; the release stage starts from whatever level the envelope has reached when the gate closes.
 pub struct ADSREnvelope<S>
 where
     S: Source<Item = f32>,
 {
     source: S,
+    generator: EnvelopeGenerator,
     sample_count: usize,
-    attack_samples: usize,
-    decay_samples: usize,
-    sustain_level: f32,
-    release_samples: usize,
-    release_start_sample: Option<usize>,
     is_released: bool,
-    max_sustain_samples: usize, // Maximum time to hold sustain before auto-release
+    gate: EnvelopeGate,
+    gate_length_samples: Option<usize>, // Sample at which the gate closes by itself, for sequenced notes
 }
 
 impl<S> ADSREnvelope<S>
@@ -28,63 +71,95 @@ where
         sustain: f32,   // Sustain level (0.0 to 1.0)
         release: f32,   // Release time in seconds
     ) -> Self {
-        let sample_rate = source.sample_rate() as f32;
-        
+        Self::with_shape(source, Dahdsr::adsr(attack, decay, sustain, release).shape())
+    }
+
+    /// Shape the source with any envelope, such as a DAHDSR or a free breakpoint envelope
+    pub fn with_shape(source: S, shape: EnvelopeShape) -> Self {
+        Self::with_gate(source, shape, EnvelopeGate::new())
+    }
+    
+    /// Shape the source with an envelope whose gate the source also follows
+    pub fn with_gate(source: S, shape: EnvelopeShape, gate: EnvelopeGate) -> Self {
+        let sample_rate = source.sample_rate();
+
         Self {
             source,
+            generator: EnvelopeGenerator::new(shape, sample_rate),
             sample_count: 0,
-            attack_samples: (attack * sample_rate) as usize,
-            decay_samples: (decay * sample_rate) as usize,
-            sustain_level: sustain,
-            release_samples: ((release * sample_rate) as usize).max(1), // Minimum 1 sample for release
-            release_start_sample: None,
             is_released: false,
-            max_sustain_samples: ((release * 0.5 + 0.05) * sample_rate) as usize, // Shorter auto-release based on release setting
+            gate,
+            gate_length_samples: None,
         }
     }
 
+    /// The shaped source, to change its pitch while the envelope runs
+    pub fn source_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    /// Handle that closes this envelope's gate
+    pub fn gate(&self) -> EnvelopeGate {
+        self.gate.clone()
+    }
+
+    /// Close the gate after the given time in seconds, as recorded for a sequenced note
+    pub fn release_after(&mut self, duration: f32) {
+        let sample_rate = self.source.sample_rate() as f32;
+        self.gate_length_samples = Some((duration.max(0.0) * sample_rate) as usize);
+    }
+
+    /// Keep the envelope running for a legato note, taking over the next note's length
+    pub fn continue_with<T: Source<Item = f32>>(&mut self, next: &ADSREnvelope<T>) {
+        self.gate_length_samples = next.gate_length_samples.map(|length| self.sample_count + length);
+    }
+
     pub fn release(&mut self) {
         if !self.is_released {
-            self.release_start_sample = Some(self.sample_count);
+            self.generator.release();
             self.is_released = true;
+            self.gate.close();
         }
     }
 
-    fn calculate_envelope_amplitude(&self) -> f32 {
-        if let Some(release_start) = self.release_start_sample {
-            // Release phase
-            let release_progress = self.sample_count - release_start;
-            if release_progress >= self.release_samples {
-                return 0.0; // Envelope finished
-            }
-            
-            if self.release_samples == 0 {
-                return 0.0;
-            }
-            
-            let release_factor = 1.0 - (release_progress as f32 / self.release_samples as f32);
-            return self.sustain_level * release_factor;
-        }
+    /// Whether the release stage has started
+    pub fn is_released(&self) -> bool {
+        self.is_released
+    }
 
-        if self.sample_count <= self.attack_samples {
-            // Attack phase
-            if self.attack_samples == 0 {
-                return 1.0;
-            }
-            let attack_progress = self.sample_count as f32 / self.attack_samples as f32;
-            return attack_progress.min(1.0);
-        } else if self.sample_count <= self.attack_samples + self.decay_samples {
-            // Decay phase
-            if self.decay_samples == 0 {
-                return self.sustain_level;
+    /// Current envelope amplitude (0.0 - 1.0)
+    pub fn current_level(&self) -> f32 {
+        self.generator.level()
+    }
+    
+    /// Start the release once the gate closes, either through a handle or after the note's length
+    fn follow_gate(&mut self) {
+        if !self.is_released {
+            let gate_expired = self.gate_length_samples.is_some_and(|length| self.sample_count >= length);
+            if gate_expired || !self.gate.is_open() {
+                self.release();
             }
-            let decay_progress = (self.sample_count - self.attack_samples) as f32 / self.decay_samples as f32;
-            return 1.0 - (1.0 - self.sustain_level) * decay_progress;
-        } else {
-            // Sustain phase 
-            return self.sustain_level;
         }
     }
+    
+    /// Advance the envelope by one sample, or None once the sound has ended
+    fn next_amplitude(&mut self) -> Option<f32> {
+        let envelope_amplitude = self.generator.next_level();
+        
+        self.sample_count += 1;
+        
+        // The sound ends once the envelope has run through all of its points
+        if self.generator.is_finished() {
+            return None;
+        }
+        
+        // Only end sound if we're in release phase and amplitude is effectively zero
+        if envelope_amplitude < 0.0001 && self.generator.is_released() {
+            return None;
+        }
+        
+        Some(envelope_amplitude)
+    }
 }
 
 impl<S> Iterator for ADSREnvelope<S>
@@ -94,33 +169,26 @@ where
     type Item = f32;
 
     fn next(&mut self) -> Option<Self::Item> {
+        self.follow_gate();
         let sample = self.source.next()?;
-        let envelope_amplitude = self.calculate_envelope_amplitude();
-        
-        self.sample_count += 1;
-        
-        // Auto-release after max sustain time
-        if self.sample_count > self.attack_samples + self.decay_samples + self.max_sustain_samples && !self.is_released {
-            self.release_start_sample = Some(self.sample_count);
-            self.is_released = true;
-        }
-        
-        // If we're in release phase and envelope is finished, return None to end the sound
-        if let Some(release_start) = self.release_start_sample {
-            if self.sample_count - release_start >= self.release_samples {
-                return None;
-            }
-        }
-        
-        // Only end sound if we're in release phase and amplitude is effectively zero
-        if envelope_amplitude < 0.0001 && self.release_start_sample.is_some() {
-            return None;
-        }
-        
+        let envelope_amplitude = self.next_amplitude()?;
         Some(sample * envelope_amplitude)
     }
 }
 
+impl<S> ADSREnvelope<S>
+where
+    S: StereoSource,
+{
+    /// Next stereo frame of the source, both channels shaped by the envelope
+    pub fn next_frame(&mut self) -> Option<(f32, f32)> {
+        self.follow_gate();
+        let (left, right) = self.source.next_frame()?;
+        let envelope_amplitude = self.next_amplitude()?;
+        Some((left * envelope_amplitude, right * envelope_amplitude))
+    }
+}
+
 impl<S> Source for ADSREnvelope<S>
 where
     S: Source<Item = f32>,
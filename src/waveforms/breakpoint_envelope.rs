@@ -0,0 +1,257 @@
+use std::fmt;
+use crate::waveforms::envelope_curve::EnvelopeCurve;
+
+/// Rate used when an envelope is evaluated for display rather than for audio
+const CONTROL_RATE: u32 = 1000;
+
+/// Number of breakpoints the fader UI can edit
+pub const MAX_BREAKPOINTS: usize = 6;
+
+/// A point the envelope moves to, reached `time` seconds after the previous one
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Breakpoint {
+    pub level: f32,            // Target level (0.0 - 1.0)
+    pub time: f32,             // Seconds to reach the level from the previous point
+    pub curve: EnvelopeCurve,  // Shape of the move towards the level
+}
+
+impl Breakpoint {
+    pub fn new(level: f32, time: f32, curve: EnvelopeCurve) -> Self {
+        Self {
+            level: level.clamp(0.0, 1.0),
+            time: time.max(0.0),
+            curve,
+        }
+    }
+}
+
+/// Envelope made of any number of breakpoints, starting from silence
+///
+/// While the gate is open the envelope stops at the sustain point, or loops from the
+/// loop start back to the sustain point when a loop is set. Closing the gate continues
+/// with the points after the sustain point. Without a sustain point it runs as a one-shot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvelopeShape {
+    pub points: Vec<Breakpoint>,
+    pub sustain_point: Option<usize>,  // Index of the point held while the gate is open
+    pub loop_start: Option<usize>,     // Index of the point the sustain loop jumps back to
+}
+
+impl EnvelopeShape {
+    pub fn new(points: Vec<Breakpoint>, sustain_point: Option<usize>, loop_start: Option<usize>) -> Self {
+        Self {
+            points,
+            sustain_point,
+            loop_start,
+        }
+    }
+
+    /// Looping shape used when a track switches to its breakpoint envelope
+    pub fn default_breakpoints() -> Self {
+        Self::new(
+            vec![
+                Breakpoint::new(1.0, 0.01, EnvelopeCurve::Linear),
+                Breakpoint::new(0.4, 0.15, EnvelopeCurve::Exponential),
+                Breakpoint::new(0.8, 0.15, EnvelopeCurve::Logarithmic),
+                Breakpoint::new(0.4, 0.15, EnvelopeCurve::Exponential),
+                Breakpoint::new(0.2, 0.3, EnvelopeCurve::Exponential),
+                Breakpoint::new(0.0, 0.3, EnvelopeCurve::Exponential),
+            ],
+            Some(3),
+            Some(2),
+        )
+    }
+
+    /// Level after the gate was held for `held` seconds and, if given, released for `released` seconds
+    ///
+    /// Runs the same generator the voices use, so displays match what is heard.
+    pub fn level_at(&self, held: f32, released: Option<f32>) -> f32 {
+        let mut generator = EnvelopeGenerator::new(self.clone(), CONTROL_RATE);
+
+        for _ in 0..(held.max(0.0) * CONTROL_RATE as f32) as usize {
+            generator.next_level();
+        }
+
+        if let Some(released) = released {
+            generator.release();
+            for _ in 0..(released.max(0.0) * CONTROL_RATE as f32) as usize {
+                generator.next_level();
+            }
+        }
+
+        generator.level()
+    }
+}
+
+/// Delay, attack, hold, decay, sustain, release settings (times in seconds)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dahdsr {
+    pub delay: f32,
+    pub attack: f32,
+    pub hold: f32,
+    pub decay: f32,
+    pub sustain: f32,  // Sustain level (0.0 - 1.0)
+    pub release: f32,
+    pub attack_curve: EnvelopeCurve,
+    pub decay_curve: EnvelopeCurve,
+    pub release_curve: EnvelopeCurve,
+}
+
+impl Dahdsr {
+    /// Plain ADSR with straight stages and no delay or hold
+    pub fn adsr(attack: f32, decay: f32, sustain: f32, release: f32) -> Self {
+        Self {
+            delay: 0.0,
+            attack,
+            hold: 0.0,
+            decay,
+            sustain,
+            release,
+            attack_curve: EnvelopeCurve::Linear,
+            decay_curve: EnvelopeCurve::Linear,
+            release_curve: EnvelopeCurve::Linear,
+        }
+    }
+
+    /// Breakpoints of the envelope, sustaining at the end of the decay
+    pub fn shape(&self) -> EnvelopeShape {
+        EnvelopeShape::new(
+            vec![
+                Breakpoint::new(0.0, self.delay, EnvelopeCurve::Linear),
+                Breakpoint::new(1.0, self.attack, self.attack_curve),
+                Breakpoint::new(1.0, self.hold, EnvelopeCurve::Linear),
+                Breakpoint::new(self.sustain, self.decay, self.decay_curve),
+                Breakpoint::new(0.0, self.release, self.release_curve),
+            ],
+            Some(3),
+            None,
+        )
+    }
+}
+
+/// Runs an envelope shape sample by sample
+///
+/// Produces levels from 0.0 to 1.0, so besides shaping a voice's amplitude it can
+/// drive any other parameter as a modulation source.
+#[derive(Debug, Clone)]
+pub struct EnvelopeGenerator {
+    shape: EnvelopeShape,
+    sample_rate: f32,
+    segment: usize,         // Index of the point currently being approached
+    segment_length: usize,  // Length of the current segment in samples
+    position: usize,        // Samples into the current segment
+    from_level: f32,        // Level the current segment started at
+    level: f32,
+    holding: bool,          // Waiting at the sustain point for the gate to close
+    released: bool,
+}
+
+impl EnvelopeGenerator {
+    pub fn new(shape: EnvelopeShape, sample_rate: u32) -> Self {
+        let mut generator = Self {
+            shape,
+            sample_rate: sample_rate as f32,
+            segment: 0,
+            segment_length: 0,
+            position: 0,
+            from_level: 0.0,
+            level: 0.0,
+            holding: false,
+            released: false,
+        };
+        generator.start_segment(0);
+        generator
+    }
+
+    /// Advance by one sample and return the new level
+    pub fn next_level(&mut self) -> f32 {
+        if self.holding {
+            return self.level;
+        }
+
+        let Some(point) = self.shape.points.get(self.segment).copied() else {
+            return self.level; // Past the last point
+        };
+
+        self.position += 1;
+        if self.position >= self.segment_length {
+            self.level = point.level;
+            self.finish_segment();
+        } else {
+            let progress = self.position as f32 / self.segment_length as f32;
+            self.level = point.curve.interpolate(self.from_level, point.level, progress);
+        }
+
+        self.level
+    }
+
+    /// Close the gate, moving on to the points after the sustain point from the current level
+    ///
+    /// One-shot shapes without a sustain point ignore the gate.
+    pub fn release(&mut self) {
+        if self.released {
+            return;
+        }
+
+        if let Some(sustain_point) = self.shape.sustain_point {
+            self.released = true;
+            self.holding = false;
+            self.start_segment(sustain_point + 1);
+        }
+    }
+
+    /// Whether the envelope has moved past its sustain point after a release
+    pub fn is_released(&self) -> bool {
+        self.released
+    }
+
+    /// Whether every point has been reached
+    pub fn is_finished(&self) -> bool {
+        self.segment >= self.shape.points.len()
+    }
+
+    /// Current level (0.0 - 1.0)
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+
+    fn start_segment(&mut self, index: usize) {
+        self.segment = index;
+        self.position = 0;
+        self.from_level = self.level;
+        self.segment_length = self.shape.points.get(index)
+            .map(|point| (point.time * self.sample_rate) as usize)
+            .unwrap_or(0);
+    }
+
+    fn finish_segment(&mut self) {
+        let reached = self.segment;
+
+        // Hold or loop at the sustain point while the gate is open
+        if !self.released && self.shape.sustain_point == Some(reached) {
+            match self.shape.loop_start {
+                Some(loop_start) if loop_start <= reached => self.start_segment(loop_start),
+                _ => self.holding = true,
+            }
+            return;
+        }
+
+        self.start_segment(reached + 1);
+    }
+}
+
+/// Which envelope shapes a track's amplitude
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EnvelopeMode {
+    Dahdsr,      // Delay, attack, hold, decay, sustain and release faders
+    Breakpoint,  // Free breakpoint envelope
+}
+
+impl fmt::Display for EnvelopeMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvelopeMode::Dahdsr => write!(f, "DAHDSR"),
+            EnvelopeMode::Breakpoint => write!(f, "Breakpoint"),
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use rodio::Source;
+use std::f32::consts::PI;
+use std::fmt;
+use std::time::Duration;
+use crate::effects::{AudioEffect, FilterMode, StateVariableFilter};
+use crate::music_theory::note::Note;
+use crate::waveforms::breakpoint_envelope::{Breakpoint, EnvelopeShape};
+use crate::waveforms::envelope_curve::EnvelopeCurve;
+use crate::waveforms::noise::{NoiseColor, NoiseGenerator};
+use crate::waveforms::MONO;
+
+/// Frequencies of the six square oscillators behind the hi-hats' metallic ring, as on the TR-808
+const HAT_OSCILLATOR_HZ: [f32; 6] = [205.3, 304.4, 369.6, 522.7, 540.0, 800.0];
+
+/// Onsets of the clap's bursts in seconds, the last one starts the reverberant tail
+const CLAP_BURST_ONSETS: [f32; 4] = [0.0, 0.011, 0.022, 0.033];
+
+/// Synthesized drum sound
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DrumSound {
+    Kick,       // Sine with a fast downward pitch sweep
+    Snare,      // Two tuned sines plus a burst of noise
+    ClosedHat,  // Metallic square cluster and noise, short decay
+    OpenHat,    // Same as the closed hat with a long decay
+    Clap,       // Band-passed noise in quick bursts
+}
+
+impl DrumSound {
+    /// Drum played by a note on a drum-mode track, laid out like the General MIDI drum map
+    ///
+    /// C, C#: kick. D, E: snare. D#, F: clap. F#, G, G#: closed hat. A, A#, B: open hat.
+    pub fn for_note(note: Note) -> DrumSound {
+        match note {
+            Note::C | Note::CSharp => DrumSound::Kick,
+            Note::D | Note::E => DrumSound::Snare,
+            Note::DSharp | Note::F => DrumSound::Clap,
+            Note::FSharp | Note::G | Note::GSharp => DrumSound::ClosedHat,
+            Note::A | Note::ASharp | Note::B => DrumSound::OpenHat,
+        }
+    }
+
+    /// Length of the sound in seconds
+    pub fn length(&self) -> f32 {
+        match self {
+            DrumSound::Kick => 0.8,
+            DrumSound::Snare => 0.4,
+            DrumSound::ClosedHat => 0.15,
+            DrumSound::OpenHat => 0.8,
+            DrumSound::Clap => 0.5,
+        }
+    }
+}
+
+impl fmt::Display for DrumSound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DrumSound::Kick => write!(f, "Kick"),
+            DrumSound::Snare => write!(f, "Snare"),
+            DrumSound::ClosedHat => write!(f, "Closed Hat"),
+            DrumSound::OpenHat => write!(f, "Open Hat"),
+            DrumSound::Clap => write!(f, "Clap"),
+        }
+    }
+}
+
+/// One hit of a synthesized drum
+///
+/// Drums shape their own amplitude and end by themselves, so they play through a flat
+/// envelope (see [DrumVoice::envelope]) that ignores note-off.
+#[derive(Debug, Clone)]
+pub struct DrumVoice {
+    sound: DrumSound,
+    sample_rate: f32,
+    position: usize,                // Samples since the hit
+    length: usize,                  // Length of the hit in samples
+    tune: f32,                      // Frequency factor set by pitch modulation
+    reference_frequency: f32,       // Note frequency the hit was started with
+    tone_phases: [f32; 6],          // Phases of the kick/snare sines or hat squares
+    noise: NoiseGenerator,
+    filter: StateVariableFilter,    // Shapes the noise: high-pass for snare and hats, band-pass for the clap
+}
+
+impl DrumVoice {
+    pub fn new(sound: DrumSound, frequency: f32, sample_rate: u32) -> Self {
+        let filter = match sound {
+            DrumSound::Kick => StateVariableFilter::new(FilterMode::LowPass, 8000.0, 0.0, sample_rate),
+            DrumSound::Snare => StateVariableFilter::new(FilterMode::HighPass, 1500.0, 0.1, sample_rate),
+            DrumSound::ClosedHat | DrumSound::OpenHat => StateVariableFilter::new(FilterMode::HighPass, 7000.0, 0.2, sample_rate),
+            DrumSound::Clap => StateVariableFilter::new(FilterMode::BandPass, 1200.0, 0.5, sample_rate),
+        };
+
+        Self {
+            sound,
+            sample_rate: sample_rate as f32,
+            position: 0,
+            length: (sound.length() * sample_rate as f32) as usize,
+            tune: 1.0,
+            reference_frequency: frequency.max(1.0),
+            tone_phases: [0.0; 6],
+            noise: NoiseGenerator::new(NoiseColor::White, sound as u32 + 1, sample_rate),
+            filter,
+        }
+    }
+
+    /// Flat envelope lasting as long as the longest drum, leaving the shape to the drum itself
+    pub fn envelope() -> EnvelopeShape {
+        EnvelopeShape::new(
+            vec![
+                Breakpoint::new(1.0, 0.0, EnvelopeCurve::Linear),
+                Breakpoint::new(1.0, 1.0, EnvelopeCurve::Linear),
+            ],
+            None,
+            None,
+        )
+    }
+
+    /// Retune the drum relative to the note it was started with
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.tune = frequency / self.reference_frequency;
+    }
+
+    /// Advance a tone phase by a frequency and return its new value
+    fn advance_phase(&mut self, index: usize, frequency: f32) -> f32 {
+        let phase = &mut self.tone_phases[index];
+        *phase += frequency * self.tune / self.sample_rate;
+        if *phase >= 1.0 {
+            *phase -= phase.floor();
+        }
+        *phase
+    }
+
+    fn kick(&mut self, time: f32) -> f32 {
+        // Pitch falls from 150 Hz to 45 Hz in the first few tens of milliseconds
+        let frequency = 45.0 + 105.0 * (-time / 0.03).exp();
+        let body = (2.0 * PI * self.advance_phase(0, frequency)).sin() * (-time / 0.25).exp();
+        let click = self.filter.process_sample(self.noise.next_sample()) * (-time / 0.002).exp() * 0.2;
+        body + click
+    }
+
+    fn snare(&mut self, time: f32) -> f32 {
+        let tone = (2.0 * PI * self.advance_phase(0, 180.0)).sin() * 0.6
+            + (2.0 * PI * self.advance_phase(1, 330.0)).sin() * 0.4;
+        let noise = self.filter.process_sample(self.noise.next_sample());
+        tone * (-time / 0.06).exp() * 0.4 + noise * (-time / 0.12).exp() * 0.6
+    }
+
+    fn hat(&mut self, time: f32, decay: f32) -> f32 {
+        let mut metal = 0.0;
+        for (index, frequency) in HAT_OSCILLATOR_HZ.into_iter().enumerate() {
+            metal += if self.advance_phase(index, frequency) < 0.5 { 1.0 } else { -1.0 };
+        }
+        let input = metal / HAT_OSCILLATOR_HZ.len() as f32 * 0.5 + self.noise.next_sample() * 0.5;
+        self.filter.process_sample(input) * (-time / decay).exp()
+    }
+
+    fn clap(&mut self, time: f32) -> f32 {
+        // Each burst restarts the envelope, the last one rings out longer
+        let (onset, decay) = match CLAP_BURST_ONSETS.iter().rposition(|&onset| time >= onset) {
+            Some(index) if index == CLAP_BURST_ONSETS.len() - 1 => (CLAP_BURST_ONSETS[index], 0.12),
+            Some(index) => (CLAP_BURST_ONSETS[index], 0.004),
+            None => (0.0, 0.004),
+        };
+        let noise = self.filter.process_sample(self.noise.next_sample());
+        noise * (-(time - onset) / decay).exp() * 1.5
+    }
+}
+
+/// Implementation of the [Iterator] trait for the [DrumVoice]
+impl Iterator for DrumVoice {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.position >= self.length {
+            return None;
+        }
+
+        let time = self.position as f32 / self.sample_rate;
+        self.position += 1;
+
+        let sample = match self.sound {
+            DrumSound::Kick => self.kick(time),
+            DrumSound::Snare => self.snare(time),
+            DrumSound::ClosedHat => self.hat(time, 0.03),
+            DrumSound::OpenHat => self.hat(time, 0.25),
+            DrumSound::Clap => self.clap(time),
+        };
+
+        Some(sample)
+    }
+}
+
+/// Implementation of the [Source] trait for the [DrumVoice]
+impl Source for DrumVoice {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        MONO
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate as u32
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(self.length as f32 / self.sample_rate))
+    }
+}
@@ -0,0 +1,68 @@
+use std::fmt;
+
+/// Curvature used by the exponential and logarithmic presets
+const PRESET_CURVATURE: f32 = 5.0;
+
+/// Largest curvature an envelope stage can be bent to in either direction
+pub const MAX_CURVATURE: f32 = 10.0;
+
+/// Shape of an envelope stage as it moves from one level to the next
+///
+/// Positive curvature moves fast at first and eases into the target like an analog RC envelope,
+/// negative curvature starts slowly and speeds up towards the target.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EnvelopeCurve {
+    Linear,
+    Exponential,  // Fast start, long tail - natural decays and releases
+    Logarithmic,  // Slow start, steep finish - swells
+    Curved(f32),  // Adjustable curvature (-MAX_CURVATURE to MAX_CURVATURE)
+}
+
+impl EnvelopeCurve {
+    /// Curvature of the shape, 0.0 for a straight line
+    pub fn curvature(&self) -> f32 {
+        match self {
+            EnvelopeCurve::Linear => 0.0,
+            EnvelopeCurve::Exponential => PRESET_CURVATURE,
+            EnvelopeCurve::Logarithmic => -PRESET_CURVATURE,
+            EnvelopeCurve::Curved(curvature) => curvature.clamp(-MAX_CURVATURE, MAX_CURVATURE),
+        }
+    }
+
+    /// The same shape with its curvature changed by `delta`, straightening to linear at zero
+    pub fn bend(&self, delta: f32) -> EnvelopeCurve {
+        let curvature = (self.curvature() + delta).clamp(-MAX_CURVATURE, MAX_CURVATURE);
+        if curvature.abs() < 1e-3 {
+            EnvelopeCurve::Linear
+        } else {
+            EnvelopeCurve::Curved(curvature)
+        }
+    }
+
+    /// Map linear progress through a stage (0.0 - 1.0) to shaped progress (0.0 - 1.0)
+    pub fn shape(&self, progress: f32) -> f32 {
+        let progress = progress.clamp(0.0, 1.0);
+        let curvature = self.curvature();
+        if curvature.abs() < 1e-3 {
+            return progress;
+        }
+
+        (1.0 - (-curvature * progress).exp()) / (1.0 - (-curvature).exp())
+    }
+
+    /// Level between `from` and `to` after the given progress through the stage
+    pub fn interpolate(&self, from: f32, to: f32, progress: f32) -> f32 {
+        from + (to - from) * self.shape(progress)
+    }
+}
+
+impl fmt::Display for EnvelopeCurve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvelopeCurve::Linear => write!(f, "Linear"),
+            EnvelopeCurve::Exponential => write!(f, "Exponential"),
+            EnvelopeCurve::Logarithmic => write!(f, "Logarithmic"),
+            EnvelopeCurve::Curved(curvature) => write!(f, "Curved ({:+.0})", curvature),
+        }
+    }
+}
@@ -0,0 +1,398 @@
+use rodio::Source;
+use std::f32::consts::PI;
+use std::fmt;
+use std::time::Duration;
+use crate::waveforms::adsr_envelope::EnvelopeGate;
+use crate::waveforms::breakpoint_envelope::{Dahdsr, EnvelopeGenerator};
+use crate::waveforms::envelope_curve::EnvelopeCurve;
+use crate::waveforms::oscillator::phase_at_sample;
+use crate::waveforms::sine_wave::sine_at;
+use crate::waveforms::MONO;
+
+/// Most operators a patch can have
+pub const MAX_OPERATORS: usize = 4;
+
+/// Fewest operators a patch can have
+pub const MIN_OPERATORS: usize = 2;
+
+/// Modulation index in radians of a modulator at full level
+pub const MODULATION_INDEX: f32 = 4.0;
+
+/// How the operators of a patch are connected
+///
+/// Operators are numbered from 1. Modulators always have a higher number than the
+/// operators they modulate, and the highest active operator has the feedback loop.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FmAlgorithm {
+    Stack,      // 4 → 3 → 2 → 1, a single carrier
+    TwoStacks,  // 2 → 1 and 4 → 3, two carriers
+    Branch,     // 2, 3 and 4 all modulate 1
+    Parallel,   // Every operator is a carrier, an additive organ
+}
+
+impl FmAlgorithm {
+    /// The next algorithm in the cycle, wrapping around
+    pub fn next(&self) -> FmAlgorithm {
+        match self {
+            FmAlgorithm::Stack => FmAlgorithm::TwoStacks,
+            FmAlgorithm::TwoStacks => FmAlgorithm::Branch,
+            FmAlgorithm::Branch => FmAlgorithm::Parallel,
+            FmAlgorithm::Parallel => FmAlgorithm::Stack,
+        }
+    }
+
+    /// Indices of the operators modulating an operator
+    pub fn modulators(&self, operator: usize) -> &'static [usize] {
+        match (self, operator) {
+            (FmAlgorithm::Stack, 0) => &[1],
+            (FmAlgorithm::Stack, 1) => &[2],
+            (FmAlgorithm::Stack, 2) => &[3],
+            (FmAlgorithm::TwoStacks, 0) => &[1],
+            (FmAlgorithm::TwoStacks, 2) => &[3],
+            (FmAlgorithm::Branch, 0) => &[1, 2, 3],
+            _ => &[],
+        }
+    }
+
+    /// Whether an operator is heard rather than only modulating others
+    pub fn is_carrier(&self, operator: usize) -> bool {
+        match self {
+            FmAlgorithm::Stack | FmAlgorithm::Branch => operator == 0,
+            FmAlgorithm::TwoStacks => operator == 0 || operator == 2,
+            FmAlgorithm::Parallel => true,
+        }
+    }
+}
+
+impl fmt::Display for FmAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FmAlgorithm::Stack => write!(f, "Stack"),
+            FmAlgorithm::TwoStacks => write!(f, "Two Stacks"),
+            FmAlgorithm::Branch => write!(f, "Branch"),
+            FmAlgorithm::Parallel => write!(f, "Parallel"),
+        }
+    }
+}
+
+/// Sine operator of an FM patch
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FmOperator {
+    pub ratio: f32,        // Frequency relative to the note
+    pub level: f32,        // Output level (0.0 - 1.0), the modulation depth for modulators
+    pub envelope: Dahdsr,
+}
+
+impl FmOperator {
+    pub fn new(ratio: f32, level: f32, envelope: Dahdsr) -> Self {
+        Self {
+            ratio,
+            level,
+            envelope,
+        }
+    }
+}
+
+/// Factory sound an FM patch started from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FmPreset {
+    ElectricPiano,
+    Bell,
+    Bass,
+}
+
+impl fmt::Display for FmPreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FmPreset::ElectricPiano => write!(f, "E-Piano"),
+            FmPreset::Bell => write!(f, "Bell"),
+            FmPreset::Bass => write!(f, "Bass"),
+        }
+    }
+}
+
+/// Setting of a patch that the FM controls can edit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FmParameter {
+    Ratio,
+    Level,
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+    Feedback,  // Shared by the whole patch
+}
+
+impl FmParameter {
+    const ALL: [FmParameter; 7] = [
+        FmParameter::Ratio,
+        FmParameter::Level,
+        FmParameter::Attack,
+        FmParameter::Decay,
+        FmParameter::Sustain,
+        FmParameter::Release,
+        FmParameter::Feedback,
+    ];
+
+    /// The next (or previous) parameter, wrapping around
+    pub fn step(&self, forward: bool) -> FmParameter {
+        let index = Self::ALL.iter().position(|parameter| parameter == self).unwrap_or(0);
+        let count = Self::ALL.len();
+        Self::ALL[if forward { (index + 1) % count } else { (index + count - 1) % count }]
+    }
+}
+
+impl fmt::Display for FmParameter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FmParameter::Ratio => write!(f, "Ratio"),
+            FmParameter::Level => write!(f, "Level"),
+            FmParameter::Attack => write!(f, "Attack"),
+            FmParameter::Decay => write!(f, "Decay"),
+            FmParameter::Sustain => write!(f, "Sustain"),
+            FmParameter::Release => write!(f, "Release"),
+            FmParameter::Feedback => write!(f, "Feedback"),
+        }
+    }
+}
+
+/// Settings of an FM voice: its operators, how they connect and the feedback of the top operator
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FmPatch {
+    pub preset: FmPreset,
+    pub operators: [FmOperator; MAX_OPERATORS],
+    pub operator_count: usize,   // Active operators, MIN_OPERATORS to MAX_OPERATORS
+    pub algorithm: FmAlgorithm,
+    pub feedback: f32,           // Self-modulation of the top operator (0.0 - 1.0)
+}
+
+impl FmPatch {
+    pub fn preset(preset: FmPreset) -> Self {
+        let decaying = |decay: f32, sustain: f32, release: f32| Dahdsr {
+            decay_curve: EnvelopeCurve::Exponential,
+            release_curve: EnvelopeCurve::Exponential,
+            ..Dahdsr::adsr(0.002, decay, sustain, release)
+        };
+
+        match preset {
+            // Two stacks: a soft fundamental and a bright tine that fades quickly
+            FmPreset::ElectricPiano => Self {
+                preset,
+                operators: [
+                    FmOperator::new(1.0, 1.0, decaying(2.5, 0.3, 0.4)),
+                    FmOperator::new(1.0, 0.35, decaying(1.5, 0.2, 0.4)),
+                    FmOperator::new(1.0, 0.5, decaying(0.8, 0.0, 0.3)),
+                    FmOperator::new(14.0, 0.25, decaying(0.15, 0.0, 0.1)),
+                ],
+                operator_count: 4,
+                algorithm: FmAlgorithm::TwoStacks,
+                feedback: 0.0,
+            },
+            // Inharmonic ratios give the metallic partials, everything rings out slowly
+            FmPreset::Bell => Self {
+                preset,
+                operators: [
+                    FmOperator::new(1.0, 1.0, decaying(4.0, 0.0, 2.0)),
+                    FmOperator::new(3.5, 0.6, decaying(2.5, 0.0, 1.5)),
+                    FmOperator::new(2.0, 0.5, decaying(3.0, 0.0, 2.0)),
+                    FmOperator::new(5.19, 0.3, decaying(1.0, 0.0, 1.0)),
+                ],
+                operator_count: 4,
+                algorithm: FmAlgorithm::TwoStacks,
+                feedback: 0.0,
+            },
+            // A short modulator burst with feedback makes the pluck, the sub octave the weight
+            FmPreset::Bass => Self {
+                preset,
+                operators: [
+                    FmOperator::new(0.5, 1.0, decaying(1.2, 0.6, 0.15)),
+                    FmOperator::new(0.5, 0.7, decaying(0.3, 0.25, 0.15)),
+                    FmOperator::new(1.0, 0.4, decaying(0.12, 0.1, 0.1)),
+                    FmOperator::new(1.0, 0.0, Dahdsr::adsr(0.0, 0.0, 1.0, 0.0)),
+                ],
+                operator_count: 3,
+                algorithm: FmAlgorithm::Stack,
+                feedback: 0.3,
+            },
+        }
+    }
+
+    /// Value of a parameter of an operator (or of the patch, for feedback)
+    pub fn parameter(&self, operator: usize, parameter: FmParameter) -> f32 {
+        let operator = &self.operators[operator.min(MAX_OPERATORS - 1)];
+        match parameter {
+            FmParameter::Ratio => operator.ratio,
+            FmParameter::Level => operator.level,
+            FmParameter::Attack => operator.envelope.attack,
+            FmParameter::Decay => operator.envelope.decay,
+            FmParameter::Sustain => operator.envelope.sustain,
+            FmParameter::Release => operator.envelope.release,
+            FmParameter::Feedback => self.feedback,
+        }
+    }
+
+    /// Move a parameter one step up or down
+    ///
+    /// Ratios step by halves from 0.5 to 16, levels by 0.05, envelope times by
+    /// 0.05 seconds up to 4 seconds.
+    pub fn step_parameter(&mut self, operator: usize, parameter: FmParameter, up: bool) {
+        let direction = if up { 1.0 } else { -1.0 };
+        let operator = &mut self.operators[operator.min(MAX_OPERATORS - 1)];
+        let step = |value: f32, step: f32, min: f32, max: f32| ((value / step).round() * step + direction * step).clamp(min, max);
+        match parameter {
+            FmParameter::Ratio => operator.ratio = step(operator.ratio, 0.5, 0.5, 16.0),
+            FmParameter::Level => operator.level = step(operator.level, 0.05, 0.0, 1.0),
+            FmParameter::Attack => operator.envelope.attack = step(operator.envelope.attack, 0.05, 0.0, 4.0),
+            FmParameter::Decay => operator.envelope.decay = step(operator.envelope.decay, 0.05, 0.0, 4.0),
+            FmParameter::Sustain => operator.envelope.sustain = step(operator.envelope.sustain, 0.05, 0.0, 1.0),
+            FmParameter::Release => operator.envelope.release = step(operator.envelope.release, 0.05, 0.0, 4.0),
+            FmParameter::Feedback => self.feedback = step(self.feedback, 0.05, 0.0, 1.0),
+        }
+    }
+
+    /// Use one more operator, wrapping from the maximum back to the minimum
+    pub fn cycle_operator_count(&mut self) {
+        self.operator_count = if self.operator_count >= MAX_OPERATORS { MIN_OPERATORS } else { self.operator_count + 1 };
+    }
+
+    /// Number of active carriers, at least one
+    fn carrier_count(&self) -> usize {
+        (0..self.operator_count).filter(|&op| self.algorithm.is_carrier(op)).count().max(1)
+    }
+
+    /// Run the operators for one sample given their phases and envelope levels
+    ///
+    /// `feedback_input` is the recent output of the top operator.
+    fn render(&self, phases: &[f32; MAX_OPERATORS], levels: &[f32; MAX_OPERATORS], feedback_input: f32) -> (f32, f32) {
+        let count = self.operator_count.clamp(MIN_OPERATORS, MAX_OPERATORS);
+        let top = count - 1;
+        let mut outputs = [0.0; MAX_OPERATORS];
+
+        // Modulators have higher numbers, so they are ready before the operators they modulate
+        for op in (0..count).rev() {
+            let mut modulation: f32 = self.algorithm.modulators(op).iter()
+                .filter(|&&modulator| modulator < count)
+                .map(|&modulator| outputs[modulator])
+                .sum();
+            if op == top {
+                modulation += feedback_input * self.feedback;
+            }
+            let phase = phases[op] + modulation * MODULATION_INDEX / (2.0 * PI);
+            outputs[op] = sine_at(phase) * self.operators[op].level * levels[op];
+        }
+
+        let carriers: f32 = (0..count)
+            .filter(|&op| self.algorithm.is_carrier(op))
+            .map(|op| outputs[op])
+            .sum();
+        (carriers / self.carrier_count() as f32, outputs[top])
+    }
+}
+
+/// Calculate the patch at a sample number with every envelope at its peak, for the waveform display
+///
+/// The feedback loop is approximated by feeding the top operator's plain sine back once.
+pub fn calculate_fm(patch: &FmPatch, frequency: f32, num_sample: usize) -> f32 {
+    let mut phases = [0.0; MAX_OPERATORS];
+    for (phase, operator) in phases.iter_mut().zip(&patch.operators) {
+        *phase = phase_at_sample(frequency * operator.ratio, num_sample);
+    }
+    let top = patch.operator_count.clamp(MIN_OPERATORS, MAX_OPERATORS) - 1;
+    let feedback_input = sine_at(phases[top]) * patch.operators[top].level;
+    patch.render(&phases, &[1.0; MAX_OPERATORS], feedback_input).0
+}
+
+/// Voice playing an FM patch
+///
+/// The operator envelopes release when the note's gate closes, independently of the
+/// track's amplitude envelope that shapes the voice as a whole.
+pub struct FmVoice {
+    patch: FmPatch,
+    frequency: f32,
+    sample_rate: f32,
+    phases: [f64; MAX_OPERATORS],
+    envelopes: Vec<EnvelopeGenerator>,
+    gate: EnvelopeGate,
+    released: bool,
+    feedback_history: [f32; 2],  // Last two outputs of the top operator, averaged against ringing
+}
+
+impl FmVoice {
+    pub fn new(patch: FmPatch, frequency: f32, sample_rate: u32, gate: EnvelopeGate) -> Self {
+        let envelopes = patch.operators.iter()
+            .map(|operator| EnvelopeGenerator::new(operator.envelope.shape(), sample_rate))
+            .collect();
+
+        Self {
+            patch,
+            frequency,
+            sample_rate: sample_rate as f32,
+            phases: [0.0; MAX_OPERATORS],
+            envelopes,
+            gate,
+            released: false,
+            feedback_history: [0.0; 2],
+        }
+    }
+
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.frequency = frequency;
+    }
+
+    /// Set the start phase (0 to 1) of the note, each operator starting at the same point of its cycle
+    pub fn set_phase(&mut self, phase: f32) {
+        self.phases = [(phase as f64).rem_euclid(1.0); MAX_OPERATORS];
+    }
+
+    pub fn next_sample(&mut self) -> f32 {
+        if !self.released && !self.gate.is_open() {
+            for envelope in &mut self.envelopes {
+                envelope.release();
+            }
+            self.released = true;
+        }
+
+        let mut phases = [0.0; MAX_OPERATORS];
+        let mut levels = [0.0; MAX_OPERATORS];
+        for op in 0..MAX_OPERATORS {
+            phases[op] = self.phases[op] as f32;
+            levels[op] = self.envelopes[op].next_level();
+
+            let increment = (self.frequency * self.patch.operators[op].ratio / self.sample_rate) as f64;
+            self.phases[op] = (self.phases[op] + increment).rem_euclid(1.0);
+        }
+
+        let feedback_input = (self.feedback_history[0] + self.feedback_history[1]) * 0.5;
+        let (output, top_output) = self.patch.render(&phases, &levels, feedback_input);
+        self.feedback_history = [top_output, self.feedback_history[0]];
+        output
+    }
+}
+
+/// Implementation of the [Iterator] trait for the [FmVoice]
+impl Iterator for FmVoice {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        Some(self.next_sample())
+    }
+}
+
+/// Implementation of the [Source] trait for the [FmVoice]
+impl Source for FmVoice {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        MONO
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate as u32
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
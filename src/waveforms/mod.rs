@@ -1,12 +1,24 @@
 use std::fmt;
+use crate::waveforms::noise::NoiseColor;
 
+pub mod oscillator;
 pub mod sine_wave;
 pub mod square_wave;
 pub mod triangle_wave;
 pub mod sawtooth_wave;
+pub mod polyblep;
 pub mod adsr_envelope;
+pub mod breakpoint_envelope;
+pub mod envelope_curve;
+pub mod random;
+pub mod noise;
+pub mod drum_voice;
+pub mod sampler;
+pub mod fm;
+pub mod wavetable;
 
 pub const MONO: u16 = 1;
+pub const STEREO: u16 = 2;
 pub const SAMPLE_RATE: f32 = 48000.0;
 pub const AMPLITUDE: f32 = 0.20;
 pub const DURATION: f32 = 0.19;
@@ -16,15 +28,10 @@ pub enum Waveform {
     SINE,
     SQUARE,
     TRIANGLE,
-    SAWTOOTH
-}
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum WaveformType {
-    Sine,
-    Square,
-    Triangle,
-    Sawtooth,
+    SAWTOOTH,
+    NOISE(NoiseColor),
+    FM,
+    WAVETABLE
 }
 
 /// Implements the [Display] trait for [WaveForm]
@@ -34,7 +41,10 @@ impl fmt::Display for Waveform {
             Waveform::SINE => write!(f, "Sine"),
             Waveform::SQUARE => write!(f, "Square"),
             Waveform::TRIANGLE => write!(f, "Triangle"),
-            Waveform::SAWTOOTH => write!(f, "Sawtooth")
+            Waveform::SAWTOOTH => write!(f, "Sawtooth"),
+            Waveform::NOISE(color) => write!(f, "{} Noise", color),
+            Waveform::FM => write!(f, "FM"),
+            Waveform::WAVETABLE => write!(f, "Wavetable")
         }
     }
 }
\ No newline at end of file
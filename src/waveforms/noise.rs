@@ -0,0 +1,102 @@
+use rodio::Source;
+use std::fmt;
+use std::time::Duration;
+use crate::waveforms::random::Random;
+use crate::waveforms::MONO;
+
+/// Spectral color of a noise source
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoiseColor {
+    White,  // Equal energy per frequency
+    Pink,   // Equal energy per octave, -3 dB per octave
+    Brown,  // Integrated white noise, -6 dB per octave
+}
+
+impl fmt::Display for NoiseColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NoiseColor::White => write!(f, "White"),
+            NoiseColor::Pink => write!(f, "Pink"),
+            NoiseColor::Brown => write!(f, "Brown"),
+        }
+    }
+}
+
+/// White, pink or brown noise generator
+///
+/// Pink noise uses Paul Kellet's filter bank over white noise, brown noise a leaky
+/// integrator. Both are scaled to peak near full scale, like white noise.
+#[derive(Debug, Clone)]
+pub struct NoiseGenerator {
+    color: NoiseColor,
+    random: Random,
+    pink_state: [f32; 7],
+    brown_state: f32,
+    sample_rate: u32,
+}
+
+impl NoiseGenerator {
+    pub fn new(color: NoiseColor, seed: u32, sample_rate: u32) -> Self {
+        Self {
+            color,
+            random: Random::new(seed),
+            pink_state: [0.0; 7],
+            brown_state: 0.0,
+            sample_rate,
+        }
+    }
+
+    /// Produce the next sample (about -1.0 to 1.0)
+    pub fn next_sample(&mut self) -> f32 {
+        let white = self.random.next_bipolar();
+
+        match self.color {
+            NoiseColor::White => white,
+            NoiseColor::Pink => {
+                let b = &mut self.pink_state;
+                b[0] = 0.99886 * b[0] + white * 0.0555179;
+                b[1] = 0.99332 * b[1] + white * 0.0750759;
+                b[2] = 0.96900 * b[2] + white * 0.153852;
+                b[3] = 0.86650 * b[3] + white * 0.3104856;
+                b[4] = 0.55000 * b[4] + white * 0.5329522;
+                b[5] = -0.7616 * b[5] - white * 0.0168980;
+                let pink = b[0] + b[1] + b[2] + b[3] + b[4] + b[5] + b[6] + white * 0.5362;
+                b[6] = white * 0.115926;
+                pink * 0.11
+            }
+            NoiseColor::Brown => {
+                self.brown_state = (self.brown_state + 0.02 * white) / 1.02;
+                self.brown_state * 3.5
+            }
+        }
+    }
+}
+
+/// Implementation of the [Iterator] trait for the [NoiseGenerator]
+impl Iterator for NoiseGenerator {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        Some(self.next_sample())
+    }
+}
+
+/// Implementation of the [Source] trait for the [NoiseGenerator]
+impl Source for NoiseGenerator {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        MONO
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
@@ -0,0 +1,112 @@
+use crate::waveforms::SAMPLE_RATE;
+
+/// Running phase of an oscillator, kept in the 0 to 1 range.
+/// Advancing by a per-sample increment instead of deriving the phase from a sample counter
+/// keeps precision on long notes and lets the frequency change without a jump in phase.
+#[derive(Debug, Clone)]
+pub struct PhaseAccumulator {
+    phase: f64,
+    phase_increment: f64,
+    frequency: f32,
+    sample_rate: f32,
+}
+
+impl PhaseAccumulator {
+    pub fn new(frequency: f32, sample_rate: f32) -> Self {
+        let mut accumulator = Self {
+            phase: 0.0,
+            phase_increment: 0.0,
+            frequency: 0.0,
+            sample_rate,
+        };
+        accumulator.set_frequency(frequency);
+        accumulator
+    }
+
+    /// Change the frequency, continuing from the current phase
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.frequency = frequency;
+        self.phase_increment = frequency as f64 / self.sample_rate as f64;
+    }
+
+    pub fn frequency(&self) -> f32 {
+        self.frequency
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Change the sample rate, keeping the frequency
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.set_frequency(self.frequency);
+    }
+
+    /// Current position within the period (0 to 1)
+    pub fn phase(&self) -> f32 {
+        self.phase as f32
+    }
+
+    /// Phase advance per sample (frequency / sample rate)
+    pub fn phase_increment(&self) -> f32 {
+        self.phase_increment as f32
+    }
+
+    /// Jump to a position within the period, wrapped into the 0 to 1 range
+    pub fn set_phase(&mut self, phase: f32) {
+        self.phase = (phase as f64).rem_euclid(1.0);
+    }
+
+    /// Restart the period from phase 0
+    pub fn reset(&mut self) {
+        self.phase = 0.0;
+    }
+
+    /// Return the current phase and increment, then move on by one sample
+    pub fn advance(&mut self) -> (f32, f32) {
+        let current = (self.phase as f32, self.phase_increment as f32);
+        self.phase = (self.phase + self.phase_increment).rem_euclid(1.0);
+        current
+    }
+}
+
+/// Common core of the waveform generators: a phase accumulator plus a shape evaluated at a phase
+pub trait Oscillator {
+    fn accumulator(&self) -> &PhaseAccumulator;
+
+    fn accumulator_mut(&mut self) -> &mut PhaseAccumulator;
+
+    /// Waveform value (-1.0 to 1.0) at a phase (0 to 1) given the per-sample phase increment
+    fn value_at(&self, phase: f32, phase_increment: f32) -> f32;
+
+    /// Produce the next sample and advance the phase
+    fn next_sample(&mut self) -> f32 {
+        let (phase, phase_increment) = self.accumulator_mut().advance();
+        self.value_at(phase, phase_increment)
+    }
+
+    /// Change the frequency; may be called between any two samples
+    fn set_frequency(&mut self, frequency: f32) {
+        self.accumulator_mut().set_frequency(frequency);
+    }
+
+    fn frequency(&self) -> f32 {
+        self.accumulator().frequency()
+    }
+
+    /// Set the start phase (0 to 1)
+    fn set_phase(&mut self, phase: f32) {
+        self.accumulator_mut().set_phase(phase);
+    }
+
+    fn reset_phase(&mut self) {
+        self.accumulator_mut().reset();
+    }
+}
+
+/// Position within the current period (0 to 1) of a wave at the given sample number.
+/// Used by the waveform display, which jumps between arbitrary sample positions.
+pub fn phase_at_sample(frequency: f32, num_sample: usize) -> f32 {
+    (num_sample as f64 * frequency as f64 / SAMPLE_RATE as f64).rem_euclid(1.0) as f32
+}
@@ -0,0 +1,57 @@
+use crate::waveforms::{
+    oscillator::phase_at_sample,
+    square_wave::square_at,
+    sawtooth_wave::sawtooth_at,
+    SAMPLE_RATE
+};
+
+/// Polynomial band-limited step (PolyBLEP) residual.
+/// Subtracting it around a discontinuity smooths the jump over two samples,
+/// removing most of the aliasing a naive hard edge produces.
+///
+/// * `phase` - Position within the current period (0 to 1)
+/// * `phase_increment` - Phase advance per sample (frequency / sample rate)
+pub fn poly_blep(phase: f32, phase_increment: f32) -> f32 {
+    if phase_increment <= 0.0 {
+        return 0.0;
+    }
+
+    if phase < phase_increment {
+        // Just after the discontinuity
+        let t = phase / phase_increment;
+        t + t - t * t - 1.0
+    } else if phase > 1.0 - phase_increment {
+        // Just before the discontinuity
+        let t = (phase - 1.0) / phase_increment;
+        t * t + t + t + 1.0
+    } else {
+        0.0
+    }
+}
+
+/// Band-limited square wave: +1 for the first half of the period, -1 for the second,
+/// with both edges corrected by PolyBLEP.
+pub fn calculate_polyblep_square(frequency: f32, num_sample: usize) -> f32 {
+    polyblep_square(phase_at_sample(frequency, num_sample), frequency / SAMPLE_RATE)
+}
+
+/// Band-limited sawtooth wave rising from -1 to 1, with the falling edge corrected by PolyBLEP.
+pub fn calculate_polyblep_sawtooth(frequency: f32, num_sample: usize) -> f32 {
+    polyblep_sawtooth(phase_at_sample(frequency, num_sample), frequency / SAMPLE_RATE)
+}
+
+/// Band-limited square value for a phase (0 to 1) and phase increment
+pub fn polyblep_square(phase: f32, phase_increment: f32) -> f32 {
+    let naive = square_at(phase);
+
+    // Rising edge at phase 0, falling edge at phase 0.5
+    naive + poly_blep(phase, phase_increment) - poly_blep((phase + 0.5) % 1.0, phase_increment)
+}
+
+/// Band-limited sawtooth value for a phase (0 to 1) and phase increment
+pub fn polyblep_sawtooth(phase: f32, phase_increment: f32) -> f32 {
+    let naive = sawtooth_at(phase);
+
+    // Falling edge at phase 0
+    naive - poly_blep(phase, phase_increment)
+}
@@ -0,0 +1,33 @@
+/// Small xorshift pseudo-random generator for noise and random modulation
+///
+/// Deterministic for a given seed, so offline renders of random sources are repeatable.
+#[derive(Debug, Clone)]
+pub struct Random {
+    state: u32,
+}
+
+impl Random {
+    pub fn new(seed: u32) -> Self {
+        // Xorshift never leaves the all-zero state
+        Self { state: seed.max(1) }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    /// Uniform value from 0.0 up to 1.0
+    pub fn next_unit(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    /// Uniform value from -1.0 up to 1.0
+    pub fn next_bipolar(&mut self) -> f32 {
+        self.next_unit() * 2.0 - 1.0
+    }
+}
@@ -0,0 +1,360 @@
+use rodio::Source;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::audio::wav::{read_wav_file, wav_files_in};
+use crate::midi::{midi_number_to_note, note_to_midi_number};
+use crate::modulation::matrix::PITCH_RANGE_SEMITONES;
+use crate::music_theory::note::Note;
+use crate::music_theory::OCTAVE_LOWER_BOUND;
+use crate::waveforms::breakpoint_envelope::{Breakpoint, EnvelopeShape};
+use crate::waveforms::envelope_curve::EnvelopeCurve;
+use crate::waveforms::MONO;
+
+/// Root key of samples that name none, middle C
+pub const DEFAULT_ROOT_KEY: u8 = 60;
+
+/// Directory holding each track's samples, in a subdirectory named after the track
+pub const SAMPLES_DIRECTORY: &str = "samples";
+
+/// Cubic Hermite interpolation of `samples` at a fractional position, holding the edge samples
+fn hermite(samples: &[f32], position: f64) -> f32 {
+    let last = samples.len() as isize - 1;
+    if last < 0 {
+        return 0.0;
+    }
+
+    let index = position.floor() as isize;
+    let t = (position - index as f64) as f32;
+    let at = |i: isize| samples[i.clamp(0, last) as usize];
+    let (y0, y1, y2, y3) = (at(index - 1), at(index), at(index + 1), at(index + 2));
+
+    let c1 = 0.5 * (y2 - y0);
+    let c2 = y0 - 2.5 * y1 + 2.0 * y2 - 0.5 * y3;
+    let c3 = 0.5 * (y3 - y0) + 1.5 * (y1 - y2);
+    ((c3 * t + c2) * t + c1) * t + y1
+}
+
+/// Resample mono audio from one sample rate to another
+pub fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let step = from_rate as f64 / to_rate as f64;
+    let length = (samples.len() as f64 / step).round() as usize;
+    (0..length).map(|i| hermite(samples, i as f64 * step)).collect()
+}
+
+/// Root key written at the end of a file name, as in "kick_C4.wav" or "pad-F#3.wav"
+fn root_key_from_name(name: &str) -> Option<u8> {
+    let token = name.rsplit(['_', '-', ' ']).next()?;
+    let split = token.find(|c: char| c.is_ascii_digit() || c == '-')?;
+    let (note_name, octave) = token.split_at(split);
+    let note = Note::from_str(&note_name.to_uppercase()).ok()?;
+    let octave: i32 = octave.parse().ok()?;
+    (-1..=9).contains(&octave).then(|| note_to_midi_number(note, octave))
+}
+
+/// Point of a sample zone that can be moved while editing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SamplePoint {
+    Start,
+    End,
+    LoopStart,
+    LoopEnd,
+}
+
+impl SamplePoint {
+    pub fn next(self) -> Self {
+        match self {
+            SamplePoint::Start => SamplePoint::End,
+            SamplePoint::End => SamplePoint::LoopStart,
+            SamplePoint::LoopStart => SamplePoint::LoopEnd,
+            SamplePoint::LoopEnd => SamplePoint::Start,
+        }
+    }
+
+    pub fn previous(self) -> Self {
+        match self {
+            SamplePoint::Start => SamplePoint::LoopEnd,
+            SamplePoint::End => SamplePoint::Start,
+            SamplePoint::LoopStart => SamplePoint::End,
+            SamplePoint::LoopEnd => SamplePoint::LoopStart,
+        }
+    }
+}
+
+impl fmt::Display for SamplePoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SamplePoint::Start => write!(f, "start"),
+            SamplePoint::End => write!(f, "end"),
+            SamplePoint::LoopStart => write!(f, "loop start"),
+            SamplePoint::LoopEnd => write!(f, "loop end"),
+        }
+    }
+}
+
+/// One sample of a track's map, played around its root key
+///
+/// The audio is kept at its own rate and a copy is resampled to the engine rate. Start,
+/// end and loop points are in frames of the original audio.
+#[derive(Debug, Clone)]
+pub struct SampleZone {
+    pub name: String,
+    pub root_key: u8,                         // MIDI note the sample plays unshifted
+    pub start: usize,
+    pub end: usize,                           // Exclusive
+    pub loop_points: Option<(usize, usize)>,  // Start and exclusive end of the sustain loop
+    source: Arc<Vec<f32>>,                    // Mono audio at the file's rate
+    source_rate: u32,
+    data: Arc<Vec<f32>>,                      // Mono audio at the engine rate
+    sample_rate: u32,
+}
+
+impl SampleZone {
+    pub fn new(name: String, samples: Vec<f32>, source_rate: u32, root_key: u8, sample_rate: u32) -> Self {
+        let data = resample(&samples, source_rate, sample_rate);
+        Self {
+            name,
+            root_key: root_key.min(127),
+            start: 0,
+            end: samples.len(),
+            loop_points: None,
+            source: Arc::new(samples),
+            source_rate,
+            data: Arc::new(data),
+            sample_rate,
+        }
+    }
+
+    /// Load a WAV file, taking the root key from the file name, its 'smpl' chunk or middle C
+    pub fn load(path: &Path, sample_rate: u32) -> Result<Self, Box<dyn std::error::Error>> {
+        let wav = read_wav_file(&path.to_string_lossy())?;
+        let name = path.file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+        let root_key = root_key_from_name(&name).or(wav.root_key).unwrap_or(DEFAULT_ROOT_KEY);
+
+        let mut zone = Self::new(name, wav.to_mono(), wav.sample_rate, root_key, sample_rate);
+        zone.loop_points = wav.loop_points.filter(|&(start, end)| end <= zone.end && start < end);
+        Ok(zone)
+    }
+
+    /// Resample the audio for a new engine rate
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        if sample_rate != self.sample_rate {
+            self.data = Arc::new(resample(&self.source, self.source_rate, sample_rate));
+            self.sample_rate = sample_rate;
+        }
+    }
+
+    /// Set the part of the sample that plays, keeping the loop inside it
+    pub fn set_range(&mut self, start: usize, end: usize) {
+        self.end = end.min(self.source.len());
+        self.start = start.min(self.end);
+        self.loop_points = self.loop_points
+            .map(|(loop_start, loop_end)| (loop_start.max(self.start), loop_end.min(self.end)))
+            .filter(|(loop_start, loop_end)| loop_start < loop_end);
+    }
+
+    pub fn set_loop_points(&mut self, start: usize, end: usize) {
+        let (start, end) = (start.max(self.start), end.min(self.end));
+        self.loop_points = (start < end).then_some((start, end));
+    }
+
+    /// Frame a point is at, an unset loop covering the whole range
+    pub fn point(&self, point: SamplePoint) -> usize {
+        let (loop_start, loop_end) = self.loop_points.unwrap_or((self.start, self.end));
+        match point {
+            SamplePoint::Start => self.start,
+            SamplePoint::End => self.end,
+            SamplePoint::LoopStart => loop_start,
+            SamplePoint::LoopEnd => loop_end,
+        }
+    }
+
+    /// Move a point by a number of frames, never past the point it pairs with
+    pub fn move_point(&mut self, point: SamplePoint, frames: isize) {
+        let frame = self.point(point).saturating_add_signed(frames);
+        let (loop_start, loop_end) = self.loop_points.unwrap_or((self.start, self.end));
+        match point {
+            SamplePoint::Start => self.set_range(frame.min(self.end.saturating_sub(1)), self.end),
+            SamplePoint::End => self.set_range(self.start, frame.max(self.start + 1)),
+            SamplePoint::LoopStart => self.set_loop_points(frame.min(loop_end.saturating_sub(1)), loop_end),
+            SamplePoint::LoopEnd => self.set_loop_points(loop_start, frame.max(loop_start + 1)),
+        }
+    }
+
+    /// Length of the whole original audio in frames
+    pub fn frames(&self) -> usize {
+        self.source.len()
+    }
+
+    /// Length of the played range in seconds
+    pub fn duration(&self) -> f32 {
+        (self.end - self.start) as f32 / self.source_rate as f32
+    }
+
+    /// Frequency the sample plays at without pitch shifting
+    pub fn root_frequency(&self) -> f32 {
+        let (note, octave) = midi_number_to_note(self.root_key);
+        note.frequency(octave)
+    }
+
+    /// Position in the resampled audio of a frame of the original
+    fn engine_position(&self, frame: usize) -> f64 {
+        frame as f64 * self.sample_rate as f64 / self.source_rate as f64
+    }
+}
+
+/// Samples of a track, each covering the notes closest to its root key
+#[derive(Debug, Clone)]
+pub struct SampleMap {
+    pub zones: Vec<SampleZone>,
+    pub looping: bool,  // Loop the zones, over their loop points or their whole range, instead of playing once
+}
+
+impl SampleMap {
+    pub fn new(zones: Vec<SampleZone>) -> Self {
+        Self {
+            zones,
+            looping: false,
+        }
+    }
+
+    /// Load every WAV file of a directory, in file name order
+    pub fn load_directory(path: &Path, sample_rate: u32) -> Result<Self, Box<dyn std::error::Error>> {
+        let paths = wav_files_in(path)?;
+        if paths.is_empty() {
+            return Err(format!("No WAV files in {}", path.display()).into());
+        }
+
+        let zones = paths.iter()
+            .map(|path| SampleZone::load(path, sample_rate)
+                .map_err(|e| format!("{}: {}", path.display(), e).into()))
+            .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
+        Ok(Self::new(zones))
+    }
+
+    /// Directory a track's samples are loaded from
+    pub fn directory_for_track(track_name: &str) -> String {
+        format!("{}/{}", SAMPLES_DIRECTORY, track_name.to_lowercase())
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        for zone in &mut self.zones {
+            zone.set_sample_rate(sample_rate);
+        }
+    }
+
+    /// Zone whose root key is closest to a note, the lower one on a tie
+    pub fn zone_for(&self, note: Note, octave: i32) -> Option<&SampleZone> {
+        let key = note_to_midi_number(note, octave) as i32;
+        self.zones.iter().min_by_key(|zone| ((zone.root_key as i32 - key).abs(), zone.root_key))
+    }
+
+    /// Player for a note, pitched from the zone's root key to `frequency`
+    pub fn player(&self, note: Note, octave: i32, frequency: f32) -> Option<SamplePlayer> {
+        self.zone_for(note, octave).map(|zone| SamplePlayer::new(zone, frequency, self.looping))
+    }
+
+    /// Flat envelope outlasting the longest zone at its slowest playback, so one-shots play out in full
+    ///
+    /// Notes below a zone's root key read it slower than its duration, down to the lowest key
+    /// with a pitch route pulling it a full range further down. The voice ends with the sample,
+    /// so the envelope only has to outlast it.
+    pub fn one_shot_envelope(&self) -> EnvelopeShape {
+        let lowest_frequency = Note::C.frequency(OCTAVE_LOWER_BOUND) * 2.0f32.powf(-PITCH_RANGE_SEMITONES / 12.0);
+        let length = self.zones.iter()
+            .map(|zone| zone.duration() * (zone.root_frequency() / lowest_frequency).max(1.0))
+            .fold(0.0, f32::max);
+        EnvelopeShape::new(
+            vec![
+                Breakpoint::new(1.0, 0.0, EnvelopeCurve::Linear),
+                Breakpoint::new(1.0, length, EnvelopeCurve::Linear),
+            ],
+            None,
+            None,
+        )
+    }
+}
+
+/// Voice playing a sample zone, pitch shifted by reading it faster or slower
+#[derive(Debug, Clone)]
+pub struct SamplePlayer {
+    data: Arc<Vec<f32>>,
+    sample_rate: u32,
+    position: f64,                    // Read position in the resampled audio
+    increment: f64,                   // Read speed, 1.0 plays at the root key
+    root_frequency: f32,
+    end: f64,
+    loop_range: Option<(f64, f64)>,
+}
+
+impl SamplePlayer {
+    pub fn new(zone: &SampleZone, frequency: f32, looping: bool) -> Self {
+        let end = zone.engine_position(zone.end);
+        let loop_range = if looping {
+            let (start, end) = zone.loop_points.unwrap_or((zone.start, zone.end));
+            Some((zone.engine_position(start), zone.engine_position(end)))
+        } else {
+            None
+        };
+
+        let mut player = Self {
+            data: Arc::clone(&zone.data),
+            sample_rate: zone.sample_rate,
+            position: zone.engine_position(zone.start),
+            increment: 1.0,
+            root_frequency: zone.root_frequency(),
+            end,
+            loop_range,
+        };
+        player.set_frequency(frequency);
+        player
+    }
+
+    /// Retune the playback relative to the zone's root key
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.increment = (frequency / self.root_frequency).max(0.0) as f64;
+    }
+}
+
+/// Implementation of the [Iterator] trait for the [SamplePlayer]
+impl Iterator for SamplePlayer {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if let Some((loop_start, loop_end)) = self.loop_range {
+            if self.position >= loop_end && loop_end > loop_start {
+                self.position = loop_start + (self.position - loop_end) % (loop_end - loop_start);
+            }
+        } else if self.position >= self.end {
+            return None;
+        }
+
+        let sample = hermite(&self.data, self.position);
+        self.position += self.increment;
+        Some(sample)
+    }
+}
+
+/// Implementation of the [Source] trait for the [SamplePlayer]
+impl Source for SamplePlayer {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        MONO
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
@@ -1,21 +1,50 @@
 use rodio::Source;
 use std::time::Duration;
 use crate::{
-    waveforms::{MONO, SAMPLE_RATE}
-};
+    waveforms::{
+        oscillator::{phase_at_sample, Oscillator, PhaseAccumulator},
+        polyblep::polyblep_sawtooth,
+        MONO
+}};
 
 #[derive(Debug)]
 pub struct SawtoothWave {
-    freq: f32,
-    num_sample: usize
+    accumulator: PhaseAccumulator,
+    band_limited: bool
 }
 
 impl SawtoothWave {
-    pub fn new(freq: f32) -> SawtoothWave {
-        SawtoothWave { freq, num_sample: 0}
+    /// Band-limited sawtooth wave (PolyBLEP) that stays clean in the upper octaves
+    pub fn new(freq: f32, sample_rate: u32) -> SawtoothWave {
+        SawtoothWave { accumulator: PhaseAccumulator::new(freq, sample_rate as f32), band_limited: true }
+    }
+
+    /// Raw sawtooth wave with a hard reset for a lo-fi, aliased sound
+    pub fn new_raw(freq: f32, sample_rate: u32) -> SawtoothWave {
+        SawtoothWave { accumulator: PhaseAccumulator::new(freq, sample_rate as f32), band_limited: false }
     }
+
     pub fn generate_sawtooth_wave(&mut self) -> f32 {
-        calculate_sawtooth(self.freq, self.num_sample)
+        self.next_sample()
+    }
+}
+
+/// Implementation of the [Oscillator] trait for the [SawtoothWave]
+impl Oscillator for SawtoothWave {
+    fn accumulator(&self) -> &PhaseAccumulator {
+        &self.accumulator
+    }
+
+    fn accumulator_mut(&mut self) -> &mut PhaseAccumulator {
+        &mut self.accumulator
+    }
+
+    fn value_at(&self, phase: f32, phase_increment: f32) -> f32 {
+        if self.band_limited {
+            polyblep_sawtooth(phase, phase_increment)
+        } else {
+            sawtooth_at(phase)
+        }
     }
 }
 
@@ -23,9 +52,6 @@ impl SawtoothWave {
 impl Iterator for SawtoothWave {
     type Item = f32;
     fn next(&mut self) -> Option<f32> {
-        // increment sample counter by 1
-        self.num_sample = self.num_sample.wrapping_add(1);
-
         // Generates a sawtooth wave
         let sawtooth_wave = self.generate_sawtooth_wave();
 
@@ -44,7 +70,7 @@ impl Source for SawtoothWave {
     }
 
     fn sample_rate(&self) -> u32 {
-        SAMPLE_RATE as u32
+        self.accumulator.sample_rate() as u32
     }
 
     fn total_duration(&self) -> Option<Duration> {
@@ -52,17 +78,13 @@ impl Source for SawtoothWave {
     }
 }
 
-/// Calculates a sawtooth wave value for a given frequency and sample number.
+/// Sawtooth value at a phase (0 to 1).
 /// Sawtooth wave rises linearly from -1 to 1 then drops immediately back to -1.
-pub fn calculate_sawtooth(frequency: f32, num_sample: usize) -> f32 {
-    // Calculate time in seconds based on the sample number and the sample rate
-    let time: f32 = num_sample as f32 / SAMPLE_RATE;
-    // Calculate the period of the wave
-    let period: f32 = 1.0 / frequency;
-    
-    // Calculate position within the current period (0 to 1)
-    let phase = (time % period) / period;
-    
-    // Generate sawtooth wave: linear rise from -1 to 1
+pub fn sawtooth_at(phase: f32) -> f32 {
     2.0 * phase - 1.0
-}
\ No newline at end of file
+}
+
+/// Calculates a sawtooth wave value for a given frequency and sample number.
+pub fn calculate_sawtooth(frequency: f32, num_sample: usize) -> f32 {
+    sawtooth_at(phase_at_sample(frequency, num_sample))
+}
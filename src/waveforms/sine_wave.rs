@@ -1,21 +1,37 @@
 use rodio::Source;
 use std::{f32::consts::PI, time::Duration};
 use crate::{
-    waveforms::{MONO, SAMPLE_RATE}
-};
+    waveforms::{
+        oscillator::{phase_at_sample, Oscillator, PhaseAccumulator},
+        MONO
+}};
 
 #[derive(Debug)]
 pub struct SineWave {
-    freq: f32,
-    num_sample: usize
+    accumulator: PhaseAccumulator
 }
 
 impl SineWave {
-    pub fn new(freq: f32) -> SineWave {
-        SineWave { freq, num_sample: 0}
+    pub fn new(freq: f32, sample_rate: u32) -> SineWave {
+        SineWave { accumulator: PhaseAccumulator::new(freq, sample_rate as f32) }
     }
     pub fn generate_sine_wave(&mut self) -> f32 {
-        calculate_sine(self.freq, self.num_sample)
+        self.next_sample()
+    }
+}
+
+/// Implementation of the [Oscillator] trait for the [SineWave]
+impl Oscillator for SineWave {
+    fn accumulator(&self) -> &PhaseAccumulator {
+        &self.accumulator
+    }
+
+    fn accumulator_mut(&mut self) -> &mut PhaseAccumulator {
+        &mut self.accumulator
+    }
+
+    fn value_at(&self, phase: f32, _phase_increment: f32) -> f32 {
+        sine_at(phase)
     }
 }
 
@@ -23,9 +39,6 @@ impl SineWave {
 impl Iterator for SineWave {
     type Item = f32;
     fn next(&mut self) -> Option<f32> {
-        // increment sample counter by 1
-        self.num_sample = self.num_sample.wrapping_add(1);
-
         // Generates a sine wave
         let sine_wave = self.generate_sine_wave();
 
@@ -44,7 +57,7 @@ impl Source for SineWave {
     }
 
     fn sample_rate(&self) -> u32 {
-        SAMPLE_RATE as u32
+        self.accumulator.sample_rate() as u32
     }
 
     fn total_duration(&self) -> Option<Duration> {
@@ -52,15 +65,15 @@ impl Source for SineWave {
     }
 }
 
+/// Sine value at a phase (0 to 1), i.e. 'sin(2π * phase)'
+pub fn sine_at(phase: f32) -> f32 {
+    (2.0 * PI * phase).sin()
+}
+
 ///  The formula for calculating a sine wave is 'y(t) = sin(2πft)', whereby:
 /// '2πf' is two times pi the frequency (ie 2 * 3.14~ * 440 hz)
 /// 't' is time in seconds in relation to the sample rate (1/48k = 2.08333×10−5 seconds)
 pub fn calculate_sine(frequency: f32, num_sample: usize) -> f32 {
-    // Calculate time in seconds based on the sample number and the sample rate
-    let time: f32 = num_sample as f32 / SAMPLE_RATE;
-    // Calculate angular frequency (2πf)
-    let angular_frequency: f32 = 2.0 * PI * frequency;
-
-    // Calculate the sine wave value using the angular frequency and time
-    (angular_frequency * time).sin()
-}
\ No newline at end of file
+    // 'ft' wrapped to the current period keeps precision for large sample numbers
+    sine_at(phase_at_sample(frequency, num_sample))
+}
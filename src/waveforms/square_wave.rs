@@ -2,19 +2,45 @@ use rodio::Source;
 use std::time::Duration;
 use crate::{
     waveforms::{
-        sine_wave::calculate_sine,
-        MONO, SAMPLE_RATE
+        oscillator::{phase_at_sample, Oscillator, PhaseAccumulator},
+        polyblep::polyblep_square,
+        MONO
 }};
 
 #[derive(Debug)]
 pub struct SquareWave {
-    freq: f32,
-    num_sample: usize
+    accumulator: PhaseAccumulator,
+    band_limited: bool
 }
 
 impl SquareWave {
-    pub fn new(freq: f32) -> SquareWave {
-        SquareWave { freq, num_sample: 0 }
+    /// Band-limited square wave (PolyBLEP) that stays clean in the upper octaves
+    pub fn new(freq: f32, sample_rate: u32) -> SquareWave {
+        SquareWave { accumulator: PhaseAccumulator::new(freq, sample_rate as f32), band_limited: true }
+    }
+
+    /// Raw square wave with hard edges for a lo-fi, aliased sound
+    pub fn new_raw(freq: f32, sample_rate: u32) -> SquareWave {
+        SquareWave { accumulator: PhaseAccumulator::new(freq, sample_rate as f32), band_limited: false }
+    }
+}
+
+/// Implementation of the [Oscillator] trait for the [SquareWave]
+impl Oscillator for SquareWave {
+    fn accumulator(&self) -> &PhaseAccumulator {
+        &self.accumulator
+    }
+
+    fn accumulator_mut(&mut self) -> &mut PhaseAccumulator {
+        &mut self.accumulator
+    }
+
+    fn value_at(&self, phase: f32, phase_increment: f32) -> f32 {
+        if self.band_limited {
+            polyblep_square(phase, phase_increment)
+        } else {
+            square_at(phase)
+        }
     }
 }
 
@@ -22,16 +48,7 @@ impl SquareWave {
 impl Iterator for SquareWave {
     type Item = f32;
     fn next(&mut self) -> Option<f32> {
-        // increment sample counter by 1
-        self.num_sample = self.num_sample.wrapping_add(1);
-
-        // Generates a sine wave
-        let sine_wave: f32 = calculate_sine(self.freq, self.num_sample);
-
-        // Utilize a sign function to normalize our sine wave to [1.0 or -1.0]
-        let square_wave: f32 = sgn(sine_wave);
-
-        Some(square_wave)
+        Some(self.next_sample())
     }
 }
 
@@ -46,7 +63,7 @@ impl Source for SquareWave {
     }
 
     fn sample_rate(&self) -> u32 {
-        SAMPLE_RATE as u32
+        self.accumulator.sample_rate() as u32
     }
 
     fn total_duration(&self) -> Option<Duration> {
@@ -54,15 +71,13 @@ impl Source for SquareWave {
     }
 }
 
-/// Returns the sign of the given floating-point number.
-///
-/// The signum function returns:
-/// - 1.0 if the number is positive,
-/// - -1.0 if the number is negative,
-///
-/// # Arguments
-///
-/// * `x` - The floating-point number for which to determine the sign.
-fn sgn(x: f32) -> f32 {
-    x.signum()
-}
\ No newline at end of file
+/// Square value at a phase (0 to 1): the sign of the sine at the same phase,
+/// 1.0 for the first half of the period and -1.0 for the second.
+pub fn square_at(phase: f32) -> f32 {
+    if phase < 0.5 { 1.0 } else { -1.0 }
+}
+
+/// Calculates a raw square wave value for a given frequency and sample number.
+pub fn calculate_square(frequency: f32, num_sample: usize) -> f32 {
+    square_at(phase_at_sample(frequency, num_sample))
+}
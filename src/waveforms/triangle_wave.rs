@@ -1,21 +1,37 @@
 use rodio::Source;
 use std::time::Duration;
 use crate::{
-    waveforms::{MONO, SAMPLE_RATE}
-};
+    waveforms::{
+        oscillator::{phase_at_sample, Oscillator, PhaseAccumulator},
+        MONO
+}};
 
 #[derive(Debug)]
 pub struct TriangleWave {
-    freq: f32,
-    num_sample: usize
+    accumulator: PhaseAccumulator
 }
 
 impl TriangleWave {
-    pub fn new(freq: f32) -> TriangleWave {
-        TriangleWave { freq, num_sample: 0}
+    pub fn new(freq: f32, sample_rate: u32) -> TriangleWave {
+        TriangleWave { accumulator: PhaseAccumulator::new(freq, sample_rate as f32) }
     }
     pub fn generate_triangle_wave(&mut self) -> f32 {
-        calculate_triangle(self.freq, self.num_sample)
+        self.next_sample()
+    }
+}
+
+/// Implementation of the [Oscillator] trait for the [TriangleWave]
+impl Oscillator for TriangleWave {
+    fn accumulator(&self) -> &PhaseAccumulator {
+        &self.accumulator
+    }
+
+    fn accumulator_mut(&mut self) -> &mut PhaseAccumulator {
+        &mut self.accumulator
+    }
+
+    fn value_at(&self, phase: f32, _phase_increment: f32) -> f32 {
+        triangle_at(phase)
     }
 }
 
@@ -23,9 +39,6 @@ impl TriangleWave {
 impl Iterator for TriangleWave {
     type Item = f32;
     fn next(&mut self) -> Option<f32> {
-        // increment sample counter by 1
-        self.num_sample = self.num_sample.wrapping_add(1);
-
         // Generates a triangle wave
         let triangle_wave = self.generate_triangle_wave();
 
@@ -44,7 +57,7 @@ impl Source for TriangleWave {
     }
 
     fn sample_rate(&self) -> u32 {
-        SAMPLE_RATE as u32
+        self.accumulator.sample_rate() as u32
     }
 
     fn total_duration(&self) -> Option<Duration> {
@@ -52,21 +65,18 @@ impl Source for TriangleWave {
     }
 }
 
-/// Calculates a triangle wave value for a given frequency and sample number.
+/// Triangle value at a phase (0 to 1).
 /// Triangle wave oscillates linearly between -1 and 1, creating a triangular shape.
-pub fn calculate_triangle(frequency: f32, num_sample: usize) -> f32 {
-    // Calculate time in seconds based on the sample number and the sample rate
-    let time: f32 = num_sample as f32 / SAMPLE_RATE;
-    // Calculate the period of the wave
-    let period: f32 = 1.0 / frequency;
-    
-    // Calculate position within the current period (0 to 1)
-    let phase = (time % period) / period;
-    
+pub fn triangle_at(phase: f32) -> f32 {
     // Generate triangle wave: rises from -1 to 1 in first half, falls from 1 to -1 in second half
     if phase < 0.5 {
         4.0 * phase - 1.0  // Rising edge: -1 to 1
     } else {
         3.0 - 4.0 * phase  // Falling edge: 1 to -1
     }
-}
\ No newline at end of file
+}
+
+/// Calculates a triangle wave value for a given frequency and sample number.
+pub fn calculate_triangle(frequency: f32, num_sample: usize) -> f32 {
+    triangle_at(phase_at_sample(frequency, num_sample))
+}
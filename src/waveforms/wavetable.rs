@@ -0,0 +1,180 @@
+use rodio::Source;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::audio::wav::{read_wav_file, wav_files_in};
+use crate::waveforms::oscillator::{phase_at_sample, PhaseAccumulator};
+use crate::waveforms::sampler::resample;
+use crate::waveforms::sawtooth_wave::sawtooth_at;
+use crate::waveforms::sine_wave::sine_at;
+use crate::waveforms::square_wave::square_at;
+use crate::waveforms::triangle_wave::triangle_at;
+use crate::waveforms::MONO;
+
+/// Samples per frame, as in Serum's wavetables
+pub const FRAME_SIZE: usize = 2048;
+
+/// Directory the wavetable files are loaded from
+pub const WAVETABLES_DIRECTORY: &str = "wavetables";
+
+/// Name of the table built from the basic shapes
+pub const BASIC_SHAPES_NAME: &str = "Basic Shapes";
+
+/// Single-cycle frames an oscillator morphs between
+///
+/// The frames are shared between the voices playing the table. They are played as
+/// they are, without band-limiting, so bright frames alias on high notes.
+#[derive(Debug, Clone)]
+pub struct Wavetable {
+    pub name: String,
+    frames: Arc<Vec<Vec<f32>>>,
+}
+
+impl Wavetable {
+    pub fn new(name: String, frames: Vec<Vec<f32>>) -> Self {
+        Self {
+            name,
+            frames: Arc::new(frames),
+        }
+    }
+
+    /// Table morphing sine → triangle → sawtooth → square
+    pub fn from_shapes() -> Self {
+        let shapes: [fn(f32) -> f32; 4] = [sine_at, triangle_at, sawtooth_at, square_at];
+        let frames = shapes.iter()
+            .map(|shape| (0..FRAME_SIZE).map(|i| shape(i as f32 / FRAME_SIZE as f32)).collect())
+            .collect();
+        Self::new(BASIC_SHAPES_NAME.to_string(), frames)
+    }
+
+    /// Load a table from a WAV file of consecutive FRAME_SIZE-sample frames
+    ///
+    /// A file shorter than one frame is taken as a single cycle and stretched to a frame.
+    /// Samples after the last whole frame are ignored.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let samples = read_wav_file(&path.to_string_lossy())?.to_mono();
+        if samples.is_empty() {
+            return Err(format!("{} has no samples", path.display()).into());
+        }
+
+        let frames = if samples.len() < FRAME_SIZE {
+            let mut frame = resample(&samples, samples.len() as u32, FRAME_SIZE as u32);
+            frame.resize(FRAME_SIZE, 0.0);
+            vec![frame]
+        } else {
+            samples.chunks_exact(FRAME_SIZE).map(<[f32]>::to_vec).collect()
+        };
+
+        let name = path.file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+        Ok(Self::new(name, frames))
+    }
+
+    /// Table following this one in the cycle of the basic shapes and the files of a directory
+    ///
+    /// Comes back to the basic shapes after the last file, or stays there when the
+    /// directory has no WAV files.
+    pub fn next_in(&self, directory: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let paths = wav_files_in(directory).unwrap_or_default();
+        let current = paths.iter()
+            .position(|path| path.file_stem().is_some_and(|stem| stem.to_string_lossy() == self.name))
+            .map_or(0, |index| index + 1);
+
+        match paths.get(current) {
+            Some(path) => Self::load(path),
+            None => Ok(Self::from_shapes()),
+        }
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Value at a phase (0 to 1) and table position (0.0 first frame, 1.0 last frame)
+    ///
+    /// Interpolates linearly within the frames and between the two frames around the position.
+    pub fn value_at(&self, phase: f32, position: f32) -> f32 {
+        let frame_position = position.clamp(0.0, 1.0) * (self.frames.len() - 1) as f32;
+        let frame = frame_position.floor() as usize;
+        let blend = frame_position - frame as f32;
+
+        let index_position = phase.rem_euclid(1.0) * FRAME_SIZE as f32;
+        let index = index_position as usize % FRAME_SIZE;
+        let fraction = index_position - index_position.floor();
+        let sample = |frame: &Vec<f32>| frame[index] + (frame[(index + 1) % FRAME_SIZE] - frame[index]) * fraction;
+
+        let current = sample(&self.frames[frame]);
+        match self.frames.get(frame + 1) {
+            Some(next) if blend > 0.0 => current + (sample(next) - current) * blend,
+            _ => current,
+        }
+    }
+}
+
+/// Calculate a wavetable at a sample number, for the waveform display
+pub fn calculate_wavetable(wavetable: &Wavetable, position: f32, frequency: f32, num_sample: usize) -> f32 {
+    wavetable.value_at(phase_at_sample(frequency, num_sample), position)
+}
+
+/// Oscillator reading a wavetable at a position that can move while it plays
+pub struct WavetableOscillator {
+    accumulator: PhaseAccumulator,
+    wavetable: Wavetable,
+    position: f32,
+}
+
+impl WavetableOscillator {
+    pub fn new(wavetable: Wavetable, position: f32, frequency: f32, sample_rate: u32) -> Self {
+        Self {
+            accumulator: PhaseAccumulator::new(frequency, sample_rate as f32),
+            wavetable,
+            position: position.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.accumulator.set_frequency(frequency);
+    }
+
+    /// Set the start phase (0 to 1)
+    pub fn set_phase(&mut self, phase: f32) {
+        self.accumulator.set_phase(phase);
+    }
+
+    /// Move to another table position (0.0 - 1.0)
+    pub fn set_position(&mut self, position: f32) {
+        self.position = position.clamp(0.0, 1.0);
+    }
+
+    pub fn next_sample(&mut self) -> f32 {
+        let (phase, _) = self.accumulator.advance();
+        self.wavetable.value_at(phase, self.position)
+    }
+}
+
+/// Implementation of the [Iterator] trait for the [WavetableOscillator]
+impl Iterator for WavetableOscillator {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        Some(self.next_sample())
+    }
+}
+
+/// Implementation of the [Source] trait for the [WavetableOscillator]
+impl Source for WavetableOscillator {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        MONO
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.accumulator.sample_rate() as u32
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}